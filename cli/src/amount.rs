@@ -0,0 +1,55 @@
+//! Conversion between UI amounts, such as `1.5`, and amounts in the smallest units of a mint
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AmountError {
+    #[error("Invalid amount {0}")]
+    Invalid(String),
+
+    #[error("Amount {0} has more than {1} decimals")]
+    TooManyDecimals(String, u8),
+
+    #[error("Amount {0} is too big")]
+    TooBig(String),
+}
+
+/// Converts a decimal UI amount to an amount in the smallest units of a mint with the given decimals.
+/// The conversion is exact, so an amount with more decimals than the mint is rejected.
+pub fn parse_ui_amount(ui_amount: &str, decimals: u8) -> Result<u64, AmountError> {
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.bytes().all(|byte| byte.is_ascii_digit())
+        || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(AmountError::Invalid(ui_amount.to_string()));
+    }
+
+    if fraction.len() > decimals as usize {
+        return Err(AmountError::TooManyDecimals(ui_amount.to_string(), decimals));
+    }
+
+    format!("{}{:0<width$}", whole, fraction, width = decimals as usize)
+        .parse()
+        .map_err(|_| AmountError::TooBig(ui_amount.to_string()))
+}
+
+/// Converts an amount in the smallest units of a mint with the given decimals to a UI amount
+/// without trailing zeros. Sums of amounts wider than `u64` are accepted.
+pub fn format_ui_amount(amount: impl Into<u128>, decimals: u8) -> String {
+    let amount = amount.into();
+
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
@@ -0,0 +1,122 @@
+//! Bulk import of users and their opening balances from a CSV file with the columns `user_id,amount`
+
+use crate::{
+    amount::{parse_ui_amount, AmountError},
+    user_id::{parse_user_id, UserIdError},
+};
+
+use emotion_user_account::state::UserId;
+use solana_sdk::{instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    ops::Range,
+    path::Path,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("Cannot read the CSV file: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Line {0}: expected the columns user_id,amount")]
+    MissingColumn(u64),
+
+    #[error("Line {0}: {1}")]
+    UserId(u64, UserIdError),
+
+    #[error("Line {0}: {1}")]
+    Amount(u64, AmountError),
+
+    #[error("Line {0}: user {1} is already imported on an earlier line")]
+    DuplicateUserId(u64, UserId),
+}
+
+/// A user to create with its opening balance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImportRow {
+    pub user_id: UserId,
+    pub amount: u64,
+}
+
+/// Reads the rows of a CSV file with a header, converting the UI amounts with the decimals of the mint
+pub fn read_import_rows<R: Read>(reader: R, decimals: u8) -> Result<Vec<ImportRow>, ImportError> {
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let mut user_ids = HashSet::new();
+    let mut rows = Vec::new();
+
+    for record in csv_reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+
+        let (user_id, amount) = match (record.get(0), record.get(1)) {
+            (Some(user_id), Some(amount)) => (user_id, amount),
+            _ => return Err(ImportError::MissingColumn(line)),
+        };
+
+        let user_id = parse_user_id(user_id).map_err(|error| ImportError::UserId(line, error))?;
+        let amount = parse_ui_amount(amount, decimals).map_err(|error| ImportError::Amount(line, error))?;
+
+        if !user_ids.insert(user_id) {
+            return Err(ImportError::DuplicateUserId(line, user_id));
+        }
+
+        rows.push(ImportRow { user_id, amount });
+    }
+
+    Ok(rows)
+}
+
+/// Splits groups of instructions into as few transactions as possible, keeping every group
+/// in a single transaction so that a user is never created without its opening balance.
+/// Returns the ranges of the groups of every transaction.
+pub fn pack_transactions(
+    groups: &[Vec<Instruction>],
+    payer: &Pubkey,
+    max_groups_per_transaction: Option<usize>
+) -> Vec<Range<usize>> {
+    let mut transactions = Vec::new();
+    let mut start = 0;
+
+    for end in 1..=groups.len() {
+        let instructions: Vec<Instruction> = groups[start..end].iter().flatten().cloned().collect();
+        let too_many_groups = matches!(max_groups_per_transaction, Some(max) if end - start > max);
+
+        if end - start > 1 && (too_many_groups || transaction_size(&instructions, payer) > PACKET_DATA_SIZE) {
+            transactions.push(start..end - 1);
+            start = end - 1;
+        }
+    }
+
+    if start < groups.len() {
+        transactions.push(start..groups.len());
+    }
+
+    transactions
+}
+
+/// The serialized size of a transaction signed by the payer only
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    // The compact length of the signatures and the signature of the payer
+    1 + 64 + Message::new(instructions, Some(payer)).serialize().len()
+}
+
+/// Returns the number of rows imported by the previous runs, 0 if the progress file does not exist
+pub fn load_progress(path: &Path) -> io::Result<usize> {
+    match fs::read_to_string(path) {
+        Ok(progress) => progress.trim().parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "The progress file is corrupted")),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(error) => Err(error),
+    }
+}
+
+/// Saves the number of imported rows, replacing the progress file at once so that
+/// an interrupted run never leaves it half written
+pub fn save_progress(path: &Path, imported_rows: usize) -> io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, imported_rows.to_string())?;
+    fs::rename(&temporary_path, path)
+}
@@ -0,0 +1,7 @@
+//! Building blocks of the `user-account` tool, kept out of the binary so that they can be tested
+
+pub mod amount;
+pub mod import;
+pub mod reconcile;
+pub mod snapshot;
+pub mod user_id;
@@ -0,0 +1,442 @@
+//! Command-line tool sending the instructions of the emotion-user-account program,
+//! a replacement of the scripts in js/
+
+use clap::{ArgEnum, Parser, Subcommand};
+use emotion_user_account::{
+    instruction::{create_derived, deposit_derived},
+    state::UserId,
+};
+use emotion_user_account_cli::{
+    amount::{format_ui_amount, parse_ui_amount},
+    import::{load_progress, pack_transactions, read_import_rows, save_progress},
+    reconcile::Reconciliation,
+    snapshot::{legacy_user_accounts_warning, snapshot_json, write_snapshot_csv},
+    user_id::{format_user_id, parse_user_id},
+};
+use emotion_user_account_client::UserAccountClient;
+use serde_json::json;
+use solana_cli_config::{Config, CONFIG_FILE};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Signature},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+/// The maximum number of accounts of a `getMultipleAccounts` request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// The exit code of `reconcile` when the vault does not match the balances, distinct from the errors
+const DISCREPANCY_EXIT_CODE: i32 = 2;
+
+#[derive(Parser)]
+#[clap(name = "user-account", version, about = "Sends the instructions of the emotion-user-account program")]
+struct Cli {
+    /// The Solana CLI config file [default: the config of `solana config`]
+    #[clap(long, global = true)]
+    config: Option<String>,
+
+    /// The RPC url, overriding the config
+    #[clap(short = 'u', long, global = true)]
+    url: Option<String>,
+
+    /// The keypair of the operator, overriding the config
+    #[clap(short = 'k', long, global = true)]
+    keypair: Option<String>,
+
+    /// The id of the deployed program
+    #[clap(long, global = true, parse(try_from_str = Pubkey::from_str))]
+    program_id: Option<Pubkey>,
+
+    /// The token mint of the user accounts
+    #[clap(long, global = true, parse(try_from_str = Pubkey::from_str))]
+    mint: Option<Pubkey>,
+
+    /// Prints human-readable text or JSON
+    #[clap(long, global = true, arg_enum, default_value = "display")]
+    output: OutputFormat,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Display,
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum SnapshotFormat {
+    Csv,
+    Json,
+}
+
+/// User ids are numbers, `u64:<number>` or `0x<64 hex digits>`, amounts are UI amounts such as `1.5`
+#[derive(Subcommand)]
+enum Command {
+    /// Creates the vault holding the deposited tokens
+    InitializeVault,
+    /// Moves the tokens of the vault of the previous versions of the program to the vault
+    MigrateVault,
+    /// Grows the account of a user created by a previous version of the program to the current layout
+    MigrateAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+    },
+    /// Creates the account of a user
+    CreateAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+    },
+    /// Deposits tokens to the account of a user
+    DepositAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+        amount: String,
+    },
+    /// Withdraws tokens from the account of a user
+    WithdrawAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+        amount: String,
+        /// The destination token account [default: the associated token account of the operator]
+        #[clap(long, parse(try_from_str = Pubkey::from_str))]
+        destination: Option<Pubkey>,
+    },
+    /// Transfers tokens between the accounts of two users
+    Transfer {
+        #[clap(parse(try_from_str = parse_user_id))]
+        sender_id: UserId,
+        #[clap(parse(try_from_str = parse_user_id))]
+        recipient_id: UserId,
+        amount: String,
+    },
+    /// Blocks tokens on the account of a user
+    BlockAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+        amount: String,
+    },
+    /// Unblocks tokens on the account of a user
+    UnblockAccount {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+        amount: String,
+    },
+    /// Shows the balance of a user
+    Balance {
+        #[clap(parse(try_from_str = parse_user_id))]
+        user_id: UserId,
+    },
+    /// Creates the users of a CSV file with the columns user_id,amount and deposits their opening balances,
+    /// skipping the users that already exist
+    Import {
+        csv_file: PathBuf,
+        /// The file recording the imported rows, so that an interrupted import resumes 
+        /// where it stopped [default: <CSV_FILE>.progress]
+        #[clap(long)]
+        progress_file: Option<PathBuf>,
+        /// Limits the number of users per transaction, to stay within the compute budget
+        #[clap(long)]
+        users_per_transaction: Option<usize>,
+    },
+    /// Writes the balances of all the users of the mint and operator
+    Snapshot {
+        #[clap(long, arg_enum, default_value = "csv")]
+        format: SnapshotFormat,
+        /// The file to write [default: the standard output]
+        #[clap(long)]
+        file: Option<PathBuf>,
+    },
+    /// Compares the vault balance with the sum of the balances of the users of the mint and operator,
+    /// exiting with code 2 on a surplus or a shortfall
+    Reconcile {
+        /// The number of accounts with the largest balances to report
+        #[clap(long, default_value = "10")]
+        top: usize,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let output = cli.output;
+
+    if let Err(error) = run(cli).await {
+        match output {
+            OutputFormat::Display => eprintln!("Error: {}", error),
+            OutputFormat::Json => println!("{}", json!({ "error": error.to_string() })),
+        }
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let config = match &cli.config {
+        Some(config_file) => Config::load(config_file)?,
+        None => CONFIG_FILE.as_ref()
+            .and_then(|config_file| Config::load(config_file).ok())
+            .unwrap_or_default(),
+    };
+
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let commitment = CommitmentConfig::from_str(&config.commitment)?;
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|error| format!("Cannot read the keypair {}: {}", keypair_path, error))?;
+
+    let program_id = cli.program_id.ok_or("The --program-id flag is required")?;
+    let token_mint = cli.mint.ok_or("The --mint flag is required")?;
+
+    let client = UserAccountClient::new(
+        RpcClient::new_with_commitment(url, commitment),
+        program_id,
+        token_mint,
+        Arc::new(signer)
+    );
+
+    let mint_data = client.rpc_client().get_account_data(&token_mint).await?;
+    let decimals = Mint::unpack(&mint_data)?.decimals;
+
+    let output = cli.output;
+    let print_signature = |action: String, signature: Signature| match output {
+        OutputFormat::Display => println!("{}\nSignature: {}", action, signature),
+        OutputFormat::Json => println!("{}", json!({ "signature": signature.to_string() })),
+    };
+
+    match cli.command {
+        Command::InitializeVault => {
+            let signature = client.initialize_vault().await?;
+            print_signature(format!("Created the vault {}", client.vault_address()), signature);
+        }
+        Command::MigrateVault => {
+            let signature = client.migrate_vault().await?;
+            print_signature(
+                format!("Moved the tokens of the previous vault to the vault {}", client.vault_address()),
+                signature);
+        }
+        Command::MigrateAccount { user_id } => {
+            let signature = client.migrate_user_account(user_id).await?;
+            print_signature(
+                format!("Migrated the account {} of user {}", client.user_account_address(user_id), user_id),
+                signature);
+        }
+        Command::CreateAccount { user_id } => {
+            let signature = client.create_user(user_id).await?;
+            print_signature(
+                format!("Created the account {} of user {}", client.user_account_address(user_id), user_id),
+                signature);
+        }
+        Command::DepositAccount { user_id, amount } => {
+            let amount = parse_ui_amount(&amount, decimals)?;
+            let signature = client.deposit(user_id, amount).await?;
+            print_signature(
+                format!("Deposited {} to user {}", format_ui_amount(amount, decimals), user_id),
+                signature);
+        }
+        Command::WithdrawAccount { user_id, amount, destination } => {
+            let amount = parse_ui_amount(&amount, decimals)?;
+            let destination = destination
+                .unwrap_or_else(|| get_associated_token_address(&client.operator(), &token_mint));
+            let signature = client.withdraw(user_id, amount, &destination).await?;
+            print_signature(
+                format!("Withdrew {} from user {} to {}", format_ui_amount(amount, decimals), user_id, destination),
+                signature);
+        }
+        Command::Transfer { sender_id, recipient_id, amount } => {
+            let amount = parse_ui_amount(&amount, decimals)?;
+            let signature = client.transfer(sender_id, recipient_id, amount).await?;
+            print_signature(
+                format!("Transferred {} from user {} to user {}",
+                    format_ui_amount(amount, decimals), sender_id, recipient_id),
+                signature);
+        }
+        Command::BlockAccount { user_id, amount } => {
+            let amount = parse_ui_amount(&amount, decimals)?;
+            let signature = client.block(user_id, amount).await?;
+            print_signature(
+                format!("Blocked {} of user {}", format_ui_amount(amount, decimals), user_id),
+                signature);
+        }
+        Command::UnblockAccount { user_id, amount } => {
+            let amount = parse_ui_amount(&amount, decimals)?;
+            let signature = client.unblock(user_id, amount).await?;
+            print_signature(
+                format!("Unblocked {} of user {}", format_ui_amount(amount, decimals), user_id),
+                signature);
+        }
+        Command::Balance { user_id } => {
+            let balance = client.get_balance(user_id).await?;
+            match output {
+                OutputFormat::Display => println!("Balance: {}\nBlocked: {}",
+                    format_ui_amount(balance.balance, decimals),
+                    format_ui_amount(balance.blocked_amount, decimals)),
+                OutputFormat::Json => println!("{}", json!({
+                    "userId": user_id.to_string(),
+                    "balance": format_ui_amount(balance.balance, decimals),
+                    "blockedAmount": format_ui_amount(balance.blocked_amount, decimals),
+                })),
+            }
+        }
+        Command::Import { csv_file, progress_file, users_per_transaction } => {
+            let progress_file = progress_file.unwrap_or_else(|| csv_file.with_extension("progress"));
+            let rows = read_import_rows(File::open(&csv_file)?, decimals)?;
+            let imported_rows = load_progress(&progress_file)?.min(rows.len());
+
+            let mut created_users = 0;
+            let mut skipped_users = 0;
+            let mut transactions = 0;
+
+            for chunk_start in (imported_rows..rows.len()).step_by(MAX_MULTIPLE_ACCOUNTS) {
+                let chunk_end = (chunk_start + MAX_MULTIPLE_ACCOUNTS).min(rows.len());
+                let chunk = &rows[chunk_start..chunk_end];
+
+                let addresses: Vec<Pubkey> = chunk.iter()
+                    .map(|row| client.user_account_address(row.user_id))
+                    .collect();
+                let existing_accounts = client.rpc_client().get_multiple_accounts(&addresses).await?;
+
+                let mut row_indices = Vec::new();
+                let mut groups = Vec::new();
+                for (index, (row, account)) in chunk.iter().zip(existing_accounts).enumerate() {
+                    if account.is_some() {
+                        skipped_users += 1;
+                        continue;
+                    }
+
+                    let mut group = vec![create_derived(&program_id, row.user_id, &token_mint, &client.operator())?];
+                    if row.amount > 0 {
+                        group.push(deposit_derived(&program_id, row.user_id, row.amount, &token_mint, &client.operator())?);
+                    }
+
+                    row_indices.push(chunk_start + index);
+                    groups.push(group);
+                }
+
+                for range in pack_transactions(&groups, &client.operator(), users_per_transaction) {
+                    let instructions: Vec<_> = groups[range.clone()].iter().flatten().cloned().collect();
+                    client.send(&instructions).await?;
+
+                    created_users += range.len();
+                    transactions += 1;
+                    save_progress(&progress_file, row_indices[range.end - 1] + 1)?;
+                }
+
+                save_progress(&progress_file, chunk_end)?;
+            }
+
+            match output {
+                OutputFormat::Display => println!("Created {} users in {} transactions, skipped {} existing users",
+                    created_users, transactions, skipped_users),
+                OutputFormat::Json => println!("{}", json!({
+                    "createdUsers": created_users,
+                    "skippedUsers": skipped_users,
+                    "transactions": transactions,
+                })),
+            }
+        }
+        Command::Snapshot { format, file } => {
+            let user_accounts = client.get_user_accounts().await?;
+            let shard_slots = client.get_shard_slots().await?;
+            let legacy_user_accounts = client.get_legacy_user_accounts().await?;
+
+            let writer: Box<dyn Write> = match &file {
+                Some(file) => Box::new(File::create(file)?),
+                None => Box::new(io::stdout()),
+            };
+
+            match format {
+                SnapshotFormat::Csv => write_snapshot_csv(writer, &user_accounts, &shard_slots, decimals)?,
+                SnapshotFormat::Json => serde_json::to_writer_pretty(
+                    writer, 
+                    &snapshot_json(&token_mint, &client.operator(), &user_accounts, &shard_slots, &legacy_user_accounts, decimals))?,
+            }
+
+            if let Some(file) = file {
+                eprintln!("Wrote the balances of {} users to {}", user_accounts.len() + shard_slots.len(), file.display());
+            }
+
+            if !legacy_user_accounts.is_empty() {
+                eprintln!("{}", legacy_user_accounts_warning(&legacy_user_accounts));
+            }
+        }
+        Command::Reconcile { top } => {
+            let user_accounts = client.get_user_accounts().await?;
+            let shard_slots = client.get_shard_slots().await?;
+            let escrowed_amounts = client.get_escrowed_amounts().await?;
+            let unclaimed_rewards = client.get_reward_pool().await?
+                .map_or(0, |reward_pool| reward_pool.unclaimed_amount);
+            let legacy_user_accounts = client.get_legacy_user_accounts().await?;
+            let vault_data = client.rpc_client().get_account_data(&client.vault_address()).await?;
+            let vault_balance = TokenAccount::unpack(&vault_data)?.amount;
+
+            let reconciliation = Reconciliation::new(
+                &user_accounts, &shard_slots, &escrowed_amounts, unclaimed_rewards, vault_balance, top);
+            let discrepancy = reconciliation.discrepancy();
+            let (status, discrepancy_amount) = match discrepancy {
+                0 => ("balanced", 0),
+                discrepancy if discrepancy > 0 => ("surplus", discrepancy.unsigned_abs()),
+                discrepancy => ("shortfall", discrepancy.unsigned_abs()),
+            };
+
+            match output {
+                OutputFormat::Display => {
+                    println!("Vault: {}", format_ui_amount(reconciliation.vault_balance, decimals));
+                    println!("Balances of {} users: {}", 
+                        user_accounts.len() + shard_slots.len(), format_ui_amount(reconciliation.total_balance, decimals));
+                    println!("Blocked amounts: {}", format_ui_amount(reconciliation.total_blocked_amount, decimals));
+                    println!("Escrowed in {} accounts: {}", 
+                        escrowed_amounts.len(), format_ui_amount(reconciliation.total_escrowed_amount, decimals));
+                    println!("Unclaimed rewards: {}", format_ui_amount(reconciliation.unclaimed_rewards, decimals));
+                    println!("Outstanding debt: {}", format_ui_amount(reconciliation.total_debt, decimals));
+                    println!("Status: {} {}", status, format_ui_amount(discrepancy_amount, decimals));
+                    println!("Largest balances:");
+                    for (user_account_key, user_id, balance) in &reconciliation.top_accounts {
+                        println!("  {} {} {}", format_user_id(*user_id), user_account_key, format_ui_amount(*balance, decimals));
+                    }
+                }
+                OutputFormat::Json => println!("{}", json!({
+                    "vaultBalance": format_ui_amount(reconciliation.vault_balance, decimals),
+                    "userCount": user_accounts.len() + shard_slots.len(),
+                    "totalBalance": format_ui_amount(reconciliation.total_balance, decimals),
+                    "totalBlockedAmount": format_ui_amount(reconciliation.total_blocked_amount, decimals),
+                    "escrowCount": escrowed_amounts.len(),
+                    "totalEscrowedAmount": format_ui_amount(reconciliation.total_escrowed_amount, decimals),
+                    "unclaimedRewards": format_ui_amount(reconciliation.unclaimed_rewards, decimals),
+                    "totalDebt": format_ui_amount(reconciliation.total_debt, decimals),
+                    "status": status,
+                    "discrepancy": format_ui_amount(discrepancy_amount, decimals),
+                    "topAccounts": reconciliation.top_accounts.iter()
+                        .map(|(user_account_key, user_id, balance)| json!({
+                            "userId": format_user_id(*user_id),
+                            "address": user_account_key.to_string(),
+                            "balance": format_ui_amount(*balance, decimals),
+                        }))
+                        .collect::<Vec<_>>(),
+                })),
+            }
+
+            if !legacy_user_accounts.is_empty() {
+                eprintln!("{}", legacy_user_accounts_warning(&legacy_user_accounts));
+            }
+
+            if discrepancy != 0 {
+                std::process::exit(DISCREPANCY_EXIT_CODE);
+            }
+        }
+    }
+
+    Ok(())
+}
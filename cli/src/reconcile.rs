@@ -0,0 +1,68 @@
+//! Reconciliation of the vault with the balances of the users
+
+use emotion_user_account::state::{ShardSlot, UserAccount, UserId};
+use solana_sdk::pubkey::Pubkey;
+use std::cmp::Reverse;
+
+/// The totals of the ledger compared with the vault
+#[derive(Debug, PartialEq)]
+pub struct Reconciliation {
+    /// The sum of the balances of the user accounts and the shard slots, the tokens the vault owes to the users
+    pub total_balance: u128,
+    /// The sum of the blocked amounts, a part of `total_balance`
+    pub total_blocked_amount: u128,
+    /// The sum of the amounts debited from the users and held by scheduled transfers, escrows and pots
+    pub total_escrowed_amount: u128,
+    /// The rewards distributed and not claimed yet, credited to the users when they claim them
+    pub unclaimed_rewards: u64,
+    /// The sum of the debts, spent by the users on credit before the tokens were deposited to the vault
+    pub total_debt: u128,
+    pub vault_balance: u64,
+    /// The accounts with the largest balances, contributing the most to a shortfall.
+    /// A user stored in a shard is listed with the address of the shard.
+    pub top_accounts: Vec<(Pubkey, UserId, u64)>,
+}
+
+impl Reconciliation {
+    /// Sums the user accounts, the shard slots and the escrowed amounts, and keeps the `top_count`
+    /// accounts with the largest balances
+    pub fn new(
+        user_accounts: &[(Pubkey, UserId, UserAccount)],
+        shard_slots: &[(Pubkey, UserId, ShardSlot)],
+        escrowed_amounts: &[(Pubkey, u64)],
+        unclaimed_rewards: u64,
+        vault_balance: u64,
+        top_count: usize
+    ) -> Self {
+        let mut top_accounts: Vec<(Pubkey, UserId, u64)> = user_accounts.iter()
+            .map(|(user_account_key, user_id, user_account)| (*user_account_key, *user_id, user_account.balance))
+            .chain(shard_slots.iter().map(|(shard_key, user_id, shard_slot)| (*shard_key, *user_id, shard_slot.balance)))
+            .collect();
+        top_accounts.sort_by_key(|(user_account_key, user_id, balance)|
+            (Reverse(*balance), *user_account_key, user_id.number()));
+        top_accounts.truncate(top_count);
+
+        Reconciliation {
+            total_balance: user_accounts.iter().map(|(_, _, user_account)| user_account.balance as u128).sum::<u128>()
+                + shard_slots.iter().map(|(_, _, shard_slot)| shard_slot.balance as u128).sum::<u128>(),
+            total_blocked_amount: user_accounts.iter().map(|(_, _, user_account)| user_account.blocked_amount as u128).sum::<u128>()
+                + shard_slots.iter().map(|(_, _, shard_slot)| shard_slot.blocked_amount as u128).sum::<u128>(),
+            total_escrowed_amount: escrowed_amounts.iter().map(|(_, amount)| *amount as u128).sum(),
+            unclaimed_rewards,
+            total_debt: user_accounts.iter().map(|(_, _, user_account)| user_account.debt as u128).sum(),
+            vault_balance,
+            top_accounts,
+        }
+    }
+
+    /// Returns the tokens the vault owes: the balances, the escrowed amounts and the unclaimed rewards
+    pub fn total_liabilities(&self) -> u128 {
+        self.total_balance + self.total_escrowed_amount + self.unclaimed_rewards as u128
+    }
+
+    /// Returns the surplus of the vault if positive, or its shortfall if negative. The outstanding
+    /// debt is reported on its own and not counted as a shortfall, as it is repaid by the next deposits.
+    pub fn discrepancy(&self) -> i128 {
+        self.vault_balance as i128 + self.total_debt as i128 - self.total_liabilities() as i128
+    }
+}
@@ -0,0 +1,90 @@
+//! Snapshots of the balances of all the users of a token mint and operator
+
+use crate::{amount::format_ui_amount, user_id::format_user_id};
+
+use emotion_user_account::state::{ShardSlot, UserAccount, UserId};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::io::Write;
+
+/// Writes a CSV snapshot with a row per user and UI amounts.
+/// A user stored in a shard is listed with the address of the shard and no debt.
+pub fn write_snapshot_csv<W: Write>(
+    writer: W,
+    user_accounts: &[(Pubkey, UserId, UserAccount)],
+    shard_slots: &[(Pubkey, UserId, ShardSlot)],
+    decimals: u8
+) -> csv::Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["user_id", "address", "balance", "blocked_amount", "debt"])?;
+
+    for (user_account_key, user_id, user_account) in user_accounts {
+        csv_writer.write_record(&[
+            format_user_id(*user_id),
+            user_account_key.to_string(),
+            format_ui_amount(user_account.balance, decimals),
+            format_ui_amount(user_account.blocked_amount, decimals),
+            format_ui_amount(user_account.debt, decimals),
+        ])?;
+    }
+
+    for (shard_key, user_id, shard_slot) in shard_slots {
+        csv_writer.write_record(&[
+            format_user_id(*user_id),
+            shard_key.to_string(),
+            format_ui_amount(shard_slot.balance, decimals),
+            format_ui_amount(shard_slot.blocked_amount, decimals),
+            format_ui_amount(0u64, decimals),
+        ])?;
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Returns a JSON snapshot of the users of the token mint and operator with UI amounts.
+/// A user stored in a shard is listed with the address of the shard and no debt.
+/// The user accounts of the previous versions, whose balances are not included, are listed 
+/// under `legacyAccounts`.
+pub fn snapshot_json(
+    token_mint: &Pubkey,
+    operator: &Pubkey,
+    user_accounts: &[(Pubkey, UserId, UserAccount)],
+    shard_slots: &[(Pubkey, UserId, ShardSlot)],
+    legacy_user_accounts: &[Pubkey],
+    decimals: u8
+) -> Value {
+    let users: Vec<Value> = user_accounts.iter()
+        .map(|(user_account_key, user_id, user_account)| json!({
+            "userId": format_user_id(*user_id),
+            "address": user_account_key.to_string(),
+            "balance": format_ui_amount(user_account.balance, decimals),
+            "blockedAmount": format_ui_amount(user_account.blocked_amount, decimals),
+            "debt": format_ui_amount(user_account.debt, decimals),
+        }))
+        .chain(shard_slots.iter().map(|(shard_key, user_id, shard_slot)| json!({
+            "userId": format_user_id(*user_id),
+            "address": shard_key.to_string(),
+            "balance": format_ui_amount(shard_slot.balance, decimals),
+            "blockedAmount": format_ui_amount(shard_slot.blocked_amount, decimals),
+            "debt": format_ui_amount(0u64, decimals),
+        })))
+        .collect();
+
+    json!({
+        "tokenMint": token_mint.to_string(),
+        "operator": operator.to_string(),
+        "users": users,
+        "legacyAccounts": legacy_user_accounts.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+    })
+}
+
+/// Describes the user accounts of the previous versions that a snapshot cannot include, 
+/// as they do not store their mint and user id until they are migrated
+pub fn legacy_user_accounts_warning(legacy_user_accounts: &[Pubkey]) -> String {
+    let addresses: Vec<String> = legacy_user_accounts.iter().map(Pubkey::to_string).collect();
+
+    format!("{} user accounts of the previous versions of the program are not included, \
+        as they may belong to any mint and operator. Migrate them with `migrate-account <user_id>`: {}", 
+        legacy_user_accounts.len(), addresses.join(", "))
+}
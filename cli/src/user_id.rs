@@ -0,0 +1,43 @@
+//! Parsing of the user ids given on the command line
+
+use emotion_user_account::state::UserId;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Invalid user id {0}, expected a 32-bit number, u64:<64-bit number> or 0x<64 hex digits>")]
+pub struct UserIdError(String);
+
+/// Parses a user id. A plain number is a 32-bit id, as used by the JS scripts, 
+/// `u64:<number>` is a 64-bit id and `0x<64 hex digits>` is an opaque 32-byte id.
+pub fn parse_user_id(user_id: &str) -> Result<UserId, UserIdError> {
+    let error = || UserIdError(user_id.to_string());
+
+    if let Some(number) = user_id.strip_prefix("u64:") {
+        return number.parse().map(UserId::U64).map_err(|_| error());
+    }
+
+    if let Some(hex) = user_id.strip_prefix("0x") {
+        if hex.len() != 64 {
+            return Err(error());
+        }
+
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| error())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| error())?;
+        }
+
+        return Ok(UserId::Bytes(bytes));
+    }
+
+    user_id.parse().map(UserId::U32).map_err(|_| error())
+}
+
+/// Formats a user id so that `parse_user_id` reads it back
+pub fn format_user_id(user_id: UserId) -> String {
+    match user_id {
+        UserId::U32(user_id) => user_id.to_string(),
+        UserId::U64(user_id) => format!("u64:{}", user_id),
+        UserId::Bytes(_) => format!("0x{}", user_id),
+    }
+}
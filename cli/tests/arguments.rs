@@ -0,0 +1,48 @@
+use emotion_user_account::state::UserId;
+use emotion_user_account_cli::{
+    amount::{format_ui_amount, parse_ui_amount, AmountError},
+    user_id::{format_user_id, parse_user_id},
+};
+
+#[test]
+fn test_parse_ui_amount() {
+    assert_eq!(parse_ui_amount("1.5", 9), Ok(1500000000));
+    assert_eq!(parse_ui_amount("1", 9), Ok(1000000000));
+    assert_eq!(parse_ui_amount(".25", 2), Ok(25));
+    assert_eq!(parse_ui_amount("7", 0), Ok(7));
+    assert_eq!(parse_ui_amount("18446744073709551615", 0), Ok(u64::MAX));
+
+    assert_eq!(parse_ui_amount("0.001", 2), Err(AmountError::TooManyDecimals("0.001".to_string(), 2)));
+    assert_eq!(parse_ui_amount("18446744073709551616", 0), Err(AmountError::TooBig("18446744073709551616".to_string())));
+    assert_eq!(parse_ui_amount("-1", 9), Err(AmountError::Invalid("-1".to_string())));
+    assert_eq!(parse_ui_amount("1e9", 9), Err(AmountError::Invalid("1e9".to_string())));
+    assert_eq!(parse_ui_amount(".", 9), Err(AmountError::Invalid(".".to_string())));
+}
+
+#[test]
+fn test_format_ui_amount() {
+    assert_eq!(format_ui_amount(1500000000u64, 9), "1.5");
+    assert_eq!(format_ui_amount(1000000000u64, 9), "1");
+    assert_eq!(format_ui_amount(5u64, 9), "0.000000005");
+    assert_eq!(format_ui_amount(0u64, 9), "0");
+    assert_eq!(format_ui_amount(7u64, 0), "7");
+    assert_eq!(format_ui_amount(u64::MAX as u128 * 10, 1), "18446744073709551615");
+}
+
+#[test]
+fn test_parse_user_id() {
+    assert_eq!(parse_user_id("100"), Ok(UserId::U32(100)));
+    assert_eq!(parse_user_id("u64:5000000000"), Ok(UserId::U64(5000000000)));
+    assert_eq!(parse_user_id(&format!("0x{}", "0a".repeat(32))), Ok(UserId::Bytes([10; 32])));
+
+    assert!(parse_user_id("5000000000").is_err());
+    assert!(parse_user_id("0x0a").is_err());
+    assert!(parse_user_id("user").is_err());
+}
+
+#[test]
+fn test_format_user_id() {
+    for user_id in [UserId::U32(100), UserId::U64(5000000000), UserId::Bytes([10; 32])] {
+        assert_eq!(parse_user_id(&format_user_id(user_id)), Ok(user_id));
+    }
+}
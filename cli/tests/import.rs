@@ -0,0 +1,71 @@
+use emotion_user_account::{
+    instruction::{create_derived, deposit_derived},
+    state::UserId,
+};
+use emotion_user_account_cli::import::{
+    load_progress, pack_transactions, read_import_rows, save_progress, transaction_size, ImportError, ImportRow
+};
+use solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey};
+
+#[test]
+fn test_read_import_rows() {
+    let csv = "user_id,amount\n1, 1.5\nu64:5000000000,0\n";
+    let rows = read_import_rows(csv.as_bytes(), 2).unwrap();
+
+    assert_eq!(rows, vec![
+        ImportRow { user_id: UserId::U32(1), amount: 150 },
+        ImportRow { user_id: UserId::U64(5000000000), amount: 0 },
+    ]);
+
+    let csv = "user_id,amount\n1,1\n2,0.001\n";
+    assert!(matches!(read_import_rows(csv.as_bytes(), 2), Err(ImportError::Amount(3, _))));
+
+    let csv = "user_id,amount\n1,1\n1,2\n";
+    assert!(matches!(read_import_rows(csv.as_bytes(), 2), Err(ImportError::DuplicateUserId(3, UserId::U32(1)))));
+}
+
+#[test]
+fn test_pack_transactions() {
+    let program_id = Pubkey::new_unique();
+    let token_mint = Pubkey::new_unique();
+    let operator = Pubkey::new_unique();
+
+    let groups: Vec<_> = (1..=100u32)
+        .map(|user_id| vec![
+            create_derived(&program_id, UserId::U32(user_id), &token_mint, &operator).unwrap(),
+            deposit_derived(&program_id, UserId::U32(user_id), 10, &token_mint, &operator).unwrap(),
+        ])
+        .collect();
+
+    let transactions = pack_transactions(&groups, &operator, None);
+
+    assert!(transactions.len() > 1 && transactions.len() < groups.len());
+    assert_eq!(transactions.first().unwrap().start, 0);
+    assert_eq!(transactions.last().unwrap().end, groups.len());
+
+    for (range, next_range) in transactions.iter().zip(transactions.iter().skip(1)) {
+        assert_eq!(range.end, next_range.start);
+
+        let instructions: Vec<_> = groups[range.clone()].iter().flatten().cloned().collect();
+        assert!(transaction_size(&instructions, &operator) <= PACKET_DATA_SIZE);
+
+        // The transaction is full
+        let instructions: Vec<_> = groups[range.start..next_range.start + 1].iter().flatten().cloned().collect();
+        assert!(transaction_size(&instructions, &operator) > PACKET_DATA_SIZE);
+    }
+
+    let transactions = pack_transactions(&groups, &operator, Some(3));
+    assert!(transactions.iter().all(|range| range.len() <= 3));
+}
+
+#[test]
+fn test_progress() {
+    let path = std::env::temp_dir().join(format!("user-account-import-{}.progress", std::process::id()));
+
+    assert_eq!(load_progress(&path).unwrap(), 0);
+
+    save_progress(&path, 42).unwrap();
+    assert_eq!(load_progress(&path).unwrap(), 42);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,80 @@
+use emotion_user_account::state::{ShardSlot, UserAccount, UserId};
+use emotion_user_account_cli::reconcile::Reconciliation;
+use solana_sdk::pubkey::Pubkey;
+
+fn user_account(balance: u64, blocked_amount: u64) -> UserAccount {
+    let mut user_account: UserAccount = bytemuck::Zeroable::zeroed();
+    user_account.balance = balance;
+    user_account.blocked_amount = blocked_amount;
+    user_account
+}
+
+#[test]
+fn test_reconciliation() {
+    let user_accounts = vec![
+        (Pubkey::new_from_array([1; 32]), UserId::U32(1), user_account(100, 10)),
+        (Pubkey::new_from_array([2; 32]), UserId::U32(2), user_account(300, 0)),
+        (Pubkey::new_from_array([3; 32]), UserId::U32(3), user_account(200, 50)),
+    ];
+
+    let reconciliation = Reconciliation::new(&user_accounts, &[], &[], 0, 600, 2);
+    assert_eq!(reconciliation.total_balance, 600);
+    assert_eq!(reconciliation.total_blocked_amount, 60);
+    assert_eq!(reconciliation.discrepancy(), 0);
+    assert_eq!(reconciliation.top_accounts, vec![
+        (Pubkey::new_from_array([2; 32]), UserId::U32(2), 300),
+        (Pubkey::new_from_array([3; 32]), UserId::U32(3), 200),
+    ]);
+
+    assert_eq!(Reconciliation::new(&user_accounts, &[], &[], 0, 650, 2).discrepancy(), 50);
+    assert_eq!(Reconciliation::new(&user_accounts, &[], &[], 0, 0, 2).discrepancy(), -600);
+}
+
+#[test]
+fn test_reconciliation_does_not_overflow() {
+    let user_accounts = vec![
+        (Pubkey::new_from_array([1; 32]), UserId::U32(1), user_account(u64::MAX, 0)),
+        (Pubkey::new_from_array([2; 32]), UserId::U32(2), user_account(u64::MAX, 0)),
+    ];
+
+    let reconciliation = Reconciliation::new(&user_accounts, &[], &[], 0, u64::MAX, 10);
+    assert_eq!(reconciliation.total_balance, 2 * u64::MAX as u128);
+    assert_eq!(reconciliation.discrepancy(), -(u64::MAX as i128));
+}
+
+#[test]
+fn test_reconciliation_of_escrows_shards_and_debt() {
+    let mut indebted_user_account = user_account(0, 0);
+    indebted_user_account.debt = 40;
+
+    let user_accounts = vec![
+        (Pubkey::new_from_array([1; 32]), UserId::U32(1), user_account(100, 10)),
+        (Pubkey::new_from_array([2; 32]), UserId::U32(2), indebted_user_account),
+    ];
+    let shard_slots = vec![
+        (Pubkey::new_from_array([3; 32]), UserId::U64(8193), ShardSlot { seed_len: 8, balance: 250, blocked_amount: 5 }),
+    ];
+    // A scheduled transfer and an escrow, debited from the users but still in the vault
+    let escrowed_amounts = vec![
+        (Pubkey::new_from_array([4; 32]), 30),
+        (Pubkey::new_from_array([5; 32]), 70),
+    ];
+
+    // The vault holds the balances, the escrowed amounts and the unclaimed rewards, 
+    // less the debt spent on credit
+    let reconciliation = Reconciliation::new(&user_accounts, &shard_slots, &escrowed_amounts, 20, 430, 1);
+    assert_eq!(reconciliation.total_balance, 350);
+    assert_eq!(reconciliation.total_blocked_amount, 15);
+    assert_eq!(reconciliation.total_escrowed_amount, 100);
+    assert_eq!(reconciliation.unclaimed_rewards, 20);
+    assert_eq!(reconciliation.total_debt, 40);
+    assert_eq!(reconciliation.total_liabilities(), 470);
+    assert_eq!(reconciliation.discrepancy(), 0);
+    assert_eq!(reconciliation.top_accounts, vec![
+        (Pubkey::new_from_array([3; 32]), UserId::U64(8193), 250),
+    ]);
+
+    // Without the escrowed amounts, the tokens they hold would look like a surplus
+    assert_eq!(Reconciliation::new(&user_accounts, &shard_slots, &[], 20, 430, 1).discrepancy(), 100);
+    assert_eq!(Reconciliation::new(&user_accounts, &shard_slots, &escrowed_amounts, 20, 400, 1).discrepancy(), -30);
+}
@@ -0,0 +1,77 @@
+use emotion_user_account::state::{ShardSlot, UserAccount, UserId};
+use emotion_user_account_cli::snapshot::{legacy_user_accounts_warning, snapshot_json, write_snapshot_csv};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+fn user_accounts() -> Vec<(Pubkey, UserId, UserAccount)> {
+    let mut user_account: UserAccount = bytemuck::Zeroable::zeroed();
+    user_account.balance = 1500;
+    user_account.blocked_amount = 500;
+    user_account.user_id = UserId::U32(1).into();
+
+    let mut opaque_user_account = user_account;
+    opaque_user_account.balance = 0;
+    opaque_user_account.blocked_amount = 0;
+    opaque_user_account.debt = 25;
+    opaque_user_account.user_id = UserId::Bytes([255; 32]).into();
+
+    vec![
+        (Pubkey::new_from_array([1; 32]), UserId::U32(1), user_account),
+        (Pubkey::new_from_array([2; 32]), UserId::Bytes([255; 32]), opaque_user_account),
+    ]
+}
+
+fn shard_slots() -> Vec<(Pubkey, UserId, ShardSlot)> {
+    vec![
+        (Pubkey::new_from_array([3; 32]), UserId::U32(7), ShardSlot { seed_len: 4, balance: 250, blocked_amount: 0 }),
+    ]
+}
+
+#[test]
+fn test_snapshot_csv() {
+    let mut csv = Vec::new();
+    write_snapshot_csv(&mut csv, &user_accounts(), &shard_slots(), 2).unwrap();
+
+    assert_eq!(String::from_utf8(csv).unwrap(), format!(
+        "user_id,address,balance,blocked_amount,debt\n1,{},15,5,0\n0x{},{},0,0,0.25\n7,{},2.5,0,0\n",
+        Pubkey::new_from_array([1; 32]), "ff".repeat(32), Pubkey::new_from_array([2; 32]), Pubkey::new_from_array([3; 32])));
+}
+
+#[test]
+fn test_snapshot_json() {
+    let token_mint = Pubkey::new_unique();
+    let operator = Pubkey::new_unique();
+
+    let legacy_user_account = Pubkey::new_unique();
+
+    let snapshot = snapshot_json(&token_mint, &operator, &user_accounts(), &shard_slots(), &[legacy_user_account], 2);
+
+    assert_eq!(snapshot["tokenMint"], json!(token_mint.to_string()));
+    assert_eq!(snapshot["operator"], json!(operator.to_string()));
+    assert_eq!(snapshot["users"][0], json!({
+        "userId": "1",
+        "address": Pubkey::new_from_array([1; 32]).to_string(),
+        "balance": "15",
+        "blockedAmount": "5",
+        "debt": "0",
+    }));
+    assert_eq!(snapshot["users"][1]["debt"], json!("0.25"));
+    assert_eq!(snapshot["users"][2], json!({
+        "userId": "7",
+        "address": Pubkey::new_from_array([3; 32]).to_string(),
+        "balance": "2.5",
+        "blockedAmount": "0",
+        "debt": "0",
+    }));
+    assert_eq!(snapshot["legacyAccounts"], json!([legacy_user_account.to_string()]));
+}
+
+#[test]
+fn test_legacy_user_accounts_warning() {
+    let legacy_user_accounts = [Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([2; 32])];
+
+    let warning = legacy_user_accounts_warning(&legacy_user_accounts);
+
+    assert!(warning.starts_with("2 user accounts of the previous versions of the program are not included"));
+    assert!(warning.ends_with(&format!("{}, {}", legacy_user_accounts[0], legacy_user_accounts[1])));
+}
@@ -0,0 +1,55 @@
+use emotion_user_account::error::UserAccountError;
+use num_traits::FromPrimitive;
+use solana_client::client_error::ClientError as RpcClientError;
+use solana_sdk::{
+    instruction::InstructionError, 
+    program_error::ProgramError, 
+    pubkey::Pubkey, 
+    transaction::TransactionError
+};
+use thiserror::Error;
+
+/// Errors that may be returned by the client
+#[derive(Debug, Error)]
+pub enum ClientError {
+    // The program rejected the transaction with one of its own errors
+    #[error("Program error: {0}")]
+    Program(UserAccountError),
+
+    // The transaction failed for another reason, such as a missing signature or a too big amount
+    #[error("Transaction error: {0}")]
+    Transaction(TransactionError),
+
+    // The RPC node could not be reached or returned an unexpected response
+    #[error("RPC error: {0}")]
+    Rpc(Box<RpcClientError>),
+
+    // The instruction could not be built
+    #[error("Instruction error: {0}")]
+    Instruction(#[from] ProgramError),
+
+    // The user account does not exist
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    // The account exists but does not hold a user account
+    #[error("Account {0} is not a user account")]
+    InvalidAccountData(Pubkey),
+}
+
+impl From<RpcClientError> for ClientError {
+    fn from(error: RpcClientError) -> Self {
+        match error.kind.get_transaction_error() {
+            Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) => {
+                match UserAccountError::from_u32(code) {
+                    Some(program_error) => ClientError::Program(program_error),
+                    None => ClientError::Transaction(
+                        TransactionError::InstructionError(index, InstructionError::Custom(code))
+                    ),
+                }
+            }
+            Some(transaction_error) => ClientError::Transaction(transaction_error),
+            None => ClientError::Rpc(Box::new(error)),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Async client of the emotion-user-account program, signing every transaction with the operator
+//! and waiting for its confirmation.
+
+mod error;
+mod rpc;
+
+pub use error::ClientError;
+pub use rpc::Rpc;
+
+use borsh::BorshDeserialize;
+use emotion_user_account::{
+    instruction::{
+        block_derived, create_derived, deposit_derived, get_legacy_vault_authority_address, get_user_account_address, 
+        get_vault_authority_address, initialize_vault, migrate_user_account, migrate_vault, transfer_derived, unblock_derived, with_reward_pool,
+        withdraw_derived
+    },
+    state::{
+        Escrow, Pot, RewardPool, ScheduledTransfer, ShardHeader, ShardSlot, TaggedAccount, UserAccount, 
+        UserId, REWARD_POOL_SEED, SHARD_SLOT_COUNT
+    },
+};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::sync::Arc;
+
+/// The balance of a user
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Balance {
+    pub balance: u64,
+    pub blocked_amount: u64,
+}
+
+/// Sends the instructions of a token mint and operator to the program through an RPC node, 
+/// or through any other `Rpc` connection to a cluster
+pub struct UserAccountClient<R: Rpc = RpcClient> {
+    rpc_client: R,
+    program_id: Pubkey,
+    token_mint: Pubkey,
+    /// The operator, also paying the fees. Any signer can be plugged in, such as a keypair 
+    /// or a remote wallet.
+    signer: Arc<dyn Signer + Send + Sync>,
+}
+
+impl<R: Rpc> UserAccountClient<R> {
+    pub fn new(
+        rpc_client: R,
+        program_id: Pubkey,
+        token_mint: Pubkey,
+        signer: Arc<dyn Signer + Send + Sync>,
+    ) -> Self {
+        Self { rpc_client, program_id, token_mint, signer }
+    }
+
+    pub fn rpc_client(&self) -> &R {
+        &self.rpc_client
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    pub fn token_mint(&self) -> Pubkey {
+        self.token_mint
+    }
+
+    pub fn operator(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    /// Returns the address of the account of the given user
+    pub fn user_account_address(&self, user_id: UserId) -> Pubkey {
+        get_user_account_address(&self.program_id, user_id, &self.token_mint, &self.operator()).0
+    }
+
+    /// Returns the address of the token account holding the deposited tokens
+    pub fn vault_address(&self) -> Pubkey {
+        let (vault_authority_key, _) = 
+            get_vault_authority_address(&self.program_id, &self.token_mint, &self.operator());
+
+        get_associated_token_address(&vault_authority_key, &self.token_mint)
+    }
+
+    pub async fn initialize_vault(&self) -> Result<Signature, ClientError> {
+        let (vault_authority_key, vault_authority_bump_seed) = 
+            get_vault_authority_address(&self.program_id, &self.token_mint, &self.operator());
+
+        self.send(&[initialize_vault(
+            &self.program_id,
+            vault_authority_bump_seed,
+            &vault_authority_key,
+            &self.vault_address(),
+            &self.token_mint,
+            &self.operator()
+        )?]).await
+    }
+
+    /// Moves the tokens of the vault of the previous versions of the program to the vault
+    pub async fn migrate_vault(&self) -> Result<Signature, ClientError> {
+        let (legacy_vault_authority_key, legacy_vault_authority_bump_seed) = 
+            get_legacy_vault_authority_address(&self.program_id, &self.token_mint, &self.operator());
+        let (vault_authority_key, vault_authority_bump_seed) = 
+            get_vault_authority_address(&self.program_id, &self.token_mint, &self.operator());
+
+        self.send(&[migrate_vault(
+            &self.program_id,
+            legacy_vault_authority_bump_seed,
+            &legacy_vault_authority_key,
+            &get_associated_token_address(&legacy_vault_authority_key, &self.token_mint),
+            vault_authority_bump_seed,
+            &vault_authority_key,
+            &self.vault_address(),
+            &self.token_mint,
+            &self.operator()
+        )?]).await
+    }
+
+    /// Grows the account of a user created by a previous version of the program to the current layout
+    pub async fn migrate_user_account(&self, user_id: UserId) -> Result<Signature, ClientError> {
+        let (user_account_key, user_account_bump_seed) = 
+            get_user_account_address(&self.program_id, user_id, &self.token_mint, &self.operator());
+
+        self.send(&[migrate_user_account(
+            &self.program_id,
+            user_id,
+            user_account_bump_seed,
+            &user_account_key,
+            &self.token_mint,
+            &self.operator()
+        )?]).await
+    }
+
+    pub async fn create_user(&self, user_id: UserId) -> Result<Signature, ClientError> {
+        self.send(&[create_derived(&self.program_id, user_id, &self.token_mint, &self.operator())?]).await
+    }
+
+    pub async fn deposit(&self, user_id: UserId, amount: u64) -> Result<Signature, ClientError> {
+        let instruction = deposit_derived(&self.program_id, user_id, amount, &self.token_mint, &self.operator())?;
+        self.send(&[self.with_reward_pools(instruction, &[user_id]).await?]).await
+    }
+
+    /// Withdraws `amount` from the vault to the `destination` token account
+    pub async fn withdraw(
+        &self, 
+        user_id: UserId, 
+        amount: u64, 
+        destination: &Pubkey
+    ) -> Result<Signature, ClientError> {
+        let instruction = 
+            withdraw_derived(&self.program_id, user_id, amount, &self.token_mint, destination, &self.operator())?;
+        self.send(&[self.with_reward_pools(instruction, &[user_id]).await?]).await
+    }
+
+    pub async fn transfer(
+        &self, 
+        sender_user_id: UserId, 
+        recipient_user_id: UserId, 
+        amount: u64
+    ) -> Result<Signature, ClientError> {
+        let instruction = 
+            transfer_derived(&self.program_id, &self.token_mint, sender_user_id, recipient_user_id, amount, &self.operator())?;
+        self.send(&[self.with_reward_pools(instruction, &[sender_user_id, recipient_user_id]).await?]).await
+    }
+
+    pub async fn block(&self, user_id: UserId, amount: u64) -> Result<Signature, ClientError> {
+        self.send(&[block_derived(&self.program_id, user_id, amount, &self.token_mint, &self.operator())?]).await
+    }
+
+    pub async fn unblock(&self, user_id: UserId, amount: u64) -> Result<Signature, ClientError> {
+        self.send(&[unblock_derived(&self.program_id, user_id, amount, &self.token_mint, &self.operator())?]).await
+    }
+
+    /// Reads the balance of the given user at the commitment of the RPC client
+    pub async fn get_balance(&self, user_id: UserId) -> Result<Balance, ClientError> {
+        let user_account = self.get_user_account(user_id).await?;
+
+        Ok(Balance {
+            balance: user_account.balance,
+            blocked_amount: user_account.blocked_amount,
+        })
+    }
+
+    /// Reads the whole account of the given user at the commitment of the RPC client
+    pub async fn get_user_account(&self, user_id: UserId) -> Result<UserAccount, ClientError> {
+        let user_account_key = self.user_account_address(user_id);
+
+        let account = self.rpc_client
+            .get_account(&user_account_key)
+            .await?
+            .ok_or(ClientError::AccountNotFound(user_account_key))?;
+
+        UserAccount::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(user_account_key))
+    }
+
+    /// Reads the reward pool of the token mint and operator, or `None` if it was not created
+    pub async fn get_reward_pool(&self) -> Result<Option<RewardPool>, ClientError> {
+        let (reward_pool_key, _) = Pubkey::find_program_address(
+            &[REWARD_POOL_SEED, &self.token_mint.to_bytes(), &self.operator().to_bytes()],
+            &self.program_id);
+
+        match self.rpc_client.get_account(&reward_pool_key).await? {
+            Some(account) => RewardPool::try_from_slice(&account.data)
+                .map(Some)
+                .map_err(|_| ClientError::InvalidAccountData(reward_pool_key)),
+            None => Ok(None),
+        }
+    }
+
+    /// Appends the reward pools of the given users to an instruction changing their balances, 
+    /// as the program requires them once the users are enrolled in rewards
+    async fn with_reward_pools(&self, mut instruction: Instruction, user_ids: &[UserId]) -> Result<Instruction, ClientError> {
+        for user_id in user_ids {
+            // The program rejects the instruction if the account does not exist
+            let reward_pool = match self.get_user_account(*user_id).await {
+                Ok(user_account) => user_account.reward.reward_pool,
+                Err(ClientError::AccountNotFound(_)) => continue,
+                Err(error) => return Err(error),
+            };
+
+            if reward_pool != Pubkey::default() && instruction.accounts.iter().all(|account| account.pubkey != reward_pool) {
+                instruction = with_reward_pool(instruction, &reward_pool);
+            }
+        }
+
+        Ok(instruction)
+    }
+
+    /// Signs the instructions in a single transaction and waits until it is confirmed
+    pub async fn send(&self, instructions: &[Instruction]) -> Result<Signature, ClientError> {
+        let operator = self.operator();
+        let signer: &dyn Signer = &*self.signer;
+        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&operator),
+            &[signer],
+            recent_blockhash
+        );
+
+        self.rpc_client.send_and_confirm_transaction(&transaction).await
+    }
+
+    /// Reads the accounts of all the users of the token mint and operator, sorted by address. The accounts created before their mint and user id 
+    /// were stored in them cannot be found.
+    pub async fn get_user_accounts(&self) -> Result<Vec<(Pubkey, UserId, UserAccount)>, ClientError> {
+        let accounts = self.get_program_accounts(vec![
+            RpcFilterType::DataSize(UserAccount::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp {
+                offset: UserAccount::TOKEN_MINT_OFFSET,
+                bytes: MemcmpEncodedBytes::Base58(self.token_mint.to_string()),
+                encoding: None,
+            }),
+        ]).await?;
+
+        let mut user_accounts = Vec::with_capacity(accounts.len());
+        for (user_account_key, account) in accounts {
+            let user_account = UserAccount::try_from_slice(&account.data)
+                .map_err(|_| ClientError::InvalidAccountData(user_account_key))?;
+
+            // The accounts of the other operators of the mint have the same layout
+            match user_account.user_id.user_id() {
+                Some(user_id) if self.user_account_address(user_id) == user_account_key => 
+                    user_accounts.push((user_account_key, user_id, user_account)),
+                _ => continue,
+            }
+        }
+
+        user_accounts.sort_by_key(|(user_account_key, _, _)| *user_account_key);
+
+        Ok(user_accounts)
+    }
+
+    /// Reads the slots of the users stored in the ledger shards of the token mint and operator, 
+    /// sorted by shard address and user id
+    pub async fn get_shard_slots(&self) -> Result<Vec<(Pubkey, UserId, ShardSlot)>, ClientError> {
+        let accounts = self.get_program_accounts(vec![
+            RpcFilterType::DataSize(ShardHeader::ACCOUNT_LEN as u64),
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Base58(self.token_mint.to_string()),
+                encoding: None,
+            }),
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 32,
+                bytes: MemcmpEncodedBytes::Base58(self.operator().to_string()),
+                encoding: None,
+            }),
+        ]).await?;
+
+        let mut shard_slots = Vec::new();
+        for (shard_key, account) in accounts {
+            let shard_header = ShardHeader::try_from_slice(&account.data[..ShardHeader::LEN])
+                .map_err(|_| ClientError::InvalidAccountData(shard_key))?;
+
+            for slot_index in 0..SHARD_SLOT_COUNT {
+                let user_number = shard_header.shard_index * SHARD_SLOT_COUNT + slot_index;
+                let shard_slot = ShardSlot::try_from_slice(&account.data[ShardHeader::slot_range(user_number)])
+                    .map_err(|_| ClientError::InvalidAccountData(shard_key))?;
+
+                if let Some(user_id) = shard_slot.user_id(user_number) {
+                    shard_slots.push((shard_key, user_id, shard_slot));
+                }
+            }
+        }
+
+        shard_slots.sort_by_key(|(shard_key, user_id, _)| (*shard_key, user_id.number()));
+
+        Ok(shard_slots)
+    }
+
+    /// Returns the amounts debited from the users of the token mint and operator and held by 
+    /// the scheduled transfers, escrows and pots, sorted by address
+    pub async fn get_escrowed_amounts(&self) -> Result<Vec<(Pubkey, u64)>, ClientError> {
+        let mut escrowed_amounts = Vec::new();
+
+        // The accounts of the other mints and operators are told apart by the users they debited
+        for (address, scheduled_transfer) in self.get_tagged_accounts::<ScheduledTransfer>(None).await? {
+            if scheduled_transfer.operator == self.operator() 
+                && scheduled_transfer.sender_account == self.user_account_address(scheduled_transfer.sender_user_id) {
+                escrowed_amounts.push((address, scheduled_transfer.amount));
+            }
+        }
+
+        for (address, escrow) in self.get_tagged_accounts::<Escrow>(None).await? {
+            if escrow.operator == self.operator() 
+                && escrow.party_a_account == self.user_account_address(escrow.party_a_user_id) {
+                escrowed_amounts.push((address, escrow.party_a_funded + escrow.party_b_funded));
+            }
+        }
+
+        for (address, pot) in self.get_tagged_accounts::<Pot>(Some(Pot::LEN)).await? {
+            if pot.token_mint == self.token_mint && pot.operator == self.operator() {
+                escrowed_amounts.push((address, pot.amount));
+            }
+        }
+
+        escrowed_amounts.sort_by_key(|(address, _)| *address);
+
+        Ok(escrowed_amounts)
+    }
+
+    /// Returns the addresses of the user accounts created by the previous versions of the program 
+    /// and not migrated yet, sorted. Those accounts store neither their mint nor their user id, 
+    /// so they are found by their size only and may belong to any mint and operator.
+    pub async fn get_legacy_user_accounts(&self) -> Result<Vec<Pubkey>, ClientError> {
+        let mut legacy_user_account_keys = Vec::new();
+
+        for legacy_len in UserAccount::LEGACY_LENS {
+            let accounts = self.rpc_client
+                .get_program_accounts(&self.program_id, vec![RpcFilterType::DataSize(legacy_len as u64)])
+                .await?;
+            legacy_user_account_keys.extend(accounts.into_iter().map(|(user_account_key, _)| user_account_key));
+        }
+
+        legacy_user_account_keys.sort();
+
+        Ok(legacy_user_account_keys)
+    }
+
+    /// Reads the program accounts of the given type and, when given, size, of all the mints and operators. 
+    /// The first byte of the other accounts may match the type, such as the balance of a user account 
+    /// or the delegate of an allowance, so the accounts that do not decode are skipped.
+    async fn get_tagged_accounts<T: TaggedAccount>(&self, data_size: Option<usize>) -> Result<Vec<(Pubkey, T)>, ClientError> {
+        let mut filters = vec![
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Bytes(vec![T::ACCOUNT_TYPE as u8]),
+                encoding: None,
+            }),
+        ];
+        filters.extend(data_size.map(|data_size| RpcFilterType::DataSize(data_size as u64)));
+
+        let accounts = self.get_program_accounts(filters).await?;
+
+        // `TaggedAccount::load` would log the rejected accounts
+        Ok(accounts.into_iter()
+            .filter_map(|(address, account)| Some((address, T::try_from_slice(&account.data).ok()?)))
+            .collect())
+    }
+
+    /// Reads the program accounts matching all the given filters
+    async fn get_program_accounts(&self, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        self.rpc_client.get_program_accounts(&self.program_id, filters).await
+    }
+}
@@ -0,0 +1,69 @@
+use crate::ClientError;
+
+use async_trait::async_trait;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Transaction,
+};
+
+/// The requests the client sends to the cluster. Implemented by the RPC client, and by any other
+/// connection to a cluster, such as the banks client of `solana-program-test` in tests.
+#[async_trait]
+pub trait Rpc: Send + Sync {
+    /// Returns the account at the given address, or `None` if it does not exist
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+
+    /// Returns the accounts of the program matching all the given filters
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError>;
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError>;
+
+    /// Sends the signed transaction and waits until it is confirmed
+    async fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature, ClientError>;
+}
+
+#[async_trait]
+impl Rpc for RpcClient {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self.get_account_with_commitment(address, self.commitment()).await?.value)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        Ok(self.get_program_accounts_with_config(program_id, config).await?)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        Ok(RpcClient::get_latest_blockhash(self).await?)
+    }
+
+    async fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        Ok(RpcClient::send_and_confirm_transaction(self, transaction).await?)
+    }
+}
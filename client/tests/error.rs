@@ -0,0 +1,37 @@
+use emotion_user_account::error::UserAccountError;
+use emotion_user_account_client::ClientError;
+use solana_client::client_error::ClientError as RpcClientError;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+#[test]
+fn test_program_error() {
+    let error = ClientError::from(RpcClientError::from(TransactionError::InstructionError(
+        0, 
+        InstructionError::Custom(UserAccountError::OutstandingDebt as u32)
+    )));
+
+    assert!(matches!(error, ClientError::Program(UserAccountError::OutstandingDebt)));
+}
+
+#[test]
+fn test_transaction_error() {
+    let error = ClientError::from(RpcClientError::from(TransactionError::InstructionError(
+        0, 
+        InstructionError::InvalidArgument
+    )));
+
+    assert!(matches!(
+        error, 
+        ClientError::Transaction(TransactionError::InstructionError(0, InstructionError::InvalidArgument))
+    ));
+
+    let error = ClientError::from(RpcClientError::from(TransactionError::InstructionError(
+        1, 
+        InstructionError::Custom(1000)
+    )));
+
+    assert!(matches!(
+        error, 
+        ClientError::Transaction(TransactionError::InstructionError(1, InstructionError::Custom(1000)))
+    ));
+}
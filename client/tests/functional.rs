@@ -0,0 +1,387 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use borsh::BorshDeserialize;
+use emotion_user_account::{
+    instruction::{
+        approve, create_escrow, create_reward_pool, deposit, enroll_rewards, fund_escrow, get_allowance_address,
+        get_shard_address, get_user_account_address, initialize_shard,
+    },
+    processor::process_instruction,
+    state::{Delegate, RewardPool, ShardHeader, UserId, ESCROW_SEED, REWARD_POOL_SEED},
+};
+use emotion_user_account_client::{Balance, ClientError, Rpc, UserAccountClient};
+use solana_client::{client_error::ClientError as RpcClientError, rpc_filter::RpcFilterType};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::InstructionError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signature, Signer},
+    system_instruction::create_account,
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use spl_token::{
+    instruction::{initialize_mint, mint_to},
+    state::{Account as TokenAccount, Mint},
+};
+
+/// Sends the requests of the client to the banks of `solana-program-test` instead of an RPC node.
+/// The banks cannot list the accounts of a program, so only the accounts passed to `scan` are listed.
+struct BanksRpc {
+    banks_client: BanksClient,
+    scanned_accounts: Mutex<Vec<Pubkey>>,
+}
+
+impl BanksRpc {
+    fn new(banks_client: BanksClient) -> Self {
+        BanksRpc { banks_client, scanned_accounts: Mutex::new(Vec::new()) }
+    }
+
+    /// Lists the account at the given address in `get_program_accounts` if the program owns it
+    fn scan(&self, address: Pubkey) {
+        self.scanned_accounts.lock().unwrap().push(address);
+    }
+}
+
+#[async_trait]
+impl Rpc for BanksRpc {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        self.banks_client.clone().get_account(*address).await.map_err(banks_error)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        let scanned_accounts = self.scanned_accounts.lock().unwrap().clone();
+        let mut accounts = Vec::new();
+
+        for address in scanned_accounts {
+            let account = match self.get_account(&address).await? {
+                Some(account) if account.owner == *program_id => account,
+                _ => continue,
+            };
+
+            let matches = filters.iter().all(|filter| match filter {
+                RpcFilterType::DataSize(data_size) => account.data.len() as u64 == *data_size,
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&account.data),
+                filter => panic!("Unsupported filter {:?}", filter),
+            });
+
+            if matches {
+                accounts.push((address, account));
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        self.banks_client.clone().get_latest_blockhash().await.map_err(banks_error)
+    }
+
+    async fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        self.banks_client.clone().process_transaction(transaction.clone()).await.map_err(banks_error)?;
+        Ok(transaction.signatures[0])
+    }
+}
+
+/// Maps the errors of the banks client like the client maps the errors of the RPC client
+fn banks_error(error: BanksClientError) -> ClientError {
+    match error {
+        BanksClientError::TransactionError(error) | BanksClientError::SimulationError { err: error, .. } =>
+            RpcClientError::from(error).into(),
+        error => RpcClientError::from(io::Error::from(error)).into(),
+    }
+}
+
+/// Starts a cluster with the program and a token mint, and returns a client whose operator
+/// is the mint authority
+async fn start_client() -> UserAccountClient<BanksRpc> {
+    start_client_with_legacy_users(&[]).await
+}
+
+/// Same as `start_client`, with the accounts of the given users and balances in the layout 
+/// of the first version of the program
+async fn start_client_with_legacy_users(legacy_users: &[(UserId, u64)]) -> UserAccountClient<BanksRpc> {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "emotion_user_account",
+        program_id,
+        processor!(process_instruction)
+    );
+
+    let token_mint = Keypair::new();
+    let payer = Keypair::new();
+    program_test.add_account(payer.pubkey(), Account::new(1_000_000_000_000, 0, &system_program::id()));
+
+    for (user_id, balance) in legacy_users {
+        let (user_account_key, _) = get_user_account_address(&program_id, *user_id, &token_mint.pubkey(), &payer.pubkey());
+
+        // The balance followed by the blocked amount
+        let mut data = balance.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+
+        program_test.add_account(user_account_key, Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: program_id,
+            ..Account::default()
+        });
+    }
+
+    let (mut banks_client, _, recent_blockhash) = program_test.start().await;
+
+    let rent = banks_client.get_rent().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_account(
+                &payer.pubkey(),
+                &token_mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            initialize_mint(&spl_token::id(), &token_mint.pubkey(), &payer.pubkey(), None, 9).unwrap()
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &token_mint],
+        recent_blockhash
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    UserAccountClient::new(BanksRpc::new(banks_client), program_id, token_mint.pubkey(), Arc::new(payer))
+}
+
+/// Initializes the vault and mints the given amount to it
+async fn fund_vault(client: &UserAccountClient<BanksRpc>, amount: u64) {
+    client.initialize_vault().await.unwrap();
+    client.send(&[
+        mint_to(&spl_token::id(), &client.token_mint(), &client.vault_address(), &client.operator(), &[], amount)
+            .unwrap()
+    ]).await.unwrap();
+}
+
+async fn get_token_balance(client: &UserAccountClient<BanksRpc>, token_account_key: &Pubkey) -> u64 {
+    let token_account = client.rpc_client().get_account(token_account_key).await.unwrap().unwrap();
+    TokenAccount::unpack(&token_account.data).unwrap().amount
+}
+
+#[tokio::test]
+async fn test_client() {
+    let sender_user_id = UserId::U32(1);
+    let recipient_user_id = UserId::U64(1 << 40);
+
+    let client = start_client().await;
+    fund_vault(&client, 1000).await;
+
+    client.create_user(sender_user_id).await.unwrap();
+    client.create_user(recipient_user_id).await.unwrap();
+    client.deposit(sender_user_id, 1000).await.unwrap();
+    client.transfer(sender_user_id, recipient_user_id, 300).await.unwrap();
+    client.block(recipient_user_id, 100).await.unwrap();
+
+    assert_eq!(
+        client.get_balance(recipient_user_id).await.unwrap(),
+        Balance { balance: 300, blocked_amount: 100 }
+    );
+
+    client.unblock(recipient_user_id, 100).await.unwrap();
+
+    let destination = get_associated_token_address(&client.operator(), &client.token_mint());
+    client.send(&[
+        create_associated_token_account(&client.operator(), &client.operator(), &client.token_mint(), &spl_token::id())
+    ]).await.unwrap();
+    client.withdraw(sender_user_id, 200, &destination).await.unwrap();
+
+    assert_eq!(client.get_balance(sender_user_id).await.unwrap(), Balance { balance: 500, blocked_amount: 0 });
+    assert_eq!(client.get_balance(recipient_user_id).await.unwrap(), Balance { balance: 300, blocked_amount: 0 });
+    assert_eq!(get_token_balance(&client, &destination).await, 200);
+    assert_eq!(get_token_balance(&client, &client.vault_address()).await, 800);
+}
+
+#[tokio::test]
+async fn test_client_errors() {
+    let user_id = UserId::U32(1);
+
+    let client = start_client().await;
+
+    assert!(matches!(
+        client.get_balance(user_id).await,
+        Err(ClientError::AccountNotFound(address)) if address == client.user_account_address(user_id)
+    ));
+
+    client.create_user(user_id).await.unwrap();
+    client.deposit(user_id, 100).await.unwrap();
+
+    assert!(matches!(
+        client.block(user_id, 101).await,
+        Err(ClientError::Transaction(TransactionError::InstructionError(0, InstructionError::InvalidArgument)))
+    ));
+}
+
+#[tokio::test]
+async fn test_client_migrate_user_account() {
+    let user_id = UserId::U32(7);
+
+    let client = start_client_with_legacy_users(&[(user_id, 300)]).await;
+
+    assert!(matches!(
+        client.get_balance(user_id).await,
+        Err(ClientError::InvalidAccountData(address)) if address == client.user_account_address(user_id)
+    ));
+
+    client.migrate_user_account(user_id).await.unwrap();
+
+    assert_eq!(client.get_balance(user_id).await.unwrap(), Balance { balance: 300, blocked_amount: 0 });
+
+    let user_account = client.get_user_account(user_id).await.unwrap();
+    assert_eq!(user_account.token_mint, client.token_mint());
+    assert_eq!(user_account.user_id.user_id(), Some(user_id));
+}
+
+#[tokio::test]
+async fn test_client_enrolled_users() {
+    let sender_user_id = UserId::U32(1);
+    let recipient_user_id = UserId::U32(2);
+
+    let client = start_client().await;
+    fund_vault(&client, 1000).await;
+
+    client.create_user(sender_user_id).await.unwrap();
+    client.create_user(recipient_user_id).await.unwrap();
+    client.deposit(sender_user_id, 500).await.unwrap();
+
+    let (reward_pool_key, reward_pool_bump) = Pubkey::find_program_address(
+        &[REWARD_POOL_SEED, &client.token_mint().to_bytes(), &client.operator().to_bytes()],
+        &client.program_id());
+    let (sender_account_key, sender_bump) = 
+        get_user_account_address(&client.program_id(), sender_user_id, &client.token_mint(), &client.operator());
+
+    client.send(&[
+        create_reward_pool(&client.program_id(), reward_pool_bump, &reward_pool_key, &client.token_mint(), &client.operator())
+            .unwrap(),
+        enroll_rewards(&client.program_id(), sender_user_id, sender_bump, &sender_account_key, &reward_pool_key, &client.operator())
+            .unwrap()
+    ]).await.unwrap();
+
+    // The program requires the reward pool of the enrolled sender, which the client appends
+    let destination = get_associated_token_address(&client.operator(), &client.token_mint());
+    client.send(&[
+        create_associated_token_account(&client.operator(), &client.operator(), &client.token_mint(), &spl_token::id())
+    ]).await.unwrap();
+    client.deposit(sender_user_id, 100).await.unwrap();
+    client.transfer(sender_user_id, recipient_user_id, 50).await.unwrap();
+    client.transfer(recipient_user_id, sender_user_id, 20).await.unwrap();
+    client.withdraw(sender_user_id, 70, &destination).await.unwrap();
+
+    assert_eq!(client.get_balance(sender_user_id).await.unwrap(), Balance { balance: 500, blocked_amount: 0 });
+
+    let reward_pool_account = client.rpc_client().get_account(&reward_pool_key).await.unwrap().unwrap();
+    let reward_pool = RewardPool::try_from_slice(&reward_pool_account.data).unwrap();
+    assert_eq!(reward_pool.total_shares, 500);
+}
+
+#[tokio::test]
+async fn test_client_escrowed_amounts() {
+    let party_a_user_id = UserId::U32(1);
+    let party_b_user_id = UserId::U32(2);
+    let escrow_id = 0u32;
+
+    let client = start_client().await;
+    let program_id = client.program_id();
+    let token_mint = client.token_mint();
+    let operator = client.operator();
+
+    // The first byte of the balance of 257 and of a key delegate is the tag of the scheduled transfers
+    client.create_user(party_a_user_id).await.unwrap();
+    client.create_user(party_b_user_id).await.unwrap();
+    client.deposit(party_a_user_id, 257).await.unwrap();
+
+    let (party_a_account_key, party_a_bump) = get_user_account_address(&program_id, party_a_user_id, &token_mint, &operator);
+    let (party_b_account_key, party_b_bump) = get_user_account_address(&program_id, party_b_user_id, &token_mint, &operator);
+    let delegate = Delegate::Key(Pubkey::new_unique());
+    let (allowance_account_key, allowance_bump) = get_allowance_address(&program_id, &party_a_account_key, &delegate);
+    let (escrow_account_key, escrow_bump) = Pubkey::find_program_address(
+        &[ESCROW_SEED, &token_mint.to_bytes(), &operator.to_bytes(), &escrow_id.to_le_bytes()],
+        &program_id);
+
+    client.send(&[
+        approve(
+            &program_id, party_a_user_id, party_a_bump, allowance_bump, delegate, 100,
+            &party_a_account_key, &allowance_account_key, &token_mint, &operator
+        ).unwrap(),
+        create_escrow(
+            &program_id, &token_mint, escrow_id, escrow_bump, &escrow_account_key,
+            party_a_user_id, party_a_bump, &party_a_account_key, 50,
+            party_b_user_id, party_b_bump, &party_b_account_key, 70,
+            &operator, i64::MAX, &operator
+        ).unwrap(),
+        fund_escrow(&program_id, &escrow_account_key, &party_a_account_key, &operator).unwrap(),
+    ]).await.unwrap();
+
+    for address in [party_a_account_key, party_b_account_key, allowance_account_key, escrow_account_key] {
+        client.rpc_client().scan(address);
+    }
+
+    assert_eq!(client.get_escrowed_amounts().await.unwrap(), vec![(escrow_account_key, 50)]);
+    assert_eq!(client.get_user_accounts().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_client_snapshot_accounts() {
+    let user_id = UserId::U32(1);
+    let legacy_user_id = UserId::U32(7);
+    let shard_user_id = UserId::U64(5);
+
+    let client = start_client_with_legacy_users(&[(legacy_user_id, 300)]).await;
+    let program_id = client.program_id();
+    let token_mint = client.token_mint();
+    let operator = client.operator();
+
+    client.create_user(user_id).await.unwrap();
+    client.deposit(user_id, 100).await.unwrap();
+
+    let shard_index = ShardHeader::shard_index(shard_user_id.number().unwrap());
+    let (shard_key, shard_bump) = get_shard_address(&program_id, &token_mint, &operator, shard_index);
+    client.send(&vec![
+        initialize_shard(&program_id, shard_index, shard_bump, &shard_key, &token_mint, &operator).unwrap();
+        ShardHeader::ALLOCATION_COUNT
+    ]).await.unwrap();
+    client.send(&[
+        deposit(&program_id, shard_user_id, 0, 200, &shard_key, &token_mint, &operator).unwrap()
+    ]).await.unwrap();
+
+    let user_account_key = client.user_account_address(user_id);
+    let legacy_user_account_key = client.user_account_address(legacy_user_id);
+    for address in [user_account_key, legacy_user_account_key, shard_key] {
+        client.rpc_client().scan(address);
+    }
+
+    let user_accounts = client.get_user_accounts().await.unwrap();
+    assert_eq!(user_accounts.len(), 1);
+    assert_eq!((user_accounts[0].0, user_accounts[0].1, user_accounts[0].2.balance), (user_account_key, user_id, 100));
+    let shard_slots = client.get_shard_slots().await.unwrap();
+    assert_eq!(shard_slots.len(), 1);
+    assert_eq!((shard_slots[0].0, shard_slots[0].1, shard_slots[0].2.balance), (shard_key, shard_user_id, 200));
+    assert_eq!(client.get_legacy_user_accounts().await.unwrap(), vec![legacy_user_account_key]);
+
+    client.migrate_user_account(legacy_user_id).await.unwrap();
+
+    let mut user_accounts: Vec<(Pubkey, UserId, u64)> = client.get_user_accounts().await.unwrap().iter()
+        .map(|(user_account_key, user_id, user_account)| (*user_account_key, *user_id, user_account.balance))
+        .collect();
+    user_accounts.sort_by_key(|(_, user_id, _)| user_id.number());
+    assert_eq!(user_accounts, vec![(user_account_key, user_id, 100), (legacy_user_account_key, legacy_user_id, 300)]);
+    assert!(client.get_legacy_user_accounts().await.unwrap().is_empty());
+}
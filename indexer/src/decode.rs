@@ -0,0 +1,341 @@
+//! Decoding of the instructions of the program into ledger entries.
+//!
+//! The balance of a user is kept in two ledger accounts, its available and blocked amounts, 
+//! and its debt in a third one. The tokens deposited and withdrawn are counted in an external 
+//! account of the mint and operator. The tokens held by the scheduled transfers, escrows, pots 
+//! and reward pools are kept in a ledger account of each of them until they are paid to the users.
+//! Only the instructions moving tokens between those accounts produce entries, the other ones 
+//! are recorded in the history only.
+//!
+//! The amounts computed by the program, such as the stake locked in an escrow or the rewards 
+//! claimed by a user, are read from the logs of the instruction.
+
+use borsh::BorshDeserialize;
+use emotion_user_account::{
+    instruction::{get_user_account_address, UserAccountInstruction},
+    state::{PotRake, PotWinner, UserId, RAKE_BASIS_POINTS_DENOMINATOR},
+};
+use solana_sdk::pubkey::Pubkey;
+
+/// A user seen in an instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedUser {
+    /// The derived address of the user account, identifying the user even when it is stored in a shard
+    pub address: Pubkey,
+    pub user_id: UserId,
+    pub token_mint: Pubkey,
+    pub operator: Pubkey,
+}
+
+/// A move of `amount` from the `debit` ledger account to the `credit` one
+#[derive(Clone, Debug, PartialEq)]
+pub struct LedgerEntry {
+    pub debit: String,
+    pub credit: String,
+    pub amount: u64,
+}
+
+/// An instruction of the program with its effect on the ledger
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedInstruction {
+    /// The name of the instruction, such as `Transfer`
+    pub name: String,
+    /// The instruction with its arguments
+    pub details: String,
+    pub users: Vec<IndexedUser>,
+    pub entries: Vec<LedgerEntry>,
+}
+
+/// The ledger account of the available balance of a user
+pub fn available_account(user: &IndexedUser) -> String {
+    user.address.to_string()
+}
+
+/// The ledger account of the blocked amount of a user
+pub fn blocked_account(user: &IndexedUser) -> String {
+    format!("{}:blocked", user.address)
+}
+
+/// The ledger account of the debt of a user, whose balance is the opposite of the amount the user owes
+pub fn debt_account(user: &IndexedUser) -> String {
+    format!("{}:debt", user.address)
+}
+
+/// The ledger account of the tokens deposited to and withdrawn from the users of a mint and operator
+pub fn external_account(token_mint: &Pubkey, operator: &Pubkey) -> String {
+    format!("external:{}:{}", token_mint, operator)
+}
+
+/// The ledger account of the tokens taken from the sender until the scheduled transfer is executed or cancelled
+pub fn scheduled_transfer_account(scheduled_transfer: &Pubkey) -> String {
+    format!("scheduled:{}", scheduled_transfer)
+}
+
+/// The ledger account of the stakes locked in an escrow
+pub fn escrow_account(escrow: &Pubkey) -> String {
+    format!("escrow:{}", escrow)
+}
+
+/// The ledger account of the stakes of a pot
+pub fn pot_account(pot: &Pubkey) -> String {
+    format!("pot:{}", pot)
+}
+
+/// The ledger account of the rewards distributed and not claimed yet
+pub fn reward_pool_account(reward_pool: &Pubkey) -> String {
+    format!("rewards:{}", reward_pool)
+}
+
+/// The ledger account of the tokens distributed as rewards. The reward pool does not name its mint 
+/// in the instructions, so the rewards are not counted in the external account of the mint and operator.
+pub fn external_rewards_account(reward_pool: &Pubkey) -> String {
+    format!("external:rewards:{}", reward_pool)
+}
+
+/// Splits the log messages of a transaction by instruction, in the order of the instructions of the transaction.
+/// An instruction starts with an invocation logged outside of any other one.
+pub fn instruction_logs(log_messages: &[String]) -> Vec<&[String]> {
+    let mut starts = vec![];
+    let mut depth = 0usize;
+
+    for (index, log_message) in log_messages.iter().enumerate() {
+        // The messages logged by the programs start with `Program log:` or `Program data:`
+        let mut words = log_message.split(' ');
+        match (words.next(), words.next(), words.next()) {
+            (Some("Program"), Some(program), Some("invoke")) if !program.ends_with(':') => {
+                if depth == 0 {
+                    starts.push(index);
+                }
+                depth += 1;
+            }
+            (Some("Program"), Some(program), Some("success" | "failed:")) if !program.ends_with(':') => {
+                depth = depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    starts.iter()
+        .enumerate()
+        .map(|(index, start)| &log_messages[*start..starts.get(index + 1).copied().unwrap_or(log_messages.len())])
+        .collect()
+}
+
+/// Decodes an instruction of the program with the keys of its accounts and its logs.
+/// Returns `None` when the data is not an instruction of the program, its accounts are missing, 
+/// or the amounts computed by the program are missing from the logs.
+pub fn decode_instruction(
+    program_id: &Pubkey,
+    data: &[u8],
+    accounts: &[Pubkey],
+    logs: &[String]
+) -> Option<DecodedInstruction> {
+    let instruction = UserAccountInstruction::try_from_slice(data).ok()?;
+
+    let details = format!("{:?}", instruction);
+    let name = details
+        .split(|character: char| !character.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let user = |user_id: UserId, token_mint_index: usize, operator_index: usize| {
+        let token_mint = *accounts.get(token_mint_index)?;
+        let operator = *accounts.get(operator_index)?;
+        let (address, _) = get_user_account_address(program_id, user_id, &token_mint, &operator);
+
+        Some(IndexedUser { address, user_id, token_mint, operator })
+    };
+
+    // The ledger account of the available balance of a user given by the address of its account
+    let address = |index: usize| accounts.get(index).map(|address| address.to_string());
+    let account = |index: usize| accounts.get(index);
+
+    let (users, entries) = match instruction {
+        UserAccountInstruction::Create { user_id, .. } => {
+            (vec![user(user_id, 2, 3)?], vec![])
+        }
+        UserAccountInstruction::Deposit { user_id, amount, .. } => {
+            let user = user(user_id, 1, 2)?;
+            let external = external_account(&user.token_mint, &user.operator);
+            let mut entries = vec![];
+
+            // The debt is repaid before anything is added to the balance
+            let repaid_amount = logged_amounts(logs, "Repaid ").first().copied().unwrap_or(0);
+            if repaid_amount > 0 {
+                entries.push(LedgerEntry { debit: external.clone(), credit: debt_account(&user), amount: repaid_amount });
+            }
+
+            entries.push(LedgerEntry {
+                debit: external,
+                credit: available_account(&user),
+                amount: amount.checked_sub(repaid_amount)?,
+            });
+            (vec![user], entries)
+        }
+        UserAccountInstruction::Withdraw { user_id, amount, .. } => {
+            let user = user(user_id, 2, 6)?;
+            let entry = LedgerEntry {
+                debit: available_account(&user),
+                credit: external_account(&user.token_mint, &user.operator),
+                amount,
+            };
+            (vec![user], vec![entry])
+        }
+        UserAccountInstruction::Transfer { sender_user_id, recipient_user_id, amount, .. } => {
+            let sender = user(sender_user_id, 0, 3)?;
+            let recipient = user(recipient_user_id, 0, 3)?;
+            let mut entries = vec![];
+
+            // The part of the amount not covered by the available balance is spent on credit
+            let borrowed_amount = logged_amounts(logs, "Borrowed ").first().copied().unwrap_or(0);
+            if borrowed_amount > 0 {
+                entries.push(LedgerEntry { debit: debt_account(&sender), credit: available_account(&sender), amount: borrowed_amount });
+            }
+
+            entries.push(LedgerEntry { debit: available_account(&sender), credit: available_account(&recipient), amount });
+            (vec![sender, recipient], entries)
+        }
+        UserAccountInstruction::TransferFrom { sender_user_id, recipient_user_id, amount, .. } => {
+            let sender = user(sender_user_id, 0, 4)?;
+            let recipient = user(recipient_user_id, 0, 4)?;
+            let entry = LedgerEntry { debit: available_account(&sender), credit: available_account(&recipient), amount };
+            (vec![sender, recipient], vec![entry])
+        }
+        UserAccountInstruction::Block { user_id, amount, .. } => {
+            let user = user(user_id, 1, 2)?;
+            let entry = LedgerEntry { debit: available_account(&user), credit: blocked_account(&user), amount };
+            (vec![user], vec![entry])
+        }
+        UserAccountInstruction::Unblock { user_id, amount, .. } => {
+            let user = user(user_id, 1, 2)?;
+            let entry = LedgerEntry { debit: blocked_account(&user), credit: available_account(&user), amount };
+            (vec![user], vec![entry])
+        }
+        UserAccountInstruction::ScheduleTransfer { sender_user_id, recipient_user_id, amount, .. } => {
+            let sender = user(sender_user_id, 1, 5)?;
+            let recipient = user(recipient_user_id, 1, 5)?;
+            let entry = LedgerEntry {
+                debit: available_account(&sender),
+                credit: scheduled_transfer_account(account(4)?),
+                amount,
+            };
+            (vec![sender, recipient], vec![entry])
+        }
+        UserAccountInstruction::ExecuteScheduledTransfer => {
+            let entry = LedgerEntry {
+                debit: scheduled_transfer_account(account(0)?),
+                credit: address(1)?,
+                amount: *logged_amounts(logs, "Executed the scheduled transfer of ").first()?,
+            };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::CancelScheduledTransfer => {
+            let entry = LedgerEntry {
+                debit: scheduled_transfer_account(account(0)?),
+                credit: address(1)?,
+                amount: *logged_amounts(logs, "Cancelled the scheduled transfer of ").first()?,
+            };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::GrantVesting { user_id, vesting, .. } => {
+            let user = user(user_id, 1, 2)?;
+            let entry = LedgerEntry {
+                debit: external_account(&user.token_mint, &user.operator),
+                credit: available_account(&user),
+                amount: vesting.total_amount,
+            };
+            (vec![user], vec![entry])
+        }
+        UserAccountInstruction::ChargeSubscription { amount } => {
+            let entry = LedgerEntry { debit: address(1)?, credit: address(2)?, amount };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::FundEscrow => {
+            let entry = LedgerEntry {
+                debit: address(1)?,
+                credit: escrow_account(account(0)?),
+                amount: *logged_amounts(logs, " locked ").first()?,
+            };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::ResolveEscrow { .. } | UserAccountInstruction::RefundEscrow => {
+            // Party A receives the first logged amount and party B the second one
+            let amounts = logged_amounts(logs, " received ");
+            let entries = vec![
+                LedgerEntry { debit: escrow_account(account(0)?), credit: address(1)?, amount: *amounts.first()? },
+                LedgerEntry { debit: escrow_account(account(0)?), credit: address(2)?, amount: *amounts.get(1)? },
+            ];
+            (vec![], entries)
+        }
+        UserAccountInstruction::JoinPot { amount, .. } => {
+            let entry = LedgerEntry { debit: address(0)?, credit: pot_account(account(1)?), amount };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::SettlePot { winners, rake } => {
+            let pot_amount = *logged_amounts(logs, "Settled the pot of ").first()?;
+
+            // The house account, when there is a rake, and the winner accounts follow the operator account
+            let entries = pot_payouts(pot_amount, &winners, rake.as_ref())
+                .into_iter()
+                .enumerate()
+                .map(|(index, amount)| Some(LedgerEntry { debit: pot_account(account(0)?), credit: address(2 + index)?, amount }))
+                .collect::<Option<Vec<_>>>()?;
+            (vec![], entries)
+        }
+        UserAccountInstruction::DistributeRewards { amount } => {
+            let entry = LedgerEntry {
+                debit: external_rewards_account(account(0)?),
+                credit: reward_pool_account(account(0)?),
+                amount,
+            };
+            (vec![], vec![entry])
+        }
+        UserAccountInstruction::Claim { .. } => {
+            let entry = LedgerEntry {
+                debit: reward_pool_account(account(1)?),
+                credit: address(0)?,
+                amount: *logged_amounts(logs, " claimed ").first()?,
+            };
+            (vec![], vec![entry])
+        }
+        _ => (vec![], vec![]),
+    };
+
+    Some(DecodedInstruction { name, details, users, entries })
+}
+
+/// Returns the amounts logged by the program right after `marker`, in the order of the logs
+fn logged_amounts(logs: &[String], marker: &str) -> Vec<u64> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program log: "))
+        .flat_map(|message| message.match_indices(marker).map(move |(index, _)| &message[index + marker.len()..]))
+        .filter_map(|amount| amount.split(|character: char| !character.is_ascii_digit()).next()?.parse().ok())
+        .collect()
+}
+
+/// Returns the amounts paid by the settlement of a pot, the rake first if any and then the prizes 
+/// of the winners, split like the program does
+fn pot_payouts(pot_amount: u64, winners: &[PotWinner], rake: Option<&PotRake>) -> Vec<u64> {
+    let mut payouts = vec![];
+    let mut prize_amount = pot_amount;
+
+    if let Some(rake) = rake {
+        let rake_amount = (u128::from(pot_amount) * u128::from(rake.basis_points)
+            / u128::from(RAKE_BASIS_POINTS_DENOMINATOR)) as u64;
+        payouts.push(rake_amount);
+        prize_amount -= rake_amount;
+    }
+
+    // Shares are rounded down, the remainder goes to the first winner
+    let total_weight: u128 = winners.iter().map(|winner| u128::from(winner.weight)).sum();
+    let shares: Vec<u64> = winners.iter()
+        .map(|winner| (u128::from(prize_amount) * u128::from(winner.weight) / total_weight.max(1)) as u64)
+        .collect();
+    let remainder = prize_amount - shares.iter().sum::<u64>();
+
+    payouts.extend(shares.iter()
+        .enumerate()
+        .map(|(index, share)| if index == 0 { share + remainder } else { *share }));
+    payouts
+}
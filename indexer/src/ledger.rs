@@ -0,0 +1,190 @@
+//! The SQLite ledger. Every ledger entry records the same amount as a debit of one ledger account
+//! and a credit of another one, so the balance of a ledger account is its credits minus its debits.
+
+use crate::decode::DecodedInstruction;
+
+use emotion_user_account::state::UserId;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{convert::TryFrom, path::Path};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS transactions (
+        signature TEXT PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        -- The error of a failed transaction, recorded without its instructions
+        error TEXT
+    );
+    CREATE INDEX IF NOT EXISTS transactions_slot ON transactions (slot);
+
+    CREATE TABLE IF NOT EXISTS instructions (
+        signature TEXT NOT NULL REFERENCES transactions (signature) ON DELETE CASCADE,
+        instruction_index INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        details TEXT NOT NULL,
+        PRIMARY KEY (signature, instruction_index)
+    );
+
+    CREATE TABLE IF NOT EXISTS instruction_users (
+        signature TEXT NOT NULL,
+        instruction_index INTEGER NOT NULL,
+        address TEXT NOT NULL,
+        FOREIGN KEY (signature, instruction_index) 
+            REFERENCES instructions (signature, instruction_index) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS instruction_users_address ON instruction_users (address);
+
+    CREATE TABLE IF NOT EXISTS users (
+        address TEXT PRIMARY KEY,
+        user_id_kind TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        token_mint TEXT NOT NULL,
+        operator TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS entries (
+        signature TEXT NOT NULL,
+        instruction_index INTEGER NOT NULL,
+        debit TEXT NOT NULL,
+        credit TEXT NOT NULL,
+        amount INTEGER NOT NULL CHECK (amount >= 0),
+        FOREIGN KEY (signature, instruction_index) 
+            REFERENCES instructions (signature, instruction_index) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS entries_debit ON entries (debit);
+    CREATE INDEX IF NOT EXISTS entries_credit ON entries (credit);
+";
+
+pub struct Ledger {
+    connection: Connection,
+}
+
+impl Ledger {
+    /// Opens the ledger stored in the given file, creating it if needed
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a ledger kept in memory, for tests
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Ledger { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Removes the transactions from the given slot onwards, so that they are indexed again
+    pub fn reset_from_slot(&self, slot: u64) -> rusqlite::Result<usize> {
+        self.connection.execute("DELETE FROM transactions WHERE slot >= ?1", params![sql_integer(slot)?])
+    }
+
+    pub fn contains_transaction(&self, signature: &str) -> rusqlite::Result<bool> {
+        self.connection
+            .query_row("SELECT 1 FROM transactions WHERE signature = ?1", params![signature], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+    }
+
+    /// Returns the slot of the latest indexed transaction
+    pub fn last_slot(&self) -> rusqlite::Result<Option<u64>> {
+        self.connection
+            .query_row("SELECT MAX(slot) FROM transactions", [], |row| row.get::<_, Option<i64>>(0))
+            .map(|slot| slot.map(|slot| slot as u64))
+    }
+
+    /// Records a failed transaction, so that it is not fetched again
+    pub fn insert_failed_transaction(
+        &mut self,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+        error: &str
+    ) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT INTO transactions (signature, slot, block_time, error) VALUES (?1, ?2, ?3, ?4)",
+            params![signature, sql_integer(slot)?, block_time, error])?;
+
+        Ok(())
+    }
+
+    /// Records a transaction with its instructions of the program, given with their index 
+    /// in the transaction, all at once
+    pub fn insert_transaction(
+        &mut self,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+        instructions: &[(usize, DecodedInstruction)]
+    ) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![signature, sql_integer(slot)?, block_time])?;
+
+        for (instruction_index, instruction) in instructions {
+            let instruction_index = sql_integer(*instruction_index as u64)?;
+
+            transaction.execute(
+                "INSERT INTO instructions (signature, instruction_index, name, details) VALUES (?1, ?2, ?3, ?4)",
+                params![signature, instruction_index, instruction.name, instruction.details])?;
+
+            for user in &instruction.users {
+                transaction.execute(
+                    "INSERT OR IGNORE INTO users (address, user_id_kind, user_id, token_mint, operator) 
+                        VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        user.address.to_string(), 
+                        user_id_kind(user.user_id),
+                        user.user_id.to_string(), 
+                        user.token_mint.to_string(), 
+                        user.operator.to_string()
+                    ])?;
+
+                transaction.execute(
+                    "INSERT INTO instruction_users (signature, instruction_index, address) VALUES (?1, ?2, ?3)",
+                    params![signature, instruction_index, user.address.to_string()])?;
+            }
+
+            for entry in &instruction.entries {
+                transaction.execute(
+                    "INSERT INTO entries (signature, instruction_index, debit, credit, amount) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![signature, instruction_index, entry.debit, entry.credit, sql_integer(entry.amount)?])?;
+            }
+        }
+
+        transaction.commit()
+    }
+
+    /// Returns the balance of a ledger account, its credits minus its debits
+    pub fn balance(&self, account: &str) -> rusqlite::Result<i128> {
+        let (credits, debits) = self.connection.query_row(
+            "SELECT 
+                (SELECT COALESCE(SUM(amount), 0) FROM entries WHERE credit = ?1),
+                (SELECT COALESCE(SUM(amount), 0) FROM entries WHERE debit = ?1)",
+            params![account],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?;
+
+        Ok(credits as i128 - debits as i128)
+    }
+}
+
+fn user_id_kind(user_id: UserId) -> &'static str {
+    match user_id {
+        UserId::U32(_) => "u32",
+        UserId::U64(_) => "u64",
+        UserId::Bytes(_) => "bytes",
+    }
+}
+
+/// Converts a number to a SQLite integer, which is signed
+fn sql_integer(value: u64) -> rusqlite::Result<i64> {
+    i64::try_from(value).map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))
+}
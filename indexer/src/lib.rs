@@ -0,0 +1,4 @@
+//! Indexer of the transactions of the emotion-user-account program into a SQLite ledger
+
+pub mod decode;
+pub mod ledger;
@@ -0,0 +1,169 @@
+//! Follows the transactions of the emotion-user-account program and writes them to a SQLite ledger
+
+use clap::Parser;
+use emotion_user_account_indexer::{decode::{decode_instruction, instruction_logs}, ledger::Ledger};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{error::Error, path::PathBuf, str::FromStr, time::Duration};
+
+/// The maximum number of signatures of a `getSignaturesForAddress` request
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+#[derive(Parser)]
+#[clap(name = "user-account-indexer", version, about = "Writes the transactions of the emotion-user-account program to a SQLite ledger")]
+struct Args {
+    /// The RPC url
+    #[clap(short = 'u', long, default_value = "http://localhost:8899")]
+    url: String,
+
+    /// The id of the deployed program
+    #[clap(long, parse(try_from_str = Pubkey::from_str))]
+    program_id: Pubkey,
+
+    /// The SQLite database of the ledger, created if needed
+    #[clap(long, default_value = "ledger.sqlite")]
+    database: PathBuf,
+
+    /// Removes the transactions indexed from this slot onwards and indexes them again. 
+    /// On an empty ledger, the older transactions are not indexed.
+    #[clap(long)]
+    from_slot: Option<u64>,
+
+    /// The number of seconds between two polls of the new transactions
+    #[clap(long, default_value = "10")]
+    poll_interval: u64,
+
+    /// Exits once the ledger has caught up instead of following the new transactions
+    #[clap(long)]
+    once: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(error) = run(Args::parse()).await {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    // Only finalized transactions are indexed, so that the ledger never has to be rolled back
+    let rpc_client = RpcClient::new_with_commitment(args.url, CommitmentConfig::finalized());
+    let mut ledger = Ledger::open(&args.database)?;
+
+    if let Some(from_slot) = args.from_slot {
+        let removed_transactions = ledger.reset_from_slot(from_slot)?;
+        println!("Removed {} transactions from slot {}", removed_transactions, from_slot);
+    }
+
+    loop {
+        let statuses = new_signatures(&rpc_client, &args.program_id, &ledger, args.from_slot).await?;
+
+        // The signatures are returned from the newest, the ledger is written from the oldest
+        for status in statuses.into_iter().rev() {
+            index_transaction(&rpc_client, &args.program_id, &mut ledger, &status).await?;
+        }
+
+        if args.once {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs(args.poll_interval)).await;
+    }
+}
+
+/// Returns the signatures of the program newer than the latest indexed one and than `from_slot`, 
+/// from the newest
+async fn new_signatures(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    ledger: &Ledger,
+    from_slot: Option<u64>
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, Box<dyn Error>> {
+    let mut statuses = Vec::new();
+    let mut before = None;
+
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(rpc_client.commitment()),
+            }).await?;
+        let page_len = page.len();
+
+        for status in page {
+            if matches!(from_slot, Some(from_slot) if status.slot < from_slot)
+                || ledger.contains_transaction(&status.signature)? {
+                return Ok(statuses);
+            }
+
+            before = Some(Signature::from_str(&status.signature)?);
+            statuses.push(status);
+        }
+
+        if page_len < SIGNATURES_PAGE_SIZE {
+            return Ok(statuses);
+        }
+    }
+}
+
+/// Fetches a transaction and records it with its instructions of the program
+async fn index_transaction(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    ledger: &mut Ledger,
+    status: &RpcConfirmedTransactionStatusWithSignature
+) -> Result<(), Box<dyn Error>> {
+    if let Some(error) = &status.err {
+        ledger.insert_failed_transaction(&status.signature, status.slot, status.block_time, &error.to_string())?;
+        return Ok(());
+    }
+
+    let confirmed_transaction = rpc_client.get_transaction_with_config(
+        &Signature::from_str(&status.signature)?,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(rpc_client.commitment()),
+            ..RpcTransactionConfig::default()
+        }).await?;
+
+    let transaction = confirmed_transaction.transaction.transaction.decode()
+        .ok_or_else(|| format!("Cannot decode the transaction {}", status.signature))?;
+    let account_keys = transaction.message.static_account_keys();
+
+    // The program logs the amounts it computes, such as the rewards claimed by a user
+    let log_messages: Vec<String> = confirmed_transaction.transaction.meta
+        .and_then(|meta| Option::from(meta.log_messages))
+        .unwrap_or_default();
+    let logs = instruction_logs(&log_messages);
+
+    let instructions: Vec<_> = transaction.message.instructions().iter()
+        .enumerate()
+        .filter(|(_, instruction)| account_keys.get(instruction.program_id_index as usize) == Some(program_id))
+        .filter_map(|(instruction_index, instruction)| {
+            // The accounts loaded from address lookup tables are not resolved
+            let accounts: Option<Vec<Pubkey>> = instruction.accounts.iter()
+                .map(|account_index| account_keys.get(*account_index as usize).copied())
+                .collect();
+
+            let logs = logs.get(instruction_index).copied().unwrap_or_default();
+
+            decode_instruction(program_id, &instruction.data, &accounts?, logs)
+                .map(|instruction| (instruction_index, instruction))
+        })
+        .collect();
+
+    ledger.insert_transaction(&status.signature, confirmed_transaction.slot, confirmed_transaction.block_time, &instructions)?;
+    println!("Indexed {} instructions of {} at slot {}", instructions.len(), status.signature, confirmed_transaction.slot);
+
+    Ok(())
+}
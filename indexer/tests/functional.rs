@@ -0,0 +1,313 @@
+use borsh::BorshDeserialize;
+use emotion_user_account::{
+    instruction::{
+        block_derived, charge_subscription, claim, create_derived, create_escrow, create_pot, create_reward_pool,
+        deposit_derived, distribute_rewards, enroll_rewards, execute_scheduled_transfer, cancel_scheduled_transfer,
+        fund_escrow, get_user_account_address, grant_vesting, join_pot, resolve_escrow, schedule_transfer,
+        set_credit_limit, settle_pot, subscribe, transfer_derived,
+    },
+    processor::process_instruction,
+    state::{
+        Escrow, Pot, PotRake, PotWinner, RewardPool, ScheduledTransfer, TaggedAccount, UserAccount, UserId, Vesting,
+        ESCROW_SEED, POT_SEED, REWARD_POOL_SEED, SCHEDULED_TRANSFER_SEED, SUBSCRIPTION_SEED,
+    },
+};
+use emotion_user_account_indexer::{
+    decode::{
+        available_account, blocked_account, debt_account, decode_instruction, escrow_account, external_account,
+        external_rewards_account, instruction_logs, pot_account, reward_pool_account, scheduled_transfer_account,
+        IndexedUser,
+    },
+    ledger::Ledger,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Runs the program and indexes every transaction it processes
+struct IndexedProgramTest {
+    context: ProgramTestContext,
+    program_id: Pubkey,
+    token_mint: Pubkey,
+    ledger: Ledger,
+    slot: u64,
+}
+
+impl IndexedProgramTest {
+    async fn start_new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "emotion_user_account",
+            program_id,
+            processor!(process_instruction)
+        );
+
+        IndexedProgramTest {
+            context: program_test.start_with_context().await,
+            program_id,
+            token_mint: Pubkey::new_unique(),
+            ledger: Ledger::open_in_memory().unwrap(),
+            slot: 0,
+        }
+    }
+
+    fn operator(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    fn user(&self, user_id: UserId) -> (IndexedUser, u8) {
+        let (address, bump_seed) = get_user_account_address(&self.program_id, user_id, &self.token_mint, &self.operator());
+        (IndexedUser { address, user_id, token_mint: self.token_mint, operator: self.operator() }, bump_seed)
+    }
+
+    fn find_address(&self, seeds: &[&[u8]]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(seeds, &self.program_id)
+    }
+
+    /// Processes the instructions in a transaction signed by the operator and the given signers,
+    /// and writes it to the ledger with the logs of the program
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        let recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions, Some(&self.operator()), &all_signers, recent_blockhash);
+
+        let result = self.context.banks_client
+            .process_transaction_with_metadata(transaction.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.result, Ok(()));
+
+        let log_messages = result.metadata.unwrap().log_messages;
+        let logs = instruction_logs(&log_messages);
+        assert_eq!(logs.len(), instructions.len());
+
+        let decoded_instructions: Vec<_> = instructions.iter()
+            .zip(logs)
+            .enumerate()
+            .map(|(instruction_index, (instruction, logs))| {
+                let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|account| account.pubkey).collect();
+                let decoded_instruction = decode_instruction(&self.program_id, &instruction.data, &accounts, logs)
+                    .unwrap();
+                (instruction_index, decoded_instruction)
+            })
+            .collect();
+
+        self.slot += 1;
+        self.ledger.insert_transaction(&transaction.signatures[0].to_string(), self.slot, None, &decoded_instructions)
+            .unwrap();
+    }
+
+    async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock = self.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    async fn now(&mut self) -> i64 {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    async fn account_data(&mut self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.context.banks_client.get_account(*address).await.unwrap().map(|account| account.data)
+    }
+
+    fn balance(&self, ledger_account: &str) -> i128 {
+        self.ledger.balance(ledger_account).unwrap()
+    }
+
+    /// Asserts that the ledger accounts of the user hold its balance, blocked amount and debt
+    async fn assert_user_balances(&mut self, user: &IndexedUser) {
+        let data = self.account_data(&user.address).await.unwrap();
+        let user_account = *UserAccount::load(&data).unwrap();
+
+        assert_eq!(self.balance(&available_account(user)) + self.balance(&blocked_account(user)), user_account.balance as i128);
+        assert_eq!(self.balance(&blocked_account(user)), user_account.blocked_amount as i128);
+        assert_eq!(self.balance(&debt_account(user)), -(user_account.debt as i128));
+    }
+}
+
+#[tokio::test]
+async fn test_ledger_totals() {
+    let mut test = IndexedProgramTest::start_new().await;
+    let program_id = test.program_id;
+    let token_mint = test.token_mint;
+    let operator = test.operator();
+
+    let (borrower, borrower_bump) = test.user(UserId::U32(1));
+    let (sender, sender_bump) = test.user(UserId::U32(2));
+    let (recipient, recipient_bump) = test.user(UserId::U64(3));
+    let (house, house_bump) = test.user(UserId::U32(4));
+    let users = [borrower.clone(), sender.clone(), recipient.clone(), house.clone()];
+
+    test.process(&[
+        create_derived(&program_id, borrower.user_id, &token_mint, &operator).unwrap(),
+        create_derived(&program_id, sender.user_id, &token_mint, &operator).unwrap(),
+        create_derived(&program_id, recipient.user_id, &token_mint, &operator).unwrap(),
+        create_derived(&program_id, house.user_id, &token_mint, &operator).unwrap(),
+        deposit_derived(&program_id, borrower.user_id, 500, &token_mint, &operator).unwrap(),
+        deposit_derived(&program_id, sender.user_id, 1000, &token_mint, &operator).unwrap(),
+        block_derived(&program_id, sender.user_id, 10, &token_mint, &operator).unwrap(),
+    ], &[]).await;
+
+    // Spends 100 on credit and repays 60 of it
+    test.process(&[
+        set_credit_limit(&program_id, borrower.user_id, borrower_bump, 200, &borrower.address, &token_mint, &operator)
+            .unwrap(),
+        transfer_derived(&program_id, &token_mint, borrower.user_id, recipient.user_id, 600, &operator).unwrap(),
+        deposit_derived(&program_id, borrower.user_id, 60, &token_mint, &operator).unwrap(),
+    ], &[]).await;
+
+    // Executes the first scheduled transfer, cancels the second one and leaves the third one pending
+    let now = test.now().await;
+    let scheduled_transfers: Vec<(Pubkey, u8)> = (0u32..3)
+        .map(|schedule_id| test.find_address(
+            &[SCHEDULED_TRANSFER_SEED, &sender.address.to_bytes(), &schedule_id.to_le_bytes()]))
+        .collect();
+    let schedules = scheduled_transfers.iter()
+        .zip([(100, now + 10), (50, now + 100_000), (70, now + 100_000)])
+        .enumerate()
+        .map(|(schedule_id, ((scheduled_transfer_key, scheduled_transfer_bump), (amount, execute_at)))|
+            schedule_transfer(
+                &program_id, &token_mint,
+                sender.user_id, sender_bump, &sender.address,
+                recipient.user_id, recipient_bump, &recipient.address,
+                schedule_id as u32, *scheduled_transfer_bump, scheduled_transfer_key,
+                amount, execute_at, &operator
+            ).unwrap())
+        .collect::<Vec<_>>();
+    test.process(&schedules, &[]).await;
+
+    test.advance_clock(20).await;
+    test.process(&[
+        execute_scheduled_transfer(&program_id, &scheduled_transfers[0].0, &recipient.address, &operator).unwrap(),
+        cancel_scheduled_transfer(&program_id, &scheduled_transfers[1].0, &sender.address, &operator).unwrap(),
+    ], &[]).await;
+
+    let (subscription_key, subscription_bump) = test.find_address(
+        &[SUBSCRIPTION_SEED, &sender.address.to_bytes(), &recipient.address.to_bytes()]);
+    test.process(&[
+        subscribe(
+            &program_id, &token_mint,
+            sender.user_id, sender_bump, &sender.address,
+            recipient.user_id, recipient_bump, &recipient.address,
+            subscription_bump, &subscription_key, 30, 3600, &operator
+        ).unwrap(),
+        charge_subscription(&program_id, &subscription_key, &sender.address, &recipient.address, 30, &operator).unwrap(),
+    ], &[]).await;
+
+    // Resolves the first escrow and leaves the second one funded by party A only
+    let arbiter = Keypair::new();
+    let refund_time = test.now().await + 3600;
+    let escrows: Vec<(Pubkey, u8)> = (0u32..2)
+        .map(|escrow_id| test.find_address(
+            &[ESCROW_SEED, &token_mint.to_bytes(), &operator.to_bytes(), &escrow_id.to_le_bytes()]))
+        .collect();
+    let create_escrows = escrows.iter()
+        .enumerate()
+        .map(|(escrow_id, (escrow_key, escrow_bump))| create_escrow(
+            &program_id, &token_mint, escrow_id as u32, *escrow_bump, escrow_key,
+            sender.user_id, sender_bump, &sender.address, 50,
+            recipient.user_id, recipient_bump, &recipient.address, 70,
+            &arbiter.pubkey(), refund_time, &operator
+        ).unwrap())
+        .collect::<Vec<_>>();
+    test.process(&create_escrows, &[]).await;
+    test.process(&[
+        fund_escrow(&program_id, &escrows[0].0, &sender.address, &operator).unwrap(),
+        fund_escrow(&program_id, &escrows[0].0, &recipient.address, &operator).unwrap(),
+        fund_escrow(&program_id, &escrows[1].0, &sender.address, &operator).unwrap(),
+    ], &[]).await;
+    test.process(&[
+        resolve_escrow(&program_id, &escrows[0].0, &sender.address, &recipient.address, 100, &operator, &arbiter.pubkey())
+            .unwrap(),
+    ], &[&arbiter]).await;
+
+    // Settles the first pot with a rake and leaves the second one open
+    let pots: Vec<(Pubkey, u8)> = (0u32..2)
+        .map(|pot_id| test.find_address(
+            &[POT_SEED, &token_mint.to_bytes(), &operator.to_bytes(), &pot_id.to_le_bytes()]))
+        .collect();
+    test.process(&[
+        create_pot(&program_id, 0, pots[0].1, &pots[0].0, &token_mint, &operator).unwrap(),
+        create_pot(&program_id, 1, pots[1].1, &pots[1].0, &token_mint, &operator).unwrap(),
+        join_pot(&program_id, sender.user_id, sender_bump, 40, &sender.address, &pots[0].0, &operator).unwrap(),
+        join_pot(&program_id, recipient.user_id, recipient_bump, 61, &recipient.address, &pots[0].0, &operator).unwrap(),
+        join_pot(&program_id, recipient.user_id, recipient_bump, 25, &recipient.address, &pots[1].0, &operator).unwrap(),
+    ], &[]).await;
+    test.process(&[
+        settle_pot(
+            &program_id,
+            &pots[0].0,
+            vec![
+                (PotWinner { user_id: sender.user_id, user_account_bump_seed: sender_bump, weight: 1 }, sender.address),
+                (PotWinner { user_id: recipient.user_id, user_account_bump_seed: recipient_bump, weight: 2 }, recipient.address),
+            ],
+            Some((PotRake { house_user_id: house.user_id, house_account_bump_seed: house_bump, basis_points: 500 }, house.address)),
+            &operator
+        ).unwrap(),
+    ], &[]).await;
+
+    let vesting = Vesting { start_time: 0, cliff_duration: 0, duration: 1, total_amount: 300 };
+    test.process(&[
+        grant_vesting(&program_id, house.user_id, house_bump, vesting, &house.address, &token_mint, &operator).unwrap(),
+    ], &[]).await;
+
+    // Claims the rewards of the first distribution and leaves the second one unclaimed
+    let (reward_pool_key, reward_pool_bump) = test.find_address(
+        &[REWARD_POOL_SEED, &token_mint.to_bytes(), &operator.to_bytes()]);
+    test.process(&[
+        create_reward_pool(&program_id, reward_pool_bump, &reward_pool_key, &token_mint, &operator).unwrap(),
+        enroll_rewards(&program_id, house.user_id, house_bump, &house.address, &reward_pool_key, &operator).unwrap(),
+        enroll_rewards(&program_id, sender.user_id, sender_bump, &sender.address, &reward_pool_key, &operator).unwrap(),
+        distribute_rewards(&program_id, 91, &reward_pool_key, &operator).unwrap(),
+        claim(&program_id, house.user_id, house_bump, &house.address, &reward_pool_key, &operator).unwrap(),
+        distribute_rewards(&program_id, 30, &reward_pool_key, &operator).unwrap(),
+    ], &[]).await;
+
+    for user in &users {
+        test.assert_user_balances(user).await;
+    }
+    assert_eq!(test.balance(&debt_account(&borrower)), -40);
+
+    let pending_transfer_data = test.account_data(&scheduled_transfers[2].0).await.unwrap();
+    let pending_transfer = ScheduledTransfer::load(&pending_transfer_data).unwrap();
+    assert_eq!(test.balance(&scheduled_transfer_account(&scheduled_transfers[2].0)), pending_transfer.amount as i128);
+
+    let open_escrow_data = test.account_data(&escrows[1].0).await.unwrap();
+    let open_escrow = Escrow::load(&open_escrow_data).unwrap();
+    assert_eq!(
+        test.balance(&escrow_account(&escrows[1].0)),
+        (open_escrow.party_a_funded + open_escrow.party_b_funded) as i128
+    );
+
+    let open_pot_data = test.account_data(&pots[1].0).await.unwrap();
+    let open_pot = Pot::load(&open_pot_data).unwrap();
+    assert_eq!(test.balance(&pot_account(&pots[1].0)), open_pot.amount as i128);
+
+    let reward_pool_data = test.account_data(&reward_pool_key).await.unwrap();
+    let reward_pool = RewardPool::try_from_slice(&reward_pool_data).unwrap();
+    assert_eq!(test.balance(&reward_pool_account(&reward_pool_key)), reward_pool.unclaimed_amount as i128);
+    assert_eq!(test.balance(&external_rewards_account(&reward_pool_key)), -121);
+
+    // The closed scheduled transfers, escrows and pots hold nothing
+    for closed_account in [
+        scheduled_transfer_account(&scheduled_transfers[0].0),
+        scheduled_transfer_account(&scheduled_transfers[1].0),
+        escrow_account(&escrows[0].0),
+        pot_account(&pots[0].0),
+    ] {
+        assert_eq!(test.balance(&closed_account), 0);
+    }
+    assert!(test.account_data(&pots[0].0).await.is_none());
+
+    // Everything deposited or granted is owed to the users, held for them or repaid
+    assert_eq!(test.balance(&external_account(&token_mint, &operator)), -(500 + 1000 + 60 + 300));
+}
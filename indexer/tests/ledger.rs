@@ -0,0 +1,79 @@
+use emotion_user_account::{
+    instruction::{
+        block_derived, create_derived, deposit_derived, get_user_account_address, transfer_derived, withdraw_derived
+    },
+    state::UserId,
+};
+use emotion_user_account_indexer::{
+    decode::{decode_instruction, external_account, DecodedInstruction},
+    ledger::Ledger,
+};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+fn decode(instruction: Instruction) -> DecodedInstruction {
+    let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|account| account.pubkey).collect();
+    decode_instruction(&instruction.program_id, &instruction.data, &accounts, &[]).unwrap()
+}
+
+#[test]
+fn test_decode_instruction() {
+    let program_id = Pubkey::new_unique();
+    let token_mint = Pubkey::new_unique();
+    let operator = Pubkey::new_unique();
+    let (sender_key, _) = get_user_account_address(&program_id, UserId::U32(1), &token_mint, &operator);
+    let (recipient_key, _) = get_user_account_address(&program_id, UserId::U64(2), &token_mint, &operator);
+
+    let create = decode(create_derived(&program_id, UserId::U32(1), &token_mint, &operator).unwrap());
+    assert_eq!(create.name, "Create");
+    assert_eq!(create.users[0].address, sender_key);
+    assert!(create.entries.is_empty());
+
+    let transfer = decode(
+        transfer_derived(&program_id, &token_mint, UserId::U32(1), UserId::U64(2), 300, &operator).unwrap());
+    assert_eq!(transfer.name, "Transfer");
+    assert_eq!(transfer.users.len(), 2);
+    assert_eq!(transfer.entries[0].debit, sender_key.to_string());
+    assert_eq!(transfer.entries[0].credit, recipient_key.to_string());
+    assert_eq!(transfer.entries[0].amount, 300);
+
+    assert!(decode_instruction(&program_id, &[255], &[], &[]).is_none());
+}
+
+#[test]
+fn test_ledger() {
+    let program_id = Pubkey::new_unique();
+    let token_mint = Pubkey::new_unique();
+    let operator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let (sender_key, _) = get_user_account_address(&program_id, UserId::U32(1), &token_mint, &operator);
+    let (recipient_key, _) = get_user_account_address(&program_id, UserId::U32(2), &token_mint, &operator);
+
+    let mut ledger = Ledger::open_in_memory().unwrap();
+
+    ledger.insert_transaction("first", 10, None, &[
+        (0, decode(create_derived(&program_id, UserId::U32(1), &token_mint, &operator).unwrap())),
+        (1, decode(create_derived(&program_id, UserId::U32(2), &token_mint, &operator).unwrap())),
+        (2, decode(deposit_derived(&program_id, UserId::U32(1), 1000, &token_mint, &operator).unwrap())),
+    ]).unwrap();
+    ledger.insert_transaction("second", 20, None, &[
+        (0, decode(transfer_derived(&program_id, &token_mint, UserId::U32(1), UserId::U32(2), 300, &operator).unwrap())),
+        (1, decode(block_derived(&program_id, UserId::U32(2), 100, &token_mint, &operator).unwrap())),
+    ]).unwrap();
+    ledger.insert_failed_transaction("failed", 25, None, "InstructionError").unwrap();
+    ledger.insert_transaction("third", 30, None, &[
+        (0, decode(withdraw_derived(&program_id, UserId::U32(1), 200, &token_mint, &destination, &operator).unwrap())),
+    ]).unwrap();
+
+    assert_eq!(ledger.balance(&sender_key.to_string()).unwrap(), 500);
+    assert_eq!(ledger.balance(&recipient_key.to_string()).unwrap(), 200);
+    assert_eq!(ledger.balance(&format!("{}:blocked", recipient_key)).unwrap(), 100);
+    assert_eq!(ledger.balance(&external_account(&token_mint, &operator)).unwrap(), -800);
+    assert!(ledger.contains_transaction("failed").unwrap());
+    assert_eq!(ledger.last_slot().unwrap(), Some(30));
+
+    assert_eq!(ledger.reset_from_slot(20).unwrap(), 3);
+    assert!(!ledger.contains_transaction("second").unwrap());
+    assert_eq!(ledger.balance(&sender_key.to_string()).unwrap(), 1000);
+    assert_eq!(ledger.balance(&recipient_key.to_string()).unwrap(), 0);
+    assert_eq!(ledger.last_slot().unwrap(), Some(10));
+}
@@ -9,16 +9,20 @@ import {
   } from '@solana/web3.js';
 
 import { struct, u8, u32 } from '@solana/buffer-layout';
-import { getPayer, getRpcUrl } from './utils';
+import { getPayer, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
 import { PROGRAM_ID, MINT_ID } from './config';
 
 interface Data {
+    versionMarker: number;
+    version: number;
     instruction: number;
+    userIdKind: number;
     userId: number;
     bumpSeed: number;
 }
 
-const DataLayout = struct<Data>([u8('instruction'), u32('userId'), u8('bumpSeed')]);
+// Each user id is preceded by its kind, 0 for the 32-bit ids used by these scripts
+const DataLayout = struct<Data>([u8('versionMarker'), u8('version'), u8('instruction'), u8('userIdKind'), u32('userId'), u8('bumpSeed')]);
 
 const userId = +process.argv[2];
   
@@ -58,7 +62,10 @@ async function main() {
     const data = Buffer.alloc(DataLayout.span);
     DataLayout.encode(
         {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
             instruction: 0,
+            userIdKind: 0,
             userId: userId,
             bumpSeed: bump
         },
@@ -9,17 +9,21 @@ import {
 
 import { struct, u8, u32 } from '@solana/buffer-layout';
 import { u64 } from '@solana/buffer-layout-utils';
-import { getPayer, getRpcUrl } from './utils';
+import { getPayer, getRewardPoolKeys, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
 import { PROGRAM_ID, MINT_ID } from './config';
 
 interface Data {
+    versionMarker: number;
+    version: number;
     instruction: number;
+    userIdKind: number;
     userId: number;
     bumpSeed: number;
     amount: BigInt;
 }
 
-const DataLayout = struct<Data>([u8('instruction'), u32('userId'), u8('bumpSeed'), u64('amount')]);
+// Each user id is preceded by its kind, 0 for the 32-bit ids used by these scripts
+const DataLayout = struct<Data>([u8('versionMarker'), u8('version'), u8('instruction'), u8('userIdKind'), u32('userId'), u8('bumpSeed'), u64('amount')]);
 
 const userId = +process.argv[2];
 const amount = BigInt(+process.argv[3]*LAMPORTS_PER_SOL);
@@ -60,7 +64,10 @@ async function main() {
     const data = Buffer.alloc(DataLayout.span);
     DataLayout.encode(
         {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
             instruction: 1,
+            userIdKind: 0,
             userId: userId,
             bumpSeed: bump,
             amount: amount,
@@ -73,6 +80,7 @@ async function main() {
             { pubkey: accountPubkey, isSigner: false, isWritable: true },
             { pubkey: MINT_ID, isSigner: false, isWritable: false },
             { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+            ...await getRewardPoolKeys(connection, [accountPubkey]),
         ],
         programId: PROGRAM_ID,
         data: data
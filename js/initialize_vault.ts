@@ -0,0 +1,100 @@
+import {
+    Connection,
+    PublicKey,
+    LAMPORTS_PER_SOL,
+    SystemProgram,
+    SYSVAR_RENT_PUBKEY,
+    TransactionInstruction,
+    Transaction,
+    sendAndConfirmTransaction,
+  } from '@solana/web3.js';
+
+import { ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { struct, u8 } from '@solana/buffer-layout';
+import { getPayer, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
+import { PROGRAM_ID, MINT_ID } from './config';
+
+interface Data {
+    versionMarker: number;
+    version: number;
+    instruction: number;
+    bumpSeed: number;
+}
+
+const DataLayout = struct<Data>([u8('versionMarker'), u8('version'), u8('instruction'), u8('bumpSeed')]);
+  
+async function main() {
+    console.log("Let's initialize the vault holding the deposited tokens...");
+
+    const rpcUrl = await getRpcUrl();
+    let connection = new Connection(rpcUrl, 'confirmed');
+    const version = await connection.getVersion();
+    console.log('Connection to cluster established:', rpcUrl, version);
+    console.log('Success');
+
+    let payer = await getPayer();
+    let lamports = await connection.getBalance(payer.publicKey);
+
+    console.log(
+      'Using account',
+      payer.publicKey.toBase58(),
+      'containing',
+      lamports / LAMPORTS_PER_SOL,
+      'SOL to pay for fees',
+    );
+
+    let [authorityAccountPubkey, bump] = await PublicKey.findProgramAddress(
+      [
+          Buffer.from('vault'),
+          MINT_ID.toBytes(),
+          payer.publicKey.toBytes()
+      ],  
+      PROGRAM_ID
+    );
+
+    const vaultPubkey = await getAssociatedTokenAddress(MINT_ID, authorityAccountPubkey, true);
+
+    console.log(`Creating vault ${vaultPubkey.toBase58()} of the authority ${authorityAccountPubkey.toBase58()}...`);  
+
+    const data = Buffer.alloc(DataLayout.span);
+    DataLayout.encode(
+        {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
+            instruction: 33,
+            bumpSeed: bump
+        },
+        data
+    );
+
+    const instruction = new TransactionInstruction({
+        keys: [
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+            { pubkey: authorityAccountPubkey, isSigner: false, isWritable: false },
+            { pubkey: vaultPubkey, isSigner: false, isWritable: true },
+            { pubkey: MINT_ID, isSigner: false, isWritable: false },
+            { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        ],
+        programId: PROGRAM_ID,
+        data: data
+    });
+
+    await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(instruction),
+        [payer],
+    );
+
+    console.log("Done.");
+}
+  
+main().then(
+    () => process.exit(),
+    err => {
+      console.error(err);
+      process.exit(-1);
+    },
+);
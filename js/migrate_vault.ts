@@ -0,0 +1,119 @@
+import {
+    Connection,
+    PublicKey,
+    LAMPORTS_PER_SOL,
+    TransactionInstruction,
+    Transaction,
+    sendAndConfirmTransaction,
+  } from '@solana/web3.js';
+
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { struct, u8 } from '@solana/buffer-layout';
+import { getPayer, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
+import { PROGRAM_ID, MINT_ID } from './config';
+
+interface Data {
+    versionMarker: number;
+    version: number;
+    instruction: number;
+    legacyBumpSeed: number;
+    bumpSeed: number;
+}
+
+const DataLayout = struct<Data>([
+    u8('versionMarker'), 
+    u8('version'), 
+    u8('instruction'), 
+    u8('legacyBumpSeed'), 
+    u8('bumpSeed')
+]);
+  
+async function main() {
+    console.log("Let's move the tokens of the vault of the previous versions to the vault...");
+
+    const rpcUrl = await getRpcUrl();
+    let connection = new Connection(rpcUrl, 'confirmed');
+    const version = await connection.getVersion();
+    console.log('Connection to cluster established:', rpcUrl, version);
+    console.log('Success');
+
+    let payer = await getPayer();
+    let lamports = await connection.getBalance(payer.publicKey);
+
+    console.log(
+      'Using account',
+      payer.publicKey.toBase58(),
+      'containing',
+      lamports / LAMPORTS_PER_SOL,
+      'SOL to pay for fees',
+    );
+
+    // The previous versions derived the vault authority like the account of the user 0
+    const legacyUserIdBuffer = Buffer.alloc(4);
+    legacyUserIdBuffer.writeUInt32LE(0);
+
+    let [legacyAuthorityAccountPubkey, legacyBump] = await PublicKey.findProgramAddress(
+      [
+          legacyUserIdBuffer,
+          MINT_ID.toBytes(),
+          payer.publicKey.toBytes()
+      ],  
+      PROGRAM_ID
+    );
+
+    let [authorityAccountPubkey, bump] = await PublicKey.findProgramAddress(
+      [
+          Buffer.from('vault'),
+          MINT_ID.toBytes(),
+          payer.publicKey.toBytes()
+      ],  
+      PROGRAM_ID
+    );
+
+    const legacyVaultPubkey = await getAssociatedTokenAddress(MINT_ID, legacyAuthorityAccountPubkey, true);
+    const vaultPubkey = await getAssociatedTokenAddress(MINT_ID, authorityAccountPubkey, true);
+
+    console.log(`Moving the tokens of the vault ${legacyVaultPubkey.toBase58()} to the vault ${vaultPubkey.toBase58()}...`);  
+
+    const data = Buffer.alloc(DataLayout.span);
+    DataLayout.encode(
+        {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
+            instruction: 35,
+            legacyBumpSeed: legacyBump,
+            bumpSeed: bump
+        },
+        data
+    );
+
+    const instruction = new TransactionInstruction({
+        keys: [
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            { pubkey: legacyAuthorityAccountPubkey, isSigner: false, isWritable: false },
+            { pubkey: legacyVaultPubkey, isSigner: false, isWritable: true },
+            { pubkey: authorityAccountPubkey, isSigner: false, isWritable: false },
+            { pubkey: vaultPubkey, isSigner: false, isWritable: true },
+            { pubkey: MINT_ID, isSigner: false, isWritable: false },
+            { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: PROGRAM_ID,
+        data: data
+    });
+
+    await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(instruction),
+        [payer],
+    );
+
+    console.log("Done.");
+}
+  
+main().then(
+    () => process.exit(),
+    err => {
+      console.error(err);
+      process.exit(-1);
+    },
+);
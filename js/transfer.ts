@@ -9,22 +9,31 @@ import {
 
 import { struct, u8, u32 } from '@solana/buffer-layout';
 import { u64 } from '@solana/buffer-layout-utils';
-import { getPayer, getRpcUrl } from './utils';
+import { getPayer, getRewardPoolKeys, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
 import { PROGRAM_ID, MINT_ID } from './config';
 
 interface Data {
+    versionMarker: number;
+    version: number;
     instruction: number;
+    senderUserIdKind: number;
     senderUserId: number;
     senderBumpSeed: number;
+    recipientUserIdKind: number;
     recipientUserId: number;
     recipientBumpSeed: number;
     amount: BigInt;
 }
 
+// Each user id is preceded by its kind, 0 for the 32-bit ids used by these scripts
 const DataLayout = struct<Data>(
-    [u8('instruction'), 
+    [u8('versionMarker'), 
+    u8('version'), 
+    u8('instruction'), 
+    u8('senderUserIdKind'), 
     u32('senderUserId'), 
     u8('senderBumpSeed'), 
+    u8('recipientUserIdKind'), 
     u32('recipientUserId'), 
     u8('recipientBumpSeed'), 
     u64('amount')]);
@@ -81,9 +90,13 @@ async function main() {
     const data = Buffer.alloc(DataLayout.span);
     DataLayout.encode(
         {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
             instruction: 3,
+            senderUserIdKind: 0,
             senderUserId: senderUserId,
             senderBumpSeed: senderBump,
+            recipientUserIdKind: 0,
             recipientUserId: recipientUserId,
             recipientBumpSeed: recipientBump,
             amount: amount,
@@ -97,6 +110,7 @@ async function main() {
             { pubkey: senderAccountPubkey, isSigner: false, isWritable: true },
             { pubkey: recipientAccountPubkey, isSigner: false, isWritable: true },
             { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+            ...await getRewardPoolKeys(connection, [senderAccountPubkey, recipientAccountPubkey]),
         ],
         programId: PROGRAM_ID,
         data: data
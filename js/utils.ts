@@ -6,7 +6,14 @@ import os from 'os';
 import fs from 'mz/fs';
 import path from 'path';
 import yaml from 'yaml';
-import {Keypair} from '@solana/web3.js';
+import {AccountMeta, Connection, Keypair, PublicKey} from '@solana/web3.js';
+
+/**
+ * The instruction data starts with this marker and the version of the encoding, followed by the tag
+ * of the instruction and its fields. The program still accepts the legacy data without them.
+ */
+export const INSTRUCTION_VERSION_MARKER = 0xff;
+export const INSTRUCTION_VERSION = 1;
 
 /**
  * @private
@@ -66,4 +73,34 @@ export async function createKeypairFromFile(
   const secretKeyString = await fs.readFile(filePath, {encoding: 'utf8'});
   const secretKey = Uint8Array.from(JSON.parse(secretKeyString));
   return Keypair.fromSecretKey(secretKey);
-}
\ No newline at end of file
+}
+
+/**
+ * Offset of the reward pool in the data of a user account, after the balance, the blocked amount,
+ * the withdrawal wallets, the owner and the vesting
+ */
+const REWARD_POOL_OFFSET = 152;
+
+/**
+ * Returns the reward pools the given user accounts are enrolled in. The program requires them
+ * in the instructions changing the balances of the enrolled users.
+ */
+export async function getRewardPoolKeys(
+  connection: Connection,
+  userAccounts: PublicKey[],
+): Promise<AccountMeta[]> {
+  const keys: AccountMeta[] = [];
+  for (const userAccount of userAccounts) {
+    const accountInfo = await connection.getAccountInfo(userAccount);
+    if (!accountInfo || accountInfo.data.length < REWARD_POOL_OFFSET + 32) continue;
+
+    const rewardPool = new PublicKey(
+      accountInfo.data.subarray(REWARD_POOL_OFFSET, REWARD_POOL_OFFSET + 32),
+    );
+    if (rewardPool.equals(PublicKey.default) || keys.some(key => key.pubkey.equals(rewardPool))) {
+      continue;
+    }
+    keys.push({pubkey: rewardPool, isSigner: false, isWritable: true});
+  }
+  return keys;
+}
@@ -11,19 +11,26 @@ import {
 
 import { struct, u8, u32 } from '@solana/buffer-layout';
 import { u64 } from '@solana/buffer-layout-utils';
-import { getPayer, getRpcUrl } from './utils';
+import { getPayer, getRewardPoolKeys, getRpcUrl, INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION } from './utils';
 import { PROGRAM_ID, MINT_ID } from './config';
 
 interface Data {
+    versionMarker: number;
+    version: number;
     instruction: number;
+    userIdKind: number;
     userId: number;
     userAccountBumpSeed: number;
     sourceAuthorityBumpSeed: number;
     amount: BigInt;
 }
 
+// Each user id is preceded by its kind, 0 for the 32-bit ids used by these scripts
 const DataLayout = struct<Data>([
+    u8('versionMarker'), 
+    u8('version'), 
     u8('instruction'), 
+    u8('userIdKind'), 
     u32('userId'), 
     u8('userAccountBumpSeed'), 
     u8('sourceAuthorityBumpSeed'), 
@@ -63,12 +70,9 @@ async function main() {
       PROGRAM_ID
     );
 
-    const zeroUserIdBuf = Buffer.alloc(4);
-    zeroUserIdBuf.writeUInt32LE(0);
-
     let [authorityAccountPubkey, authorityBump] = await PublicKey.findProgramAddress(
       [
-          zeroUserIdBuf,
+          Buffer.from('vault'),
           MINT_ID.toBytes(),
           payer.publicKey.toBytes()
       ],  
@@ -83,7 +87,10 @@ async function main() {
     const data = Buffer.alloc(DataLayout.span);
     DataLayout.encode(
         {
+            versionMarker: INSTRUCTION_VERSION_MARKER,
+            version: INSTRUCTION_VERSION,
             instruction: 2,
+            userIdKind: 0,
             userId: userId,
             userAccountBumpSeed: userBump,
             sourceAuthorityBumpSeed: authorityBump,
@@ -101,6 +108,7 @@ async function main() {
             { pubkey: authorityAccountPubkey, isSigner: false, isWritable: false },
             { pubkey: destinationPubkey, isSigner: false, isWritable: true },
             { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+            ...await getRewardPoolKeys(connection, [accountPubkey]),
         ],
         programId: PROGRAM_ID,
         data: data
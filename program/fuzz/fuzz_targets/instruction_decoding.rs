@@ -0,0 +1,17 @@
+#![no_main]
+use borsh::{BorshDeserialize, BorshSerialize};
+use emotion_user_account::instruction::{UserAccountInstruction, INSTRUCTION_VERSION_MARKER};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(instruction) = UserAccountInstruction::try_from_slice(data) {
+        let encoded = instruction.try_to_vec().unwrap();
+
+        // The versioned encoding is canonical, and a legacy instruction is encoded again in the versioned one
+        if data[0] == INSTRUCTION_VERSION_MARKER {
+            assert_eq!(encoded, data);
+        } else {
+            assert_eq!(UserAccountInstruction::try_from_slice(&encoded).unwrap(), instruction);
+        }
+    }
+});
@@ -0,0 +1,439 @@
+#![no_main]
+//! Processes a single instruction over mocked accounts. The user accounts start in any state the
+//! program can leave them in, and the invocations of other programs are not executed.
+use arbitrary::Arbitrary;
+use borsh::BorshSerialize;
+use bytemuck::Zeroable;
+use emotion_user_account::{
+    instruction::{
+        block_derived, claim, create_derived, deposit_derived, distribute_rewards, get_user_account_address,
+        grant_vesting, set_credit_limit, transfer_derived, unblock_derived, with_reward_pool, withdraw_derived
+    },
+    processor::process_instruction,
+    state::{RewardPool, UserAccount, UserId, Vesting, REWARD_POOL_SEED},
+};
+use libfuzzer_sys::fuzz_target;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Once,
+};
+
+/// The unix timestamp of the clock sysvar
+static NOW: AtomicI64 = AtomicI64::new(0);
+static SYSCALL_STUBS: Once = Once::new();
+
+struct FuzzSyscallStubs;
+
+impl SyscallStubs for FuzzSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        // The accounts created through the system program cannot be allocated here.
+        // The other invocations move tokens, which the user accounts do not depend on.
+        if instruction.program_id == system_program::id() {
+            Err(ProgramError::InvalidAccountData)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW.load(Ordering::Relaxed), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// The small ids are the most frequent, so that the instructions often share accounts
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum FuzzUserId {
+    Small(u8),
+    U64(u64),
+    Bytes([u8; 32]),
+}
+
+impl From<FuzzUserId> for UserId {
+    fn from(user_id: FuzzUserId) -> Self {
+        match user_id {
+            FuzzUserId::Small(user_id) => UserId::U32(u32::from(user_id % 4)),
+            FuzzUserId::U64(user_id) => UserId::U64(user_id),
+            FuzzUserId::Bytes(user_id) => UserId::Bytes(user_id),
+        }
+    }
+}
+
+/// A vesting schedule that `GrantVesting` accepts
+#[derive(Arbitrary, Debug)]
+struct FuzzVesting {
+    start_time: i64,
+    cliff_duration: u32,
+    duration_after_cliff: u32,
+    total_amount: u64,
+}
+
+impl From<&FuzzVesting> for Vesting {
+    fn from(vesting: &FuzzVesting) -> Self {
+        Vesting {
+            start_time: vesting.start_time,
+            cliff_duration: i64::from(vesting.cliff_duration),
+            duration: i64::from(vesting.cliff_duration) + i64::from(vesting.duration_after_cliff),
+            total_amount: vesting.total_amount,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzUserAccount {
+    Missing,
+    Existing {
+        balance: u64,
+        blocked_amount: u64,
+        credit_limit: u64,
+        debt: u64,
+        vesting: Option<FuzzVesting>,
+        /// The reward per share at the last settlement and the pending amount,
+        /// if the user is enrolled in the reward pool
+        reward: Option<(u128, u64)>,
+        has_owner: bool,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzRewardPool {
+    reward_per_share: u128,
+    total_shares: u64,
+    unclaimed_amount: u64,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccountMeta {
+    /// The index of the key among the accounts of the other instructions
+    key_index: u8,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzInstruction {
+    Create { user_id: FuzzUserId },
+    Deposit { user_id: FuzzUserId, amount: u64 },
+    Withdraw { user_id: FuzzUserId, amount: u64 },
+    Transfer { sender_user_id: FuzzUserId, recipient_user_id: FuzzUserId, amount: u64 },
+    Block { user_id: FuzzUserId, amount: u64 },
+    Unblock { user_id: FuzzUserId, amount: u64 },
+    SetCreditLimit { user_id: FuzzUserId, credit_limit: u64 },
+    GrantVesting { user_id: FuzzUserId, start_time: i64, cliff_duration: i64, duration: i64, total_amount: u64 },
+    DistributeRewards { amount: u64 },
+    Claim { user_id: FuzzUserId },
+    /// Any instruction data, so that the instructions without a dedicated case are covered too
+    Raw { data: Vec<u8>, accounts: Vec<FuzzAccountMeta> },
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    now: i64,
+    instruction: FuzzInstruction,
+    /// The states of the user accounts, in the order the instruction lists them
+    user_accounts: Vec<FuzzUserAccount>,
+    reward_pool: Option<FuzzRewardPool>,
+    signed_by_operator: bool,
+}
+
+struct Keys {
+    program_id: Pubkey,
+    token_mint: Pubkey,
+    operator: Pubkey,
+    owner: Pubkey,
+    destination: Pubkey,
+    reward_pool: Pubkey,
+}
+
+impl Keys {
+    fn new() -> Self {
+        let program_id = Pubkey::new_from_array([1; 32]);
+        let token_mint = Pubkey::new_from_array([2; 32]);
+        let operator = Pubkey::new_from_array([3; 32]);
+        let (reward_pool, _) = Pubkey::find_program_address(
+            &[REWARD_POOL_SEED, &token_mint.to_bytes(), &operator.to_bytes()],
+            &program_id);
+
+        Keys {
+            program_id,
+            token_mint,
+            operator,
+            owner: Pubkey::new_from_array([4; 32]),
+            destination: Pubkey::new_from_array([5; 32]),
+            reward_pool,
+        }
+    }
+
+    fn user_account(&self, user_id: UserId) -> (Pubkey, u8) {
+        get_user_account_address(&self.program_id, user_id, &self.token_mint, &self.operator)
+    }
+}
+
+/// Returns the instruction and the ids of the users whose accounts it may list
+fn build_instruction(
+    keys: &Keys,
+    instruction: &FuzzInstruction,
+    with_reward_pool_account: bool
+) -> (Instruction, Vec<UserId>) {
+    let program_id = &keys.program_id;
+    let token_mint = &keys.token_mint;
+    let operator = &keys.operator;
+
+    let (instruction, user_ids) = match *instruction {
+        FuzzInstruction::Create { user_id } =>
+            (create_derived(program_id, user_id.into(), token_mint, operator), vec![user_id.into()]),
+        FuzzInstruction::Deposit { user_id, amount } =>
+            (deposit_derived(program_id, user_id.into(), amount, token_mint, operator), vec![user_id.into()]),
+        FuzzInstruction::Withdraw { user_id, amount } => (
+            withdraw_derived(program_id, user_id.into(), amount, token_mint, &keys.destination, operator),
+            vec![user_id.into()]
+        ),
+        FuzzInstruction::Transfer { sender_user_id, recipient_user_id, amount } => (
+            transfer_derived(program_id, token_mint, sender_user_id.into(), recipient_user_id.into(), amount, operator),
+            vec![sender_user_id.into(), recipient_user_id.into()]
+        ),
+        FuzzInstruction::Block { user_id, amount } =>
+            (block_derived(program_id, user_id.into(), amount, token_mint, operator), vec![user_id.into()]),
+        FuzzInstruction::Unblock { user_id, amount } =>
+            (unblock_derived(program_id, user_id.into(), amount, token_mint, operator), vec![user_id.into()]),
+        FuzzInstruction::SetCreditLimit { user_id, credit_limit } => {
+            let (user_account, bump_seed) = keys.user_account(user_id.into());
+            (
+                set_credit_limit(program_id, user_id.into(), bump_seed, credit_limit, &user_account, token_mint, operator),
+                vec![user_id.into()]
+            )
+        }
+        FuzzInstruction::GrantVesting { user_id, start_time, cliff_duration, duration, total_amount } => {
+            let (user_account, bump_seed) = keys.user_account(user_id.into());
+            let vesting = Vesting { start_time, cliff_duration, duration, total_amount };
+            (
+                grant_vesting(program_id, user_id.into(), bump_seed, vesting, &user_account, token_mint, operator),
+                vec![user_id.into()]
+            )
+        }
+        FuzzInstruction::DistributeRewards { amount } =>
+            (distribute_rewards(program_id, amount, &keys.reward_pool, operator), vec![]),
+        FuzzInstruction::Claim { user_id } => {
+            let (user_account, bump_seed) = keys.user_account(user_id.into());
+            (
+                claim(program_id, user_id.into(), bump_seed, &user_account, &keys.reward_pool, operator),
+                vec![user_id.into()]
+            )
+        }
+        FuzzInstruction::Raw { ref data, ref accounts } => {
+            let user_ids: Vec<UserId> = (0..4).map(UserId::U32).collect();
+            let mut account_keys = vec![
+                *program_id, *token_mint, *operator, keys.owner, keys.destination, keys.reward_pool,
+                system_program::id(),
+            ];
+            account_keys.extend(user_ids.iter().map(|user_id| keys.user_account(*user_id).0));
+
+            let accounts = accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account_keys[account.key_index as usize % account_keys.len()],
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect();
+
+            return (Instruction { program_id: *program_id, accounts, data: data.clone() }, user_ids);
+        }
+    };
+
+    let instruction = instruction.unwrap();
+
+    // The balance changes settle the rewards of the enrolled users
+    if with_reward_pool_account && !instruction.accounts.iter().any(|account| account.pubkey == keys.reward_pool) {
+        (with_reward_pool(instruction, &keys.reward_pool), user_ids)
+    } else {
+        (instruction, user_ids)
+    }
+}
+
+struct MockAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+    /// Whether `blocked_amount <= balance` must hold after a successful instruction
+    check_invariant: bool,
+}
+
+impl MockAccount {
+    fn empty(key: Pubkey) -> Self {
+        MockAccount {
+            key,
+            owner: system_program::id(),
+            lamports: 0,
+            data: Vec::new(),
+            is_signer: false,
+            is_writable: false,
+            check_invariant: false,
+        }
+    }
+
+    fn user(keys: &Keys, user_id: UserId, state: Option<&FuzzUserAccount>) -> Self {
+        let (key, _) = keys.user_account(user_id);
+
+        match state {
+            Some(FuzzUserAccount::Existing {
+                balance, blocked_amount, credit_limit, debt, vesting, reward, has_owner
+            }) => {
+                let mut user_account = UserAccount::zeroed();
+                user_account.balance = *balance;
+                user_account.blocked_amount = *blocked_amount;
+                user_account.credit_limit = *credit_limit;
+                user_account.debt = *debt;
+                user_account.token_mint = keys.token_mint;
+                user_account.user_id = user_id.into();
+
+                if let Some(vesting) = vesting {
+                    user_account.vesting = vesting.into();
+                }
+
+                if let Some((reward_per_share, pending_amount)) = reward {
+                    user_account.reward.reward_pool = keys.reward_pool;
+                    user_account.reward.reward_per_share = reward_per_share.to_le_bytes();
+                    user_account.reward.pending_amount = *pending_amount;
+                }
+
+                if *has_owner {
+                    user_account.owner = keys.owner;
+                }
+
+                MockAccount {
+                    owner: keys.program_id,
+                    lamports: Rent::default().minimum_balance(UserAccount::LEN),
+                    data: user_account.try_to_vec().unwrap(),
+                    check_invariant: blocked_amount <= balance,
+                    ..MockAccount::empty(key)
+                }
+            }
+            Some(FuzzUserAccount::Missing) | None => MockAccount::empty(key),
+        }
+    }
+
+    fn reward_pool(keys: &Keys, state: &FuzzRewardPool) -> Self {
+        let reward_pool = RewardPool {
+            token_mint: keys.token_mint,
+            operator: keys.operator,
+            reward_per_share: state.reward_per_share,
+            total_shares: state.total_shares,
+            unclaimed_amount: state.unclaimed_amount,
+        };
+
+        MockAccount {
+            owner: keys.program_id,
+            data: reward_pool.try_to_vec().unwrap(),
+            ..MockAccount::empty(keys.reward_pool)
+        }
+    }
+}
+
+fuzz_target!(|input: FuzzInput| {
+    SYSCALL_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(FuzzSyscallStubs));
+    });
+    NOW.store(input.now, Ordering::Relaxed);
+
+    let keys = Keys::new();
+    let (mut instruction, user_ids) = build_instruction(&keys, &input.instruction, input.reward_pool.is_some());
+
+    if !input.signed_by_operator {
+        instruction.accounts
+            .iter_mut()
+            .filter(|account| account.pubkey == keys.operator)
+            .for_each(|account| account.is_signer = false);
+    }
+
+    // An account listed several times is shared, as in the runtime
+    let mut user_accounts = input.user_accounts.iter();
+    let mut mock_accounts: Vec<MockAccount> = Vec::new();
+
+    for account_meta in &instruction.accounts {
+        if !mock_accounts.iter().any(|account| account.key == account_meta.pubkey) {
+            let user_id = user_ids
+                .iter()
+                .find(|user_id| keys.user_account(**user_id).0 == account_meta.pubkey);
+
+            let mock_account = match (user_id, &input.reward_pool) {
+                (Some(user_id), _) => MockAccount::user(&keys, *user_id, user_accounts.next()),
+                (None, Some(reward_pool)) if account_meta.pubkey == keys.reward_pool =>
+                    MockAccount::reward_pool(&keys, reward_pool),
+                _ => MockAccount::empty(account_meta.pubkey),
+            };
+            mock_accounts.push(mock_account);
+        }
+
+        let mock_account = mock_accounts
+            .iter_mut()
+            .find(|account| account.key == account_meta.pubkey)
+            .unwrap();
+        mock_account.is_signer |= account_meta.is_signer;
+        mock_account.is_writable |= account_meta.is_writable;
+    }
+
+    let result = {
+        let account_infos: Vec<AccountInfo> = mock_accounts
+            .iter_mut()
+            .map(|account| AccountInfo::new(
+                &account.key,
+                account.is_signer,
+                account.is_writable,
+                &mut account.lamports,
+                &mut account.data,
+                &account.owner,
+                false,
+                0))
+            .collect();
+
+        let accounts: Vec<AccountInfo> = instruction.accounts
+            .iter()
+            .map(|account_meta| account_infos
+                .iter()
+                .find(|account| *account.key == account_meta.pubkey)
+                .unwrap()
+                .clone())
+            .collect();
+
+        process_instruction(&keys.program_id, &accounts, &instruction.data)
+    };
+
+    // The runtime discards the changes of a failed instruction
+    if result.is_ok() {
+        for account in mock_accounts.iter().filter(|account| account.check_invariant) {
+            let user_account = UserAccount::load(&account.data).unwrap();
+            assert!(
+                user_account.blocked_amount <= user_account.balance,
+                "{:?} left the user account {} with {:?}",
+                input.instruction, account.key, user_account);
+        }
+    }
+});
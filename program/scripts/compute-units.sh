@@ -0,0 +1,17 @@
+#!/bin/sh
+# Builds the BPF program and runs the compute unit benchmark against it.
+# `test_compute_units` loads the shared object from the output directory and fails without it,
+# so CI runs this script instead of `cargo test`.
+set -e
+
+cd "$(dirname "$0")/.."
+OUT_DIR="$PWD/target/deploy"
+
+# `cargo build-sbf` replaces `cargo build-bpf` in the recent Solana tool suites
+if command -v cargo-build-sbf > /dev/null; then
+    cargo build-sbf --sbf-out-dir "$OUT_DIR"
+else
+    cargo build-bpf --bpf-out-dir "$OUT_DIR"
+fi
+
+BPF_OUT_DIR="$OUT_DIR" SBF_OUT_DIR="$OUT_DIR" cargo test --features test-bpf --test compute_units -- --nocapture
@@ -6,7 +6,6 @@ use solana_program::{
     account_info::AccountInfo, 
     entrypoint, 
     entrypoint::ProgramResult, 
-    msg,
     program_error::PrintProgramError, 
     pubkey::Pubkey,
 };
@@ -20,7 +19,7 @@ pub fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    msg!("Entrypoint");
+    msg_verbose!("Entrypoint");
     if let Err(error) = processor::process_instruction(program_id, accounts, instruction_data) {
         // catch the error so we can print it
         error.print::<UserAccountError>();
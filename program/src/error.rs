@@ -11,7 +11,43 @@ use thiserror::Error;
 pub enum UserAccountError {
     // Invalid instruction
     #[error("Invalid Instruction")]
-    InvalidInstruction
+    InvalidInstruction,
+
+    // The destination is not the registered withdrawal wallet's token account
+    #[error("Invalid withdrawal destination")]
+    InvalidWithdrawalDestination,
+
+    // There is no withdrawal wallet change to cancel
+    #[error("No pending withdrawal wallet change")]
+    NoPendingWithdrawalWalletChange,
+
+    // A new vesting schedule cannot replace one that is not fully vested
+    #[error("Vesting in progress")]
+    VestingInProgress,
+
+    // The reward pool the user is enrolled in was not provided
+    #[error("Missing reward pool")]
+    MissingRewardPool,
+
+    // Rewards cannot be distributed while no balance is enrolled
+    #[error("No enrolled balance")]
+    NoEnrolledBalance,
+
+    // The transfer needs more credit than the credit limit allows
+    #[error("Credit limit exceeded")]
+    CreditLimitExceeded,
+
+    // Withdrawals are not allowed until the debt is repaid
+    #[error("Outstanding debt")]
+    OutstandingDebt,
+
+    // Accounts cannot be created for the reserved user ids
+    #[error("Reserved user id")]
+    ReservedUserId,
+
+    // A transfer cannot move tokens between a ledger shard and a user account
+    #[error("Mixed ledger transfer")]
+    MixedLedgerTransfer
 }
 
 impl PrintProgramError for UserAccountError {
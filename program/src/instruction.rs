@@ -1,17 +1,23 @@
 use {
+    crate::state::{Delegate, PotRake, PotWinner, UserId, Vesting, ALLOWANCE_SEED, SHARD_SEED, VAULT_SEED},
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         instruction::{AccountMeta, Instruction},
         program_error::ProgramError,
         pubkey::Pubkey,
         system_program,
+        sysvar,
     },
+    spl_associated_token_account::get_associated_token_address,
+    std::io::{self, Write},
 };
 
-#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The instructions of the program, encoded with the tags assigned by `instruction_tags!`
+#[derive(Clone, Debug, PartialEq)]
 pub enum UserAccountInstruction {
 
-    /// Creates a new account for the given `user_id`.
+    /// Creates a new account for the given `user_id`. The account address is derived
+    /// from `user_id.seed()`, the token mint and the operator.
     ///
     /// Accounts expected by this instruction:
     ///   0. `[]` The system program account
@@ -19,18 +25,18 @@ pub enum UserAccountInstruction {
     ///   2. `[]` The token mint address
     ///   3. `[signer]` Operator/fee payer account
     Create { 
-        user_id: u32,
+        user_id: UserId,
         user_account_bump_seed: u8
     },
 
     /// Adds the given `amount` of tokens to the balance of the existing user
     ///
     /// Accounts expected by this instruction:
-    ///   0. `[writeable]` The user account
+    ///   0. `[writeable]` The user account, or the ledger shard storing the user
     ///   1. `[]` The token mint address
     ///   2. `[signer]` Operator/fee payer account
     Deposit { 
-        user_id: u32,
+        user_id: UserId,
         user_account_bump_seed: u8,
         amount: u64   
     },
@@ -39,30 +45,34 @@ pub enum UserAccountInstruction {
     /// 
     /// Accounts expected by this instruction:
     ///   0. `[]` The SPL token program account
-    ///   1. `[writeable]` The user account
+    ///   1. `[writeable]` The user account, or the ledger shard storing the user
     ///   2. `[]` The token mint address
-    ///   3. `[writeable]` Account to be used as the source for the transfer operation 
-    ///   4. `[]` The source authority account
-    ///   5. `[writeable]` Account to be used as the destination for the transfer operation 
-    ///   6. `[signer]` Operator/fee payer account
+    ///   3. `[writeable]` The source account, the vault token account
+    ///   4. `[]` The source authority account, the vault authority
+    ///   5. `[writeable]` The destination account, the registered withdrawal wallet's token account 
+    ///      if the user has one, otherwise the owner's token account if the owner signs
+    ///   6. `[signer]` Operator/fee payer account, not a signer if the owner signs
+    ///   7. `[signer]` (Optional) The user's owner
     Withdraw { 
-        user_id: u32,
+        user_id: UserId,
         user_account_bump_seed: u8,
         source_authority_bump_seed: u8,
         amount: u64
     },
 
     /// Transfers the given `amount` of tokens from the `sender` to `recipient`.
+    /// Both users must be stored in ledger shards, or both in user accounts.
     /// 
     /// Accounts expected by this instruction:
     ///   0. `[]` The token mint address
-    ///   1. `[writeable]` The sender account
-    ///   2. `[writeable]` The recipient account 
-    ///   3. `[signer]` Operator/fee payer account
+    ///   1. `[writeable]` The sender account, or the ledger shard storing the sender
+    ///   2. `[writeable]` The recipient account, or the ledger shard storing the recipient
+    ///   3. `[signer]` Operator/fee payer account, not a signer if the sender's owner signs
+    ///   4. `[signer]` (Optional) The sender's owner
     Transfer { 
-        sender_user_id: u32,
+        sender_user_id: UserId,
         sender_account_bump_seed: u8,
-        recipient_user_id: u32,
+        recipient_user_id: UserId,
         recipient_account_bump_seed: u8,
         amount: u64
     },
@@ -70,11 +80,11 @@ pub enum UserAccountInstruction {
     /// Blocks the given amount of tokens on the existing user's balance.
     ///
     /// Accounts expected by this instruction:
-    ///   0. `[writeable]` The user account
+    ///   0. `[writeable]` The user account, or the ledger shard storing the user
     ///   1. `[]` The token mint address
     ///   2. `[signer]` Operator/fee payer account
     Block { 
-        user_id: u32,
+        user_id: UserId,
         user_account_bump_seed: u8,
         amount: u64
     },
@@ -82,20 +92,679 @@ pub enum UserAccountInstruction {
     /// Unblocks the given amount of tokens on the existing user's balance.
     ///
     /// Accounts expected by this instruction:
-    ///   0. `[writeable]` The user account
+    ///   0. `[writeable]` The user account, or the ledger shard storing the user
     ///   1. `[]` The token mint address
     ///   2. `[signer]` Operator/fee payer account
     Unblock { 
-        user_id: u32,
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        amount: u64
+    },
+
+    /// Creates or updates the operator config for the given token mint. A higher 
+    /// `withdrawal_wallet_delay` takes effect immediately, a lower one only after the current delay.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[writeable]` The config account
+    ///   2. `[]` The token mint address
+    ///   3. `[signer]` Operator/fee payer account
+    SetConfig {
+        config_bump_seed: u8,
+        withdrawal_wallet_delay: i64
+    },
+
+    /// Registers the wallet that receives the user's withdrawals. The first wallet 
+    /// takes effect immediately, a replacement only after the configured delay.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[]` The config account
+    ///   2. `[]` The token mint address
+    ///   3. `[signer]` Operator/fee payer account
+    SetWithdrawalWallet {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        config_bump_seed: u8,
+        wallet: Pubkey
+    },
+
+    /// Cancels the pending withdrawal wallet change of the existing user.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[]` The token mint address
+    ///   2. `[signer]` Operator/fee payer account, not a signer if the user's owner signs
+    ///   3. `[signer]` (Optional) The user's owner
+    CancelWithdrawalWalletChange {
+        user_id: UserId,
+        user_account_bump_seed: u8
+    },
+
+    /// Registers the `owner` key that can sign transfers and withdrawals of the existing user.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[]` The token mint address
+    ///   2. `[signer]` Operator/fee payer account, not a signer if the current owner signs
+    ///   3. `[signer]` (Optional) The current owner
+    SetOwner {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        owner: Pubkey
+    },
+
+    /// Allows the `delegate` to spend up to `amount` tokens from the balance of the existing user.
+    /// Replaces the previous allowance of the same delegate.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The user account
+    ///   2. `[writeable]` The allowance account
+    ///   3. `[]` The token mint address
+    ///   4. `[signer]` Operator/fee payer account, not a signer if the user's owner signs
+    ///   5. `[writeable, signer]` (Optional) The user's owner, pays for the allowance account
+    Approve {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        allowance_bump_seed: u8,
+        delegate: Delegate,
+        amount: u64
+    },
+
+    /// Revokes the allowance of the `delegate`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The user account
+    ///   1. `[writeable]` The allowance account
+    ///   2. `[]` The token mint address
+    ///   3. `[signer]` Operator/fee payer account, not a signer if the user's owner signs
+    ///   4. `[signer]` (Optional) The user's owner
+    Revoke {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        allowance_bump_seed: u8,
+        delegate: Delegate
+    },
+
+    /// Transfers the given `amount` of tokens from the `sender` to `recipient` 
+    /// on behalf of the `delegate`, spending the delegate's allowance.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The token mint address
+    ///   1. `[writeable]` The sender account
+    ///   2. `[writeable]` The recipient account
+    ///   3. `[writeable]` The allowance account
+    ///   4. `[]` Operator account
+    ///   5. `[signer]` The delegate key, or the owner of the delegate user
+    ///   6. `[]` The delegate user account, only for user delegates
+    TransferFrom {
+        sender_user_id: UserId,
+        sender_account_bump_seed: u8,
+        recipient_user_id: UserId,
+        recipient_account_bump_seed: u8,
+        allowance_bump_seed: u8,
+        delegate: Delegate,
+        /// Ignored for key delegates
+        delegate_account_bump_seed: u8,
+        amount: u64
+    },
+
+    /// Takes the given `amount` of tokens from the `sender` into escrow 
+    /// and schedules its transfer to `recipient` at the `execute_at` unix timestamp.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[writeable]` The sender account
+    ///   3. `[]` The recipient account
+    ///   4. `[writeable]` The scheduled transfer account
+    ///   5. `[signer]` Operator/fee payer account
+    ScheduleTransfer {
+        sender_user_id: UserId,
+        sender_account_bump_seed: u8,
+        recipient_user_id: UserId,
+        recipient_account_bump_seed: u8,
+        schedule_id: u32,
+        scheduled_transfer_bump_seed: u8,
+        amount: u64,
+        execute_at: i64
+    },
+
+    /// Executes a due scheduled transfer. Can be sent by anyone.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The scheduled transfer account
+    ///   1. `[writeable]` The recipient account
+    ///   2. `[writeable]` The operator account, receives the rent of the scheduled transfer account
+    ExecuteScheduledTransfer,
+
+    /// Cancels a scheduled transfer before it is due and returns the escrowed tokens to the sender.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The scheduled transfer account
+    ///   1. `[writeable]` The sender account
+    ///   2. `[writeable, signer]` Operator/fee payer account
+    CancelScheduledTransfer,
+
+    /// Adds the `total_amount` of the `vesting` schedule to the balance of the existing user. 
+    /// Only the vested part of it is available for transfers, withdrawals and blocking.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[]` The token mint address
+    ///   2. `[signer]` Operator/fee payer account
+    GrantVesting {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        vesting: Vesting
+    },
+
+    /// Authorizes the merchant to charge up to `amount_per_period` tokens 
+    /// from the subscriber's balance once every `period` seconds.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[]` The subscriber account
+    ///   3. `[]` The merchant account
+    ///   4. `[writeable]` The subscription account
+    ///   5. `[signer]` Operator/fee payer account, not a signer if the subscriber's owner signs
+    ///   6. `[writeable, signer]` (Optional) The subscriber's owner, pays for the subscription account
+    Subscribe {
+        subscriber_user_id: UserId,
+        subscriber_account_bump_seed: u8,
+        merchant_user_id: UserId,
+        merchant_account_bump_seed: u8,
+        subscription_bump_seed: u8,
+        amount_per_period: u64,
+        period: i64
+    },
+
+    /// Moves the given `amount` of tokens from the subscriber to the merchant 
+    /// if the current period of the subscription has not been charged yet.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The subscription account
+    ///   1. `[writeable]` The subscriber account
+    ///   2. `[writeable]` The merchant account
+    ///   3. `[signer]` The authority, the operator or the merchant's owner
+    ChargeSubscription {
+        amount: u64
+    },
+
+    /// Cancels the subscription and returns its rent to the payer.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The subscription account
+    ///   1. `[]` The subscriber account
+    ///   2. `[]` The merchant account
+    ///   3. `[writeable]` The payer account, that paid for the subscription account
+    ///   4. `[signer]` The authority, the operator or the owner of the subscriber or the merchant
+    CancelSubscription,
+
+    /// Creates an escrow in which two users lock their stakes until the `arbiter`
+    /// resolves it, or until anyone refunds it after the `refund_time` unix timestamp.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[]` The party A account
+    ///   3. `[]` The party B account
+    ///   4. `[writeable]` The escrow account
+    ///   5. `[signer]` Operator/fee payer account
+    CreateEscrow {
+        escrow_id: u32,
+        escrow_bump_seed: u8,
+        party_a_user_id: UserId,
+        party_a_account_bump_seed: u8,
+        party_a_stake: u64,
+        party_b_user_id: UserId,
+        party_b_account_bump_seed: u8,
+        party_b_stake: u64,
+        arbiter: Pubkey,
+        refund_time: i64
+    },
+
+    /// Locks the stake of one of the parties in the escrow.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The escrow account
+    ///   1. `[writeable]` The party account
+    ///   2. `[signer]` The authority, the operator or the party's owner
+    FundEscrow,
+
+    /// Pays `party_a_amount` of the escrowed tokens to party A and the rest to party B.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The escrow account
+    ///   1. `[writeable]` The party A account
+    ///   2. `[writeable]` The party B account
+    ///   3. `[writeable]` The operator account, receives the rent of the escrow account
+    ///   4. `[signer]` The arbiter
+    ResolveEscrow {
+        party_a_amount: u64
+    },
+
+    /// Returns the locked stakes to the parties after the refund time. Can be sent by anyone.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The escrow account
+    ///   1. `[writeable]` The party A account
+    ///   2. `[writeable]` The party B account
+    ///   3. `[writeable]` The operator account, receives the rent of the escrow account
+    RefundEscrow,
+
+    /// Creates an empty game pot that users can join with their stakes.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[writeable]` The pot account
+    ///   3. `[signer]` Operator/fee payer account
+    CreatePot {
+        pot_id: u32,
+        pot_bump_seed: u8
+    },
+
+    /// Moves the given `amount` from the available balance of the user to the pot.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[writeable]` The pot account
+    ///   2. `[signer]` Operator account, not a signer when the owner signs
+    ///   3. `[signer]` (Optional) The user's owner
+    JoinPot {
+        user_id: UserId,
         user_account_bump_seed: u8,
         amount: u64
+    },
+
+    /// Takes the optional `rake` for the house user, splits the rest of the pot between 
+    /// the `winners` proportionally to their weights and closes the pot.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The pot account
+    ///   1. `[writeable, signer]` Operator account, receives the rent of the pot account
+    ///   2. `[writeable]` The house account, only when `rake` is given
+    ///   3. ..3+N `[writeable]` The accounts of the N winners, in the order of `winners`
+    SettlePot {
+        winners: Vec<PotWinner>,
+        rake: Option<PotRake>
+    },
+
+    /// Creates the reward pool of the operator. Once a user is enrolled, every instruction 
+    /// changing its balance must also be given the reward pool account, see `with_reward_pool`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[writeable]` The reward pool account
+    ///   3. `[signer]` Operator/fee payer account
+    CreateRewardPool {
+        reward_pool_bump_seed: u8
+    },
+
+    /// Makes the user earn a part of the rewards distributed from now on, 
+    /// in proportion to its balance.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[writeable]` The reward pool account
+    ///   2. `[signer]` Operator account
+    EnrollRewards {
+        user_id: UserId,
+        user_account_bump_seed: u8
+    },
+
+    /// Shares the given `amount` between all the enrolled users in proportion to their balances.
+    /// Every user receives its share when claiming.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The reward pool account
+    ///   1. `[signer]` Operator account
+    DistributeRewards {
+        amount: u64
+    },
+
+    /// Adds the rewards earned by the user to its balance.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[writeable]` The reward pool account
+    ///   2. `[signer]` Operator account, not a signer when the owner signs
+    ///   3. `[signer]` (Optional) The user's owner
+    Claim {
+        user_id: UserId,
+        user_account_bump_seed: u8
+    },
+
+    /// Lets the user transfer up to `credit_limit` more than its available balance.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writeable]` The user account
+    ///   1. `[]` The token mint address
+    ///   2. `[signer]` Operator/fee payer account
+    SetCreditLimit {
+        user_id: UserId,
+        user_account_bump_seed: u8,
+        credit_limit: u64
+    },
+
+    /// Initializes a ledger shard storing the users from `shard_index * SHARD_SLOT_COUNT` 
+    /// without an account per user. Deposit, Withdraw, Transfer, Block and Unblock accept the 
+    /// shard in place of the user account, ignoring the user account bump seeds.
+    /// The shard address is derived from `SHARD_SEED`, the token mint, the operator and `shard_index`.
+    /// Each call allocates up to `MAX_PERMITTED_DATA_INCREASE` bytes of the shard, so the instruction 
+    /// must be sent `ShardHeader::ALLOCATION_COUNT` times before the shard can be used.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The token mint address
+    ///   2. `[writeable]` The shard account
+    ///   3. `[signer]` Operator/fee payer account
+    InitializeShard {
+        shard_index: u64,
+        shard_bump_seed: u8
+    },
+
+    /// Creates the associated token account of the vault authority, which holds the deposited 
+    /// tokens and is the source of the withdrawals
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[]` The SPL token program account
+    ///   2. `[]` The SPL associated token account program account
+    ///   3. `[]` The rent sysvar
+    ///   4. `[]` The vault authority account
+    ///   5. `[writeable]` The vault token account, the associated token account of the vault authority
+    ///   6. `[]` The token mint address
+    ///   7. `[signer]` Operator/fee payer account
+    InitializeVault {
+        vault_authority_bump_seed: u8
+    },
+
+    /// Grows a user account created by a previous version of the program to the current layout.
+    /// The stored fields are kept, the fields added since are zeroed, and the mint and the user id 
+    /// are stored. Does nothing if the account has the current layout already.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The system program account
+    ///   1. `[writeable]` The user account
+    ///   2. `[]` The token mint address
+    ///   3. `[writeable, signer]` Operator/fee payer account, that pays the rent of the added bytes
+    MigrateUserAccount {
+        user_id: UserId,
+        user_account_bump_seed: u8
+    },
+
+    /// Moves the tokens of the vault of the previous versions of the program, the associated token 
+    /// account of the authority derived like the account of the user 0, to the vault. The vault must 
+    /// be initialized beforehand. Does nothing if the previous vault is empty.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The SPL token program account
+    ///   1. `[]` The vault authority account of the previous versions
+    ///   2. `[writeable]` The vault token account of the previous versions
+    ///   3. `[]` The vault authority account
+    ///   4. `[writeable]` The vault token account
+    ///   5. `[]` The token mint address
+    ///   6. `[signer]` Operator/fee payer account
+    MigrateVault {
+        legacy_vault_authority_bump_seed: u8,
+        vault_authority_bump_seed: u8
     }
 }
 
-#[allow(clippy::too_many_arguments)]
+/// The first byte of the versioned encoding of the instructions. The legacy encoding starts with
+/// the Borsh index of the variant instead, which is below `LEGACY_INSTRUCTION_COUNT`.
+pub const INSTRUCTION_VERSION_MARKER: u8 = 0xff;
+
+/// The version of the encoding written by the instruction builders
+pub const INSTRUCTION_VERSION: u8 = 1;
+
+/// The number of instructions of the first version of the program, which encoded them without a version, 
+/// as the Borsh index of the variant followed by the fields with 32-bit user ids. 
+/// Their tags are these indexes.
+pub const LEGACY_INSTRUCTION_COUNT: u8 = 6;
+
+/// Implements the encoding of `UserAccountInstruction` with explicit tags, so that the variants
+/// can be reordered or inserted without changing the encoding of the existing instructions.
+/// An instruction is encoded as `INSTRUCTION_VERSION_MARKER`, `INSTRUCTION_VERSION`, its tag and its fields
+/// in Borsh, in the order listed here. A tag is never reused.
+macro_rules! instruction_tags {
+    ($($variant:ident { $($field:ident),* } = $tag:literal),*) => {
+        impl UserAccountInstruction {
+            /// The tag identifying the instruction in its encoding
+            pub fn tag(&self) -> u8 {
+                match self {
+                    $(UserAccountInstruction::$variant { .. } => $tag,)*
+                }
+            }
+
+            fn serialize_fields<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                match self {
+                    $(UserAccountInstruction::$variant { $($field),* } => {
+                        $(BorshSerialize::serialize($field, writer)?;)*
+                    })*
+                }
+
+                Ok(())
+            }
+
+            fn deserialize_fields(tag: u8, buf: &mut &[u8]) -> io::Result<Self> {
+                match tag {
+                    $($tag => Ok(UserAccountInstruction::$variant {
+                        $($field: BorshDeserialize::deserialize(buf)?),*
+                    }),)*
+                    _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown instruction tag {}", tag))),
+                }
+            }
+        }
+    };
+}
+
+instruction_tags! {
+    Create { user_id, user_account_bump_seed } = 0,
+    Deposit { user_id, user_account_bump_seed, amount } = 1,
+    Withdraw { user_id, user_account_bump_seed, source_authority_bump_seed, amount } = 2,
+    Transfer {
+        sender_user_id, sender_account_bump_seed, recipient_user_id, recipient_account_bump_seed,
+        amount
+    } = 3,
+    Block { user_id, user_account_bump_seed, amount } = 4,
+    Unblock { user_id, user_account_bump_seed, amount } = 5,
+    SetConfig { config_bump_seed, withdrawal_wallet_delay } = 6,
+    SetWithdrawalWallet { user_id, user_account_bump_seed, config_bump_seed, wallet } = 7,
+    CancelWithdrawalWalletChange { user_id, user_account_bump_seed } = 8,
+    SetOwner { user_id, user_account_bump_seed, owner } = 9,
+    Approve { user_id, user_account_bump_seed, allowance_bump_seed, delegate, amount } = 10,
+    Revoke { user_id, user_account_bump_seed, allowance_bump_seed, delegate } = 11,
+    TransferFrom {
+        sender_user_id, sender_account_bump_seed, recipient_user_id, recipient_account_bump_seed,
+        allowance_bump_seed, delegate, delegate_account_bump_seed, amount
+    } = 12,
+    ScheduleTransfer {
+        sender_user_id, sender_account_bump_seed, recipient_user_id, recipient_account_bump_seed,
+        schedule_id, scheduled_transfer_bump_seed, amount, execute_at
+    } = 13,
+    ExecuteScheduledTransfer {} = 14,
+    CancelScheduledTransfer {} = 15,
+    GrantVesting { user_id, user_account_bump_seed, vesting } = 16,
+    Subscribe {
+        subscriber_user_id, subscriber_account_bump_seed, merchant_user_id,
+        merchant_account_bump_seed, subscription_bump_seed, amount_per_period, period
+    } = 17,
+    ChargeSubscription { amount } = 18,
+    CancelSubscription {} = 19,
+    CreateEscrow {
+        escrow_id, escrow_bump_seed, party_a_user_id, party_a_account_bump_seed, party_a_stake,
+        party_b_user_id, party_b_account_bump_seed, party_b_stake, arbiter, refund_time
+    } = 20,
+    FundEscrow {} = 21,
+    ResolveEscrow { party_a_amount } = 22,
+    RefundEscrow {} = 23,
+    CreatePot { pot_id, pot_bump_seed } = 24,
+    JoinPot { user_id, user_account_bump_seed, amount } = 25,
+    SettlePot { winners, rake } = 26,
+    CreateRewardPool { reward_pool_bump_seed } = 27,
+    EnrollRewards { user_id, user_account_bump_seed } = 28,
+    DistributeRewards { amount } = 29,
+    Claim { user_id, user_account_bump_seed } = 30,
+    SetCreditLimit { user_id, user_account_bump_seed, credit_limit } = 31,
+    InitializeShard { shard_index, shard_bump_seed } = 32,
+    InitializeVault { vault_authority_bump_seed } = 33,
+    MigrateUserAccount { user_id, user_account_bump_seed } = 34,
+    MigrateVault { legacy_vault_authority_bump_seed, vault_authority_bump_seed } = 35
+}
+
+impl BorshSerialize for UserAccountInstruction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION, self.tag()])?;
+        self.serialize_fields(writer)
+    }
+}
+
+impl BorshDeserialize for UserAccountInstruction {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let tag = match u8::deserialize(buf)? {
+            INSTRUCTION_VERSION_MARKER => match u8::deserialize(buf)? {
+                INSTRUCTION_VERSION => u8::deserialize(buf)?,
+                version => return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unsupported instruction version {}", version))),
+            },
+            tag if tag < LEGACY_INSTRUCTION_COUNT => return Self::deserialize_legacy_fields(tag, buf),
+            tag => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown legacy instruction {}", tag))),
+        };
+
+        Self::deserialize_fields(tag, buf)
+    }
+}
+
+impl UserAccountInstruction {
+    /// Decodes the fields of an instruction of the first version of the program, 
+    /// which are the current fields with 32-bit user ids
+    fn deserialize_legacy_fields(tag: u8, buf: &mut &[u8]) -> io::Result<Self> {
+        let legacy_user_id = |buf: &mut &[u8]| u32::deserialize(buf).map(UserId::U32);
+
+        match tag {
+            0 => Ok(UserAccountInstruction::Create {
+                user_id: legacy_user_id(buf)?,
+                user_account_bump_seed: u8::deserialize(buf)?
+            }),
+            1 => Ok(UserAccountInstruction::Deposit {
+                user_id: legacy_user_id(buf)?,
+                user_account_bump_seed: u8::deserialize(buf)?,
+                amount: u64::deserialize(buf)?
+            }),
+            2 => Ok(UserAccountInstruction::Withdraw {
+                user_id: legacy_user_id(buf)?,
+                user_account_bump_seed: u8::deserialize(buf)?,
+                source_authority_bump_seed: u8::deserialize(buf)?,
+                amount: u64::deserialize(buf)?
+            }),
+            3 => Ok(UserAccountInstruction::Transfer {
+                sender_user_id: legacy_user_id(buf)?,
+                sender_account_bump_seed: u8::deserialize(buf)?,
+                recipient_user_id: legacy_user_id(buf)?,
+                recipient_account_bump_seed: u8::deserialize(buf)?,
+                amount: u64::deserialize(buf)?
+            }),
+            4 => Ok(UserAccountInstruction::Block {
+                user_id: legacy_user_id(buf)?,
+                user_account_bump_seed: u8::deserialize(buf)?,
+                amount: u64::deserialize(buf)?
+            }),
+            5 => Ok(UserAccountInstruction::Unblock {
+                user_id: legacy_user_id(buf)?,
+                user_account_bump_seed: u8::deserialize(buf)?,
+                amount: u64::deserialize(buf)?
+            }),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown legacy instruction {}", tag))),
+        }
+    }
+}
+
+/// Derives the address and bump seed of the account of the given user
+pub fn get_user_account_address(
+    program_id: &Pubkey,
+    user_id: UserId,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &user_id.seed()[..],
+            &token_mint_key.to_bytes(),
+            &operator_key.to_bytes(),
+        ],
+        program_id)
+}
+
+/// Derives the address and bump seed of the authority of the token account holding the deposited tokens
+pub fn get_vault_authority_address(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VAULT_SEED,
+            &token_mint_key.to_bytes(),
+            &operator_key.to_bytes(),
+        ],
+        program_id)
+}
+
+/// Derives the address and bump seed of the ledger shard with the given index
+pub fn get_shard_address(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    shard_index: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SHARD_SEED,
+            &token_mint_key.to_bytes(),
+            &operator_key.to_bytes(),
+            &shard_index.to_le_bytes(),
+        ],
+        program_id)
+}
+
+/// Derives the address and bump seed of the allowance of a delegate on the account of a user
+pub fn get_allowance_address(
+    program_id: &Pubkey,
+    user_account_key: &Pubkey,
+    delegate: &Delegate,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ALLOWANCE_SEED,
+            &user_account_key.to_bytes(),
+            &delegate.kind_seed(),
+            &delegate.seed(),
+        ],
+        program_id)
+}
+
+/// Derives the address and bump seed of the authority of the vault of the previous versions of the 
+/// program, which is the address of the account of the user 0
+pub fn get_legacy_vault_authority_address(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> (Pubkey, u8) {
+    get_user_account_address(program_id, UserId::U32(0), token_mint_key, operator_key)
+}
+
 pub fn create(
     program_id: &Pubkey,
-    user_id: u32,
+    user_id: UserId,
     user_account_bump_seed: u8,
     user_account_key: &Pubkey,
     token_mint_key: &Pubkey,
@@ -121,7 +790,7 @@ pub fn create(
 
 pub fn deposit(
     program_id: &Pubkey,
-    user_id: u32,
+    user_id: UserId,
     user_account_bump_seed: u8,
     amount: u64, 
     user_account_key: &Pubkey,
@@ -147,7 +816,7 @@ pub fn deposit(
 
 pub fn withdraw(
     program_id: &Pubkey,
-    user_id: u32,
+    user_id: UserId,
     user_account_bump_seed: u8,
     source_authority_bump_seed: u8,
     amount: u64,
@@ -184,13 +853,49 @@ pub fn withdraw(
     })
 }
 
+/// Same as `withdraw`, but signed by the user's owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    source_authority_bump_seed: u8,
+    amount: u64,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    source_account_key: &Pubkey,
+    source_authority_account_key: &Pubkey,
+    destination_account_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = withdraw(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        source_authority_bump_seed,
+        amount,
+        user_account_key,
+        token_mint_key,
+        source_account_key,
+        source_authority_account_key,
+        destination_account_key,
+        operator_key
+    )?;
+    instruction.accounts[6].is_signer = false;
+    instruction.accounts.push(AccountMeta::new_readonly(*owner_key, true));
+
+    Ok(instruction)
+}
+
 pub fn transfer(
     program_id: &Pubkey,
     token_mint_key: &Pubkey,
-    sender_user_id: u32,
+    sender_user_id: UserId,
     sender_account_bump_seed: u8,
     sender_account_key: &Pubkey,
-    recipient_user_id: u32,
+    recipient_user_id: UserId,
     recipient_account_bump_seed: u8,
     recipient_account_key: &Pubkey,
     amount: u64,
@@ -220,14 +925,48 @@ pub fn transfer(
     })
 }
 
-pub fn block(
+/// Same as `transfer`, but signed by the sender's owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_by_owner(
     program_id: &Pubkey,
-    user_id: u32,
-    user_account_bump_seed: u8,
-    amount: u64, 
-    user_account_key: &Pubkey,
     token_mint_key: &Pubkey,
-    payer_key: &Pubkey,
+    sender_user_id: UserId,
+    sender_account_bump_seed: u8,
+    sender_account_key: &Pubkey,
+    recipient_user_id: UserId,
+    recipient_account_bump_seed: u8,
+    recipient_account_key: &Pubkey,
+    amount: u64,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = transfer(
+        program_id,
+        token_mint_key,
+        sender_user_id,
+        sender_account_bump_seed,
+        sender_account_key,
+        recipient_user_id,
+        recipient_account_bump_seed,
+        recipient_account_key,
+        amount,
+        operator_key
+    )?;
+    instruction.accounts[3].is_signer = false;
+    instruction.accounts.push(AccountMeta::new_readonly(*owner_key, true));
+
+    Ok(instruction)
+}
+
+pub fn block(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    amount: u64, 
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
 
     let instruction_data = UserAccountInstruction::Block { user_id, user_account_bump_seed, amount };
@@ -248,7 +987,7 @@ pub fn block(
 
 pub fn unblock(
     program_id: &Pubkey,
-    user_id: u32,
+    user_id: UserId,
     user_account_bump_seed: u8,
     amount: u64, 
     user_account_key: &Pubkey,
@@ -270,4 +1009,1263 @@ pub fn unblock(
         accounts,
         data,
     })
-}
\ No newline at end of file
+}
+
+/// Same as `create`, deriving the user account from `user_id`
+pub fn create_derived(
+    program_id: &Pubkey,
+    user_id: UserId,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey
+) -> Result<Instruction, ProgramError> {
+
+    let (user_account_key, user_account_bump_seed) = 
+        get_user_account_address(program_id, user_id, token_mint_key, payer_key);
+
+    create(program_id, user_id, user_account_bump_seed, &user_account_key, token_mint_key, payer_key)
+}
+
+/// Same as `deposit`, deriving the user account from `user_id`
+pub fn deposit_derived(
+    program_id: &Pubkey,
+    user_id: UserId,
+    amount: u64,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let (user_account_key, user_account_bump_seed) = 
+        get_user_account_address(program_id, user_id, token_mint_key, payer_key);
+
+    deposit(program_id, user_id, user_account_bump_seed, amount, &user_account_key, token_mint_key, payer_key)
+}
+
+/// Same as `withdraw`, deriving the user account from `user_id` and taking the tokens 
+/// from the vault authority's associated token account
+pub fn withdraw_derived(
+    program_id: &Pubkey,
+    user_id: UserId,
+    amount: u64,
+    token_mint_key: &Pubkey,
+    destination_account_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let (user_account_key, user_account_bump_seed) = 
+        get_user_account_address(program_id, user_id, token_mint_key, payer_key);
+
+    let (source_authority_account_key, source_authority_bump_seed) = 
+        get_vault_authority_address(program_id, token_mint_key, payer_key);
+
+    let source_account_key = get_associated_token_address(&source_authority_account_key, token_mint_key);
+
+    withdraw(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        source_authority_bump_seed,
+        amount,
+        &user_account_key,
+        token_mint_key,
+        &source_account_key,
+        &source_authority_account_key,
+        destination_account_key,
+        payer_key
+    )
+}
+
+/// Same as `transfer`, deriving the sender and recipient accounts from their user ids
+pub fn transfer_derived(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    sender_user_id: UserId,
+    recipient_user_id: UserId,
+    amount: u64,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let (sender_account_key, sender_account_bump_seed) = 
+        get_user_account_address(program_id, sender_user_id, token_mint_key, payer_key);
+
+    let (recipient_account_key, recipient_account_bump_seed) = 
+        get_user_account_address(program_id, recipient_user_id, token_mint_key, payer_key);
+
+    transfer(
+        program_id,
+        token_mint_key,
+        sender_user_id,
+        sender_account_bump_seed,
+        &sender_account_key,
+        recipient_user_id,
+        recipient_account_bump_seed,
+        &recipient_account_key,
+        amount,
+        payer_key
+    )
+}
+
+/// Same as `block`, deriving the user account from `user_id`
+pub fn block_derived(
+    program_id: &Pubkey,
+    user_id: UserId,
+    amount: u64,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let (user_account_key, user_account_bump_seed) = 
+        get_user_account_address(program_id, user_id, token_mint_key, payer_key);
+
+    block(program_id, user_id, user_account_bump_seed, amount, &user_account_key, token_mint_key, payer_key)
+}
+
+/// Same as `unblock`, deriving the user account from `user_id`
+pub fn unblock_derived(
+    program_id: &Pubkey,
+    user_id: UserId,
+    amount: u64,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let (user_account_key, user_account_bump_seed) = 
+        get_user_account_address(program_id, user_id, token_mint_key, payer_key);
+
+    unblock(program_id, user_id, user_account_bump_seed, amount, &user_account_key, token_mint_key, payer_key)
+}
+
+pub fn set_config(
+    program_id: &Pubkey,
+    config_bump_seed: u8,
+    withdrawal_wallet_delay: i64,
+    config_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::SetConfig { config_bump_seed, withdrawal_wallet_delay };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*config_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn set_withdrawal_wallet(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    config_bump_seed: u8,
+    wallet: &Pubkey,
+    user_account_key: &Pubkey,
+    config_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::SetWithdrawalWallet { 
+        user_id, 
+        user_account_bump_seed, 
+        config_bump_seed, 
+        wallet: *wallet 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*config_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn cancel_withdrawal_wallet_change(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CancelWithdrawalWalletChange { user_id, user_account_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `cancel_withdrawal_wallet_change`, but signed by the user's owner instead of the operator.
+pub fn cancel_withdrawal_wallet_change_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = cancel_withdrawal_wallet_change(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        user_account_key,
+        token_mint_key,
+        operator_key
+    )?;
+    instruction.accounts[2].is_signer = false;
+    instruction.accounts.push(AccountMeta::new_readonly(*owner_key, true));
+
+    Ok(instruction)
+}
+
+pub fn set_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    owner: &Pubkey,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::SetOwner { user_id, user_account_bump_seed, owner: *owner };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `set_owner`, but signed by the current owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn set_owner_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    owner: &Pubkey,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    current_owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = set_owner(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        owner,
+        user_account_key,
+        token_mint_key,
+        operator_key
+    )?;
+    instruction.accounts[2].is_signer = false;
+    instruction.accounts.push(AccountMeta::new_readonly(*current_owner_key, true));
+
+    Ok(instruction)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn approve(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    amount: u64,
+    user_account_key: &Pubkey,
+    allowance_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::Approve { 
+        user_id, 
+        user_account_bump_seed, 
+        allowance_bump_seed, 
+        delegate, 
+        amount 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*user_account_key, false),
+        AccountMeta::new(*allowance_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `approve`, but signed by the user's owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn approve_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    amount: u64,
+    user_account_key: &Pubkey,
+    allowance_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = approve(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        allowance_bump_seed,
+        delegate,
+        amount,
+        user_account_key,
+        allowance_account_key,
+        token_mint_key,
+        operator_key
+    )?;
+    instruction.accounts[4].is_signer = false;
+    instruction.accounts.push(AccountMeta::new(*owner_key, true));
+
+    Ok(instruction)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn revoke(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    user_account_key: &Pubkey,
+    allowance_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::Revoke { 
+        user_id, 
+        user_account_bump_seed, 
+        allowance_bump_seed, 
+        delegate 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*user_account_key, false),
+        AccountMeta::new(*allowance_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `revoke`, but signed by the user's owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn revoke_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    user_account_key: &Pubkey,
+    allowance_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = revoke(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        allowance_bump_seed,
+        delegate,
+        user_account_key,
+        allowance_account_key,
+        token_mint_key,
+        operator_key
+    )?;
+    instruction.accounts[3].is_signer = false;
+    instruction.accounts.push(AccountMeta::new_readonly(*owner_key, true));
+
+    Ok(instruction)
+}
+
+/// Creates a `TransferFrom` instruction. `delegate_account` is the delegate user account 
+/// with its bump seed and must be given for user delegates only.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_from(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    sender_user_id: UserId,
+    sender_account_bump_seed: u8,
+    sender_account_key: &Pubkey,
+    recipient_user_id: UserId,
+    recipient_account_bump_seed: u8,
+    recipient_account_key: &Pubkey,
+    allowance_bump_seed: u8,
+    allowance_account_key: &Pubkey,
+    delegate: Delegate,
+    delegate_account: Option<(&Pubkey, u8)>,
+    amount: u64,
+    operator_key: &Pubkey,
+    authority_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let delegate_account_bump_seed = delegate_account.map(|(_, bump_seed)| bump_seed).unwrap_or(0);
+
+    let instruction_data = UserAccountInstruction::TransferFrom { 
+        sender_user_id, 
+        sender_account_bump_seed, 
+        recipient_user_id, 
+        recipient_account_bump_seed, 
+        allowance_bump_seed, 
+        delegate, 
+        delegate_account_bump_seed, 
+        amount 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*sender_account_key, false),
+        AccountMeta::new(*recipient_account_key, false),
+        AccountMeta::new(*allowance_account_key, false),
+        AccountMeta::new_readonly(*operator_key, false),
+        AccountMeta::new_readonly(*authority_key, true)
+    ];
+
+    if let Some((delegate_account_key, _)) = delegate_account {
+        accounts.push(AccountMeta::new_readonly(*delegate_account_key, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_transfer(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    sender_user_id: UserId,
+    sender_account_bump_seed: u8,
+    sender_account_key: &Pubkey,
+    recipient_user_id: UserId,
+    recipient_account_bump_seed: u8,
+    recipient_account_key: &Pubkey,
+    schedule_id: u32,
+    scheduled_transfer_bump_seed: u8,
+    scheduled_transfer_account_key: &Pubkey,
+    amount: u64,
+    execute_at: i64,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::ScheduleTransfer { 
+        sender_user_id, 
+        sender_account_bump_seed, 
+        recipient_user_id, 
+        recipient_account_bump_seed, 
+        schedule_id, 
+        scheduled_transfer_bump_seed, 
+        amount, 
+        execute_at 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*sender_account_key, false),
+        AccountMeta::new_readonly(*recipient_account_key, false),
+        AccountMeta::new(*scheduled_transfer_account_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn execute_scheduled_transfer(
+    program_id: &Pubkey,
+    scheduled_transfer_account_key: &Pubkey,
+    recipient_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::ExecuteScheduledTransfer;
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*scheduled_transfer_account_key, false),
+        AccountMeta::new(*recipient_account_key, false),
+        AccountMeta::new(*operator_key, false)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn cancel_scheduled_transfer(
+    program_id: &Pubkey,
+    scheduled_transfer_account_key: &Pubkey,
+    sender_account_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CancelScheduledTransfer;
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*scheduled_transfer_account_key, false),
+        AccountMeta::new(*sender_account_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn grant_vesting(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    vesting: Vesting,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::GrantVesting { user_id, user_account_bump_seed, vesting };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn subscribe(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    subscriber_user_id: UserId,
+    subscriber_account_bump_seed: u8,
+    subscriber_account_key: &Pubkey,
+    merchant_user_id: UserId,
+    merchant_account_bump_seed: u8,
+    merchant_account_key: &Pubkey,
+    subscription_bump_seed: u8,
+    subscription_account_key: &Pubkey,
+    amount_per_period: u64,
+    period: i64,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::Subscribe { 
+        subscriber_user_id, 
+        subscriber_account_bump_seed, 
+        merchant_user_id, 
+        merchant_account_bump_seed, 
+        subscription_bump_seed, 
+        amount_per_period, 
+        period 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*subscriber_account_key, false),
+        AccountMeta::new_readonly(*merchant_account_key, false),
+        AccountMeta::new(*subscription_account_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Same as `subscribe`, but signed by the subscriber's owner instead of the operator.
+#[allow(clippy::too_many_arguments)]
+pub fn subscribe_by_owner(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    subscriber_user_id: UserId,
+    subscriber_account_bump_seed: u8,
+    subscriber_account_key: &Pubkey,
+    merchant_user_id: UserId,
+    merchant_account_bump_seed: u8,
+    merchant_account_key: &Pubkey,
+    subscription_bump_seed: u8,
+    subscription_account_key: &Pubkey,
+    amount_per_period: u64,
+    period: i64,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = subscribe(
+        program_id,
+        token_mint_key,
+        subscriber_user_id,
+        subscriber_account_bump_seed,
+        subscriber_account_key,
+        merchant_user_id,
+        merchant_account_bump_seed,
+        merchant_account_key,
+        subscription_bump_seed,
+        subscription_account_key,
+        amount_per_period,
+        period,
+        operator_key
+    )?;
+    instruction.accounts[5].is_signer = false;
+    instruction.accounts.push(AccountMeta::new(*owner_key, true));
+
+    Ok(instruction)
+}
+
+pub fn charge_subscription(
+    program_id: &Pubkey,
+    subscription_account_key: &Pubkey,
+    subscriber_account_key: &Pubkey,
+    merchant_account_key: &Pubkey,
+    amount: u64,
+    authority_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::ChargeSubscription { amount };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*subscription_account_key, false),
+        AccountMeta::new(*subscriber_account_key, false),
+        AccountMeta::new(*merchant_account_key, false),
+        AccountMeta::new_readonly(*authority_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn cancel_subscription(
+    program_id: &Pubkey,
+    subscription_account_key: &Pubkey,
+    subscriber_account_key: &Pubkey,
+    merchant_account_key: &Pubkey,
+    payer_key: &Pubkey,
+    authority_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CancelSubscription;
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*subscription_account_key, false),
+        AccountMeta::new_readonly(*subscriber_account_key, false),
+        AccountMeta::new_readonly(*merchant_account_key, false),
+        AccountMeta::new(*payer_key, false),
+        AccountMeta::new_readonly(*authority_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow(
+    program_id: &Pubkey,
+    token_mint_key: &Pubkey,
+    escrow_id: u32,
+    escrow_bump_seed: u8,
+    escrow_account_key: &Pubkey,
+    party_a_user_id: UserId,
+    party_a_account_bump_seed: u8,
+    party_a_account_key: &Pubkey,
+    party_a_stake: u64,
+    party_b_user_id: UserId,
+    party_b_account_bump_seed: u8,
+    party_b_account_key: &Pubkey,
+    party_b_stake: u64,
+    arbiter: &Pubkey,
+    refund_time: i64,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CreateEscrow { 
+        escrow_id, 
+        escrow_bump_seed, 
+        party_a_user_id, 
+        party_a_account_bump_seed, 
+        party_a_stake, 
+        party_b_user_id, 
+        party_b_account_bump_seed, 
+        party_b_stake, 
+        arbiter: *arbiter, 
+        refund_time 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*party_a_account_key, false),
+        AccountMeta::new_readonly(*party_b_account_key, false),
+        AccountMeta::new(*escrow_account_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn fund_escrow(
+    program_id: &Pubkey,
+    escrow_account_key: &Pubkey,
+    party_account_key: &Pubkey,
+    authority_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::FundEscrow;
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow_account_key, false),
+        AccountMeta::new(*party_account_key, false),
+        AccountMeta::new_readonly(*authority_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn resolve_escrow(
+    program_id: &Pubkey,
+    escrow_account_key: &Pubkey,
+    party_a_account_key: &Pubkey,
+    party_b_account_key: &Pubkey,
+    party_a_amount: u64,
+    operator_key: &Pubkey,
+    arbiter_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::ResolveEscrow { party_a_amount };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow_account_key, false),
+        AccountMeta::new(*party_a_account_key, false),
+        AccountMeta::new(*party_b_account_key, false),
+        AccountMeta::new(*operator_key, false),
+        AccountMeta::new_readonly(*arbiter_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn refund_escrow(
+    program_id: &Pubkey,
+    escrow_account_key: &Pubkey,
+    party_a_account_key: &Pubkey,
+    party_b_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::RefundEscrow;
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow_account_key, false),
+        AccountMeta::new(*party_a_account_key, false),
+        AccountMeta::new(*party_b_account_key, false),
+        AccountMeta::new(*operator_key, false)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn create_pot(
+    program_id: &Pubkey,
+    pot_id: u32,
+    pot_bump_seed: u8,
+    pot_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CreatePot { pot_id, pot_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*pot_account_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn join_pot(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    amount: u64,
+    user_account_key: &Pubkey,
+    pot_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::JoinPot { 
+        user_id, 
+        user_account_bump_seed, 
+        amount 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new(*pot_account_key, false),
+        AccountMeta::new_readonly(*operator_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn join_pot_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    amount: u64,
+    user_account_key: &Pubkey,
+    pot_account_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = join_pot(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        amount,
+        user_account_key,
+        pot_account_key,
+        operator_key
+    )?;
+    instruction.accounts[2].is_signer = false;
+    instruction.accounts.push(AccountMeta::new(*owner_key, true));
+
+    Ok(instruction)
+}
+
+/// `winners` pairs every winner with the key of its user account, 
+/// `rake` pairs the rake with the key of the house user account
+pub fn settle_pot(
+    program_id: &Pubkey,
+    pot_account_key: &Pubkey,
+    winners: Vec<(PotWinner, Pubkey)>,
+    rake: Option<(PotRake, Pubkey)>,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut accounts = vec![
+        AccountMeta::new(*pot_account_key, false),
+        AccountMeta::new(*operator_key, true)
+    ];
+
+    let rake = rake.map(|(rake, house_account_key)| {
+        accounts.push(AccountMeta::new(house_account_key, false));
+        rake
+    });
+
+    let winners = winners
+        .into_iter()
+        .map(|(winner, winner_account_key)| {
+            accounts.push(AccountMeta::new(winner_account_key, false));
+            winner
+        })
+        .collect();
+
+    let instruction_data = UserAccountInstruction::SettlePot { winners, rake };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Appends the reward pool account to an instruction changing the balance of an enrolled user
+pub fn with_reward_pool(mut instruction: Instruction, reward_pool_key: &Pubkey) -> Instruction {
+    instruction.accounts.push(AccountMeta::new(*reward_pool_key, false));
+    instruction
+}
+
+pub fn create_reward_pool(
+    program_id: &Pubkey,
+    reward_pool_bump_seed: u8,
+    reward_pool_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::CreateRewardPool { reward_pool_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*reward_pool_account_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn enroll_rewards(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    reward_pool_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::EnrollRewards { user_id, user_account_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new(*reward_pool_account_key, false),
+        AccountMeta::new_readonly(*operator_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn distribute_rewards(
+    program_id: &Pubkey,
+    amount: u64,
+    reward_pool_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::DistributeRewards { amount };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*reward_pool_account_key, false),
+        AccountMeta::new_readonly(*operator_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn claim(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    reward_pool_account_key: &Pubkey,
+    operator_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::Claim { user_id, user_account_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new(*reward_pool_account_key, false),
+        AccountMeta::new_readonly(*operator_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn claim_by_owner(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    reward_pool_account_key: &Pubkey,
+    operator_key: &Pubkey,
+    owner_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let mut instruction = claim(
+        program_id,
+        user_id,
+        user_account_bump_seed,
+        user_account_key,
+        reward_pool_account_key,
+        operator_key
+    )?;
+    instruction.accounts[2].is_signer = false;
+    instruction.accounts.push(AccountMeta::new(*owner_key, true));
+
+    Ok(instruction)
+}
+
+pub fn set_credit_limit(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    credit_limit: u64,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::SetCreditLimit { 
+        user_id, 
+        user_account_bump_seed, 
+        credit_limit 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn initialize_shard(
+    program_id: &Pubkey,
+    shard_index: u64,
+    shard_bump_seed: u8,
+    shard_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::InitializeShard { shard_index, shard_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*shard_account_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn initialize_vault(
+    program_id: &Pubkey,
+    vault_authority_bump_seed: u8,
+    vault_authority_key: &Pubkey,
+    vault_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::InitializeVault { vault_authority_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(*vault_authority_key, false),
+        AccountMeta::new(*vault_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn migrate_user_account(
+    program_id: &Pubkey,
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    user_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::MigrateUserAccount { user_id, user_account_bump_seed };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*user_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn migrate_vault(
+    program_id: &Pubkey,
+    legacy_vault_authority_bump_seed: u8,
+    legacy_vault_authority_key: &Pubkey,
+    legacy_vault_account_key: &Pubkey,
+    vault_authority_bump_seed: u8,
+    vault_authority_key: &Pubkey,
+    vault_account_key: &Pubkey,
+    token_mint_key: &Pubkey,
+    payer_key: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+
+    let instruction_data = UserAccountInstruction::MigrateVault { 
+        legacy_vault_authority_bump_seed, 
+        vault_authority_bump_seed 
+    };
+    let data = instruction_data.try_to_vec().unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*legacy_vault_authority_key, false),
+        AccountMeta::new(*legacy_vault_account_key, false),
+        AccountMeta::new_readonly(*vault_authority_key, false),
+        AccountMeta::new(*vault_account_key, false),
+        AccountMeta::new_readonly(*token_mint_key, false),
+        AccountMeta::new_readonly(*payer_key, true)
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
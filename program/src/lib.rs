@@ -1,5 +1,8 @@
 #![forbid(unsafe_code)]
 
+#[macro_use]
+mod log;
+
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
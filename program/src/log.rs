@@ -0,0 +1,11 @@
+//! Program logging helpers
+
+/// Logs like `msg!`, but only when the `verbose-logging` feature is enabled, as every 
+/// log costs compute units. The arguments are type-checked either way.
+macro_rules! msg_verbose {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logging") {
+            solana_program::msg!($($arg)*);
+        }
+    };
+}
@@ -5,6 +5,37 @@ mod process_withdraw;
 mod process_transfer;
 mod process_block;
 mod process_unblock;
+mod process_set_config;
+mod process_set_withdrawal_wallet;
+mod process_cancel_withdrawal_wallet_change;
+mod process_set_owner;
+mod process_approve;
+mod process_revoke;
+mod process_transfer_from;
+mod process_schedule_transfer;
+mod process_execute_scheduled_transfer;
+mod process_cancel_scheduled_transfer;
+mod process_grant_vesting;
+mod process_subscribe;
+mod process_charge_subscription;
+mod process_cancel_subscription;
+mod process_create_escrow;
+mod process_fund_escrow;
+mod process_resolve_escrow;
+mod process_refund_escrow;
+mod process_create_pot;
+mod process_join_pot;
+mod process_settle_pot;
+mod process_create_reward_pool;
+mod process_enroll_rewards;
+mod process_distribute_rewards;
+mod process_claim;
+mod process_set_credit_limit;
+mod process_initialize_shard;
+mod process_initialize_vault;
+mod process_migrate_user_account;
+mod process_migrate_vault;
+mod shard;
 
 use process_create::*;
 use process_deposit::*;
@@ -12,10 +43,44 @@ use process_transfer::*;
 use process_withdraw::*;
 use process_block::*;
 use process_unblock::*;
+use process_set_config::*;
+use process_set_withdrawal_wallet::*;
+use process_cancel_withdrawal_wallet_change::*;
+use process_set_owner::*;
+use process_approve::*;
+use process_revoke::*;
+use process_transfer_from::*;
+use process_schedule_transfer::*;
+use process_execute_scheduled_transfer::*;
+use process_cancel_scheduled_transfer::*;
+use process_grant_vesting::*;
+use process_subscribe::*;
+use process_charge_subscription::*;
+use process_cancel_subscription::*;
+use process_create_escrow::*;
+use process_fund_escrow::*;
+use process_resolve_escrow::*;
+use process_refund_escrow::*;
+use process_create_pot::*;
+use process_join_pot::*;
+use process_settle_pot::*;
+use process_create_reward_pool::*;
+use process_enroll_rewards::*;
+use process_distribute_rewards::*;
+use process_claim::*;
+use process_set_credit_limit::*;
+use process_initialize_shard::*;
+use process_initialize_vault::*;
+use process_migrate_user_account::*;
+use process_migrate_vault::*;
 
-use crate::instruction::UserAccountInstruction;
+use crate::{
+    error::UserAccountError,
+    instruction::UserAccountInstruction,
+    state::{RewardPool, UserAccount},
+};
 
-use borsh::BorshDeserialize;
+use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
@@ -30,10 +95,10 @@ pub fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    msg!("Beginning processing");
+    msg_verbose!("Beginning processing");
     let instruction = UserAccountInstruction::try_from_slice(instruction_data)
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    msg!("Instruction unpacked");
+    msg_verbose!("Instruction unpacked");
 
     match instruction {
         UserAccountInstruction::Create { user_id, user_account_bump_seed } => {
@@ -108,5 +173,311 @@ pub fn process_instruction(
                 amount
             )
         }
+
+        UserAccountInstruction::SetConfig { config_bump_seed, withdrawal_wallet_delay } => {
+            process_set_config(
+                program_id, 
+                accounts, 
+                config_bump_seed, 
+                withdrawal_wallet_delay
+            )
+        }
+
+        UserAccountInstruction::SetWithdrawalWallet { 
+            user_id, 
+            user_account_bump_seed, 
+            config_bump_seed, 
+            wallet 
+        } => {
+            process_set_withdrawal_wallet(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed, 
+                config_bump_seed, 
+                wallet
+            )
+        }
+
+        UserAccountInstruction::CancelWithdrawalWalletChange { user_id, user_account_bump_seed } => {
+            process_cancel_withdrawal_wallet_change(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed
+            )
+        }
+
+        UserAccountInstruction::SetOwner { user_id, user_account_bump_seed, owner } => {
+            process_set_owner(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed, 
+                owner
+            )
+        }
+
+        UserAccountInstruction::Approve { 
+            user_id, 
+            user_account_bump_seed, 
+            allowance_bump_seed, 
+            delegate, 
+            amount 
+        } => {
+            process_approve(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed, 
+                allowance_bump_seed, 
+                delegate, 
+                amount
+            )
+        }
+
+        UserAccountInstruction::Revoke { 
+            user_id, 
+            user_account_bump_seed, 
+            allowance_bump_seed, 
+            delegate 
+        } => {
+            process_revoke(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed, 
+                allowance_bump_seed, 
+                delegate
+            )
+        }
+
+        UserAccountInstruction::TransferFrom { 
+            sender_user_id, 
+            sender_account_bump_seed, 
+            recipient_user_id, 
+            recipient_account_bump_seed, 
+            allowance_bump_seed, 
+            delegate, 
+            delegate_account_bump_seed, 
+            amount 
+        } => {
+            process_transfer_from(
+                program_id, 
+                accounts, 
+                sender_user_id, 
+                sender_account_bump_seed, 
+                recipient_user_id, 
+                recipient_account_bump_seed, 
+                allowance_bump_seed, 
+                delegate, 
+                delegate_account_bump_seed, 
+                amount
+            )
+        }
+
+        UserAccountInstruction::ScheduleTransfer { 
+            sender_user_id, 
+            sender_account_bump_seed, 
+            recipient_user_id, 
+            recipient_account_bump_seed, 
+            schedule_id, 
+            scheduled_transfer_bump_seed, 
+            amount, 
+            execute_at 
+        } => {
+            process_schedule_transfer(
+                program_id, 
+                accounts, 
+                sender_user_id, 
+                sender_account_bump_seed, 
+                recipient_user_id, 
+                recipient_account_bump_seed, 
+                schedule_id, 
+                scheduled_transfer_bump_seed, 
+                amount, 
+                execute_at
+            )
+        }
+
+        UserAccountInstruction::ExecuteScheduledTransfer => {
+            process_execute_scheduled_transfer(program_id, accounts)
+        }
+
+        UserAccountInstruction::CancelScheduledTransfer => {
+            process_cancel_scheduled_transfer(program_id, accounts)
+        }
+
+        UserAccountInstruction::GrantVesting { user_id, user_account_bump_seed, vesting } => {
+            process_grant_vesting(
+                program_id, 
+                accounts, 
+                user_id, 
+                user_account_bump_seed, 
+                vesting
+            )
+        }
+
+        UserAccountInstruction::Subscribe { 
+            subscriber_user_id, 
+            subscriber_account_bump_seed, 
+            merchant_user_id, 
+            merchant_account_bump_seed, 
+            subscription_bump_seed, 
+            amount_per_period, 
+            period 
+        } => {
+            process_subscribe(
+                program_id, 
+                accounts, 
+                subscriber_user_id, 
+                subscriber_account_bump_seed, 
+                merchant_user_id, 
+                merchant_account_bump_seed, 
+                subscription_bump_seed, 
+                amount_per_period, 
+                period
+            )
+        }
+
+        UserAccountInstruction::ChargeSubscription { amount } => {
+            process_charge_subscription(program_id, accounts, amount)
+        }
+
+        UserAccountInstruction::CancelSubscription => {
+            process_cancel_subscription(program_id, accounts)
+        }
+
+        UserAccountInstruction::CreateEscrow { 
+            escrow_id, 
+            escrow_bump_seed, 
+            party_a_user_id, 
+            party_a_account_bump_seed, 
+            party_a_stake, 
+            party_b_user_id, 
+            party_b_account_bump_seed, 
+            party_b_stake, 
+            arbiter, 
+            refund_time 
+        } => {
+            process_create_escrow(
+                program_id, 
+                accounts, 
+                escrow_id, 
+                escrow_bump_seed, 
+                party_a_user_id, 
+                party_a_account_bump_seed, 
+                party_a_stake, 
+                party_b_user_id, 
+                party_b_account_bump_seed, 
+                party_b_stake, 
+                arbiter, 
+                refund_time
+            )
+        }
+
+        UserAccountInstruction::FundEscrow => {
+            process_fund_escrow(program_id, accounts)
+        }
+
+        UserAccountInstruction::ResolveEscrow { party_a_amount } => {
+            process_resolve_escrow(program_id, accounts, party_a_amount)
+        }
+
+        UserAccountInstruction::RefundEscrow => {
+            process_refund_escrow(program_id, accounts)
+        }
+
+        UserAccountInstruction::CreatePot { pot_id, pot_bump_seed } => {
+            process_create_pot(program_id, accounts, pot_id, pot_bump_seed)
+        }
+
+        UserAccountInstruction::JoinPot { user_id, user_account_bump_seed, amount } => {
+            process_join_pot(program_id, accounts, user_id, user_account_bump_seed, amount)
+        }
+
+        UserAccountInstruction::SettlePot { winners, rake } => {
+            process_settle_pot(program_id, accounts, winners, rake)
+        }
+
+        UserAccountInstruction::CreateRewardPool { reward_pool_bump_seed } => {
+            process_create_reward_pool(program_id, accounts, reward_pool_bump_seed)
+        }
+
+        UserAccountInstruction::EnrollRewards { user_id, user_account_bump_seed } => {
+            process_enroll_rewards(program_id, accounts, user_id, user_account_bump_seed)
+        }
+
+        UserAccountInstruction::DistributeRewards { amount } => {
+            process_distribute_rewards(program_id, accounts, amount)
+        }
+
+        UserAccountInstruction::Claim { user_id, user_account_bump_seed } => {
+            process_claim(program_id, accounts, user_id, user_account_bump_seed)
+        }
+
+        UserAccountInstruction::SetCreditLimit { user_id, user_account_bump_seed, credit_limit } => {
+            process_set_credit_limit(program_id, accounts, user_id, user_account_bump_seed, credit_limit)
+        }
+
+        UserAccountInstruction::InitializeShard { shard_index, shard_bump_seed } => {
+            process_initialize_shard(program_id, accounts, shard_index, shard_bump_seed)
+        }
+
+        UserAccountInstruction::InitializeVault { vault_authority_bump_seed } => {
+            process_initialize_vault(program_id, accounts, vault_authority_bump_seed)
+        }
+
+        UserAccountInstruction::MigrateUserAccount { user_id, user_account_bump_seed } => {
+            process_migrate_user_account(program_id, accounts, user_id, user_account_bump_seed)
+        }
+
+        UserAccountInstruction::MigrateVault { legacy_vault_authority_bump_seed, vault_authority_bump_seed } => {
+            process_migrate_vault(program_id, accounts, legacy_vault_authority_bump_seed, vault_authority_bump_seed)
+        }
+    }
+}
+
+/// Closes a program account by moving all its lamports to `destination_account`
+fn close_account(account: &AccountInfo, destination_account: &AccountInfo) -> ProgramResult {
+    let lamports = account.lamports();
+    **destination_account.lamports.borrow_mut() = destination_account.lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    **account.lamports.borrow_mut() = 0;
+    account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// Settles the rewards earned by the `previous_balance` of an enrolled user and moves
+/// its shares in the reward pool to the current balance. Must be called whenever the
+/// balance changes; the reward pool the user is enrolled in is looked up in `accounts`.
+fn update_rewards(
+    accounts: &[AccountInfo],
+    user_account_object: &mut UserAccount,
+    previous_balance: u64) -> ProgramResult {
+
+    if user_account_object.reward.reward_pool == Pubkey::default() {
+        return Ok(());
     }
-}
\ No newline at end of file
+
+    let reward_pool_account = accounts
+        .iter()
+        .find(|account| *account.key == user_account_object.reward.reward_pool)
+        .ok_or_else(|| {
+            msg!("The reward pool {} is not provided", user_account_object.reward.reward_pool);
+            UserAccountError::MissingRewardPool
+        })?;
+
+    let mut reward_pool_object = RewardPool::try_from_slice(&reward_pool_account.data.borrow())?;
+
+    user_account_object.reward.settle(previous_balance, reward_pool_object.reward_per_share)?;
+    reward_pool_object.total_shares = reward_pool_object.total_shares
+        .checked_sub(previous_balance)
+        .and_then(|total_shares| total_shares.checked_add(user_account_object.balance))
+        .ok_or(ProgramError::InvalidArgument)?;
+    reward_pool_object.serialize(&mut &mut reward_pool_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use crate::state::{Allowance, Delegate, UserAccount, UserId, ALLOWANCE_SEED};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+pub fn process_approve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let user_account = next_account_info(accounts_iter)?;
+    let allowance_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_account_object = *UserAccount::load(&user_account.data.borrow())?;
+
+    let payer_account = if operator_account.is_signer {
+        operator_account
+    } else {
+        match owner_account {
+            Some(owner_account) if user_account_object.is_signed_by_owner(owner_account) => owner_account,
+            _ => {
+                msg!("Neither the operator nor the user's owner signed the approval");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+    };
+
+    let delegate_seed = delegate.seed();
+    let allowance_seeds = [
+        ALLOWANCE_SEED,
+        &user_account_key.to_bytes(),
+        &delegate.kind_seed(),
+        &delegate_seed,
+        &[allowance_bump_seed]
+    ];
+
+    let allowance_account_key = Pubkey::create_program_address(
+        &allowance_seeds,
+        program_id)?;
+
+    if *allowance_account.key != allowance_account_key {
+        msg!("Provided allowance account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let allowance_object = Allowance { delegate, amount };
+    let allowance_data = allowance_object.try_to_vec()?;
+
+    if allowance_account.data_is_empty() {
+        let rent = Rent::get()?;
+        let ix = create_account(
+            payer_account.key,
+            allowance_account.key,
+            rent.minimum_balance(allowance_data.len()),
+            allowance_data.len() as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &ix,
+            &[
+                payer_account.clone(),
+                allowance_account.clone(),
+                system_account.clone()
+            ],
+            &[&allowance_seeds],
+        )?;
+
+        msg!("The allowance account is created");
+    }
+
+    allowance_account.data.borrow_mut().copy_from_slice(&allowance_data);
+
+    msg!("User [id={}] approved {:?} to spend {}", user_id, allowance_object.delegate, amount);
+
+    Ok(())
+}
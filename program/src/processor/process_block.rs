@@ -1,18 +1,20 @@
-use crate::state::UserAccount;
+use crate::state::{UserAccount, UserId};
+use super::shard::{is_shard, load_shard_slot};
 
-use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
     msg
 };
 
 pub fn process_block(
     program_id: &Pubkey, 
     accounts: &[AccountInfo], 
-    user_id: u32, 
+    user_id: UserId, 
     user_account_bump_seed: u8,
     amount: u64) -> ProgramResult {
 
@@ -26,9 +28,13 @@ pub fn process_block(
         msg!("Provided operator account is not a signer");
         return Err(ProgramError::MissingRequiredSignature);   
     }
+
+    if is_shard(program_id, user_account) {
+        return process_shard_block(program_id, user_account, token_mint_account, operator_account, user_id, amount);
+    }
     
     let seeds = [
-        &user_id.to_le_bytes()[..], 
+        &user_id.seed()[..], 
         &token_mint_account.key.to_bytes(), 
         &operator_account.key.to_bytes(), 
         &[user_account_bump_seed]
@@ -43,20 +49,45 @@ pub fn process_block(
         return Err(ProgramError::InvalidAccountData);   
     }
 
-    let mut user_account_object = UserAccount::try_from_slice(&user_account.data.borrow())?;
-    msg!("Updating user account [id={}, balance={}, blocked_amount={}]", 
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    msg_verbose!("Updating user account [id={}, balance={}, blocked_amount={}]", 
         user_id, user_account_object.balance, user_account_object.blocked_amount);
 
-    if user_account_object.blocked_amount + amount > user_account_object.balance {
+    if user_account_object.available_balance(Clock::get()?.unix_timestamp) < amount {
         msg!("The given amount is greater than the available balance");
         return Err(ProgramError::InvalidArgument);   
     }
 
     user_account_object.blocked_amount += amount;
-    user_account_object.serialize(&mut &mut user_account.data.borrow_mut()[..])?;
 
-    msg!("Blocked {}. Updated user account [id={}, balance={}, blocked_amount={}]", 
+    msg_verbose!("Blocked {}. Updated user account [id={}, balance={}, blocked_amount={}]", 
         amount, user_id, user_account_object.balance, user_account_object.blocked_amount);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Blocks the given `amount` of a user stored in a ledger shard
+fn process_shard_block(
+    program_id: &Pubkey,
+    shard_account: &AccountInfo,
+    token_mint_account: &AccountInfo,
+    operator_account: &AccountInfo,
+    user_id: UserId,
+    amount: u64) -> ProgramResult {
+
+    let mut shard_slot = load_shard_slot(program_id, shard_account, token_mint_account.key, operator_account.key, user_id)?;
+
+    if shard_slot.balance.saturating_sub(shard_slot.blocked_amount) < amount {
+        msg!("The given amount is greater than the available balance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    shard_slot.blocked_amount += amount;
+
+    msg_verbose!("Blocked {}. Updated shard slot [id={}, balance={}, blocked_amount={}]", 
+        amount, user_id, shard_slot.balance, shard_slot.blocked_amount);
+
+    Ok(())
+}
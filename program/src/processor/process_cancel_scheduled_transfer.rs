@@ -0,0 +1,65 @@
+use crate::state::{ScheduledTransfer, TaggedAccount};
+use super::{close_account, process_transfer::credit_recipient};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_cancel_scheduled_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let scheduled_transfer_account = next_account_info(accounts_iter)?;
+    let sender_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if scheduled_transfer_account.owner != program_id {
+        msg!("Provided scheduled transfer account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let scheduled_transfer_object = ScheduledTransfer::load(&scheduled_transfer_account.data.borrow())?;
+
+    if *operator_account.key != scheduled_transfer_object.operator {
+        msg!("Provided operator account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *sender_account.key != scheduled_transfer_object.sender_account {
+        msg!("Provided sender account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if Clock::get()?.unix_timestamp >= scheduled_transfer_object.execute_at {
+        msg!("The scheduled transfer is due and can no longer be cancelled");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    credit_recipient(
+        accounts,
+        sender_account,
+        scheduled_transfer_object.sender_user_id,
+        scheduled_transfer_object.amount)?;
+
+    close_account(scheduled_transfer_account, operator_account)?;
+
+    msg!("Cancelled the scheduled transfer of {} from user {} to user {}",
+        scheduled_transfer_object.amount,
+        scheduled_transfer_object.sender_user_id,
+        scheduled_transfer_object.recipient_user_id);
+
+    Ok(())
+}
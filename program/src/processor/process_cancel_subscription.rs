@@ -0,0 +1,65 @@
+use crate::state::{Subscription, TaggedAccount, UserAccount};
+use super::close_account;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_cancel_subscription(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let subscription_account = next_account_info(accounts_iter)?;
+    let subscriber_account = next_account_info(accounts_iter)?;
+    let merchant_account = next_account_info(accounts_iter)?;
+    let payer_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if subscription_account.owner != program_id {
+        msg!("Provided subscription account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let subscription_object = Subscription::load(&subscription_account.data.borrow())?;
+
+    if *subscriber_account.key != subscription_object.subscriber_account {
+        msg!("Provided subscriber account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *merchant_account.key != subscription_object.merchant_account {
+        msg!("Provided merchant account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *payer_account.key != subscription_object.payer {
+        msg!("Provided payer account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let subscriber_account_object = *UserAccount::load(&subscriber_account.data.borrow())?;
+    let merchant_account_object = *UserAccount::load(&merchant_account.data.borrow())?;
+
+    let signed_by_operator = authority_account.is_signer 
+        && *authority_account.key == subscription_object.operator;
+
+    if !signed_by_operator
+        && !subscriber_account_object.is_signed_by_owner(authority_account)
+        && !merchant_account_object.is_signed_by_owner(authority_account) {
+        msg!("Neither the operator nor the owner of the subscriber or the merchant signed the cancellation");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    close_account(subscription_account, payer_account)?;
+
+    msg!("Cancelled the subscription of user {} to user {}",
+        subscription_object.subscriber_user_id, subscription_object.merchant_user_id);
+
+    Ok(())
+}
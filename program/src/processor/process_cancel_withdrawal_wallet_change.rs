@@ -0,0 +1,64 @@
+use crate::{error::UserAccountError, state::{UserAccount, UserId}};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_cancel_withdrawal_wallet_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    if !operator_account.is_signer
+        && !matches!(owner_account, Some(owner_account) if user_account_object.is_signed_by_owner(owner_account)) {
+        msg!("Neither the operator nor the user's owner signed the cancellation");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    user_account_object.apply_pending_withdrawal_wallet(Clock::get()?.unix_timestamp);
+
+    if user_account_object.pending_withdrawal_wallet == Pubkey::default() {
+        msg!("There is no pending withdrawal wallet change");
+        return Err(UserAccountError::NoPendingWithdrawalWalletChange.into());
+    }
+
+    user_account_object.pending_withdrawal_wallet = Pubkey::default();
+    user_account_object.withdrawal_wallet_change_time = 0;
+
+    msg!("Cancelled the withdrawal wallet change of user account [id={}]", user_id);
+
+    Ok(())
+}
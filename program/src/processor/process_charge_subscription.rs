@@ -0,0 +1,85 @@
+use crate::state::{Subscription, TaggedAccount, UserAccount};
+use super::process_transfer::{credit_recipient, debit_sender};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_charge_subscription(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let subscription_account = next_account_info(accounts_iter)?;
+    let subscriber_account = next_account_info(accounts_iter)?;
+    let merchant_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if subscription_account.owner != program_id {
+        msg!("Provided subscription account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut subscription_object = Subscription::load(&subscription_account.data.borrow())?;
+
+    if *subscriber_account.key != subscription_object.subscriber_account {
+        msg!("Provided subscriber account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *merchant_account.key != subscription_object.merchant_account {
+        msg!("Provided merchant account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let merchant_account_object = *UserAccount::load(&merchant_account.data.borrow())?;
+
+    let signed_by_operator = authority_account.is_signer 
+        && *authority_account.key == subscription_object.operator;
+
+    if !signed_by_operator
+        && !merchant_account_object.is_signed_by_owner(authority_account) {
+        msg!("Neither the operator nor the merchant's owner signed the charge");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if amount > subscription_object.amount_per_period {
+        msg!("The given amount is greater than the amount per period");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if now < subscription_object.next_charge_time {
+        msg!("The subscription cannot be charged before {}", subscription_object.next_charge_time);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Skip the periods that were not charged, so that a late charge does not allow another one right away
+    let missed_periods = (now - subscription_object.next_charge_time) / subscription_object.period;
+    subscription_object.next_charge_time = (missed_periods + 1)
+        .checked_mul(subscription_object.period)
+        .and_then(|elapsed_time| subscription_object.next_charge_time.checked_add(elapsed_time))
+        .ok_or(ProgramError::InvalidArgument)?;
+    subscription_object.serialize(&mut &mut subscription_account.data.borrow_mut()[..])?;
+
+    debit_sender(accounts, subscriber_account, subscription_object.subscriber_user_id, amount)?;
+    credit_recipient(accounts, merchant_account, subscription_object.merchant_user_id, amount)?;
+
+    msg!("Charged {} for the subscription of user {} to user {}, next charge at {}",
+        amount,
+        subscription_object.subscriber_user_id,
+        subscription_object.merchant_user_id,
+        subscription_object.next_charge_time);
+
+    Ok(())
+}
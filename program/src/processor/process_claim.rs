@@ -0,0 +1,84 @@
+use crate::state::{RewardPool, UserAccount, UserId};
+use super::update_rewards;
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_claim(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let reward_pool_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    if *reward_pool_account.key != user_account_object.reward.reward_pool {
+        msg!("The user is not enrolled in the provided reward pool");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let reward_pool_object = RewardPool::try_from_slice(&reward_pool_account.data.borrow())?;
+
+    let user_seeds = [
+        &user_id.seed()[..],
+        &reward_pool_object.token_mint.to_bytes(),
+        &reward_pool_object.operator.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &user_seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let signed_by_operator = operator_account.is_signer
+        && *operator_account.key == reward_pool_object.operator;
+
+    if !signed_by_operator
+        && !matches!(owner_account, Some(owner_account) if user_account_object.is_signed_by_owner(owner_account)) {
+        msg!("Neither the operator nor the user's owner signed the claim");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Settle the rewards up to now without changing the balance
+    let previous_balance = user_account_object.balance;
+    update_rewards(accounts, user_account_object, previous_balance)?;
+
+    let amount = user_account_object.reward.pending_amount;
+    user_account_object.reward.pending_amount = 0;
+    user_account_object.balance = user_account_object.balance
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // The claimed rewards become shares, like any other credit
+    update_rewards(accounts, user_account_object, previous_balance)?;
+
+    let mut reward_pool_object = RewardPool::try_from_slice(&reward_pool_account.data.borrow())?;
+    reward_pool_object.unclaimed_amount = reward_pool_object.unclaimed_amount
+        .checked_sub(amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    reward_pool_object.serialize(&mut &mut reward_pool_account.data.borrow_mut()[..])?;
+
+    msg!("User {} claimed {}. Updated user account [balance={}, blocked_amount={}]",
+        user_id, amount, user_account_object.balance, user_account_object.blocked_amount);
+
+    Ok(())
+}
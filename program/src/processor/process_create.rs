@@ -1,6 +1,5 @@
-use crate::state::UserAccount;
+use crate::{error::UserAccountError, state::{UserAccount, UserId}};
 
-use std::mem::size_of;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -15,7 +14,7 @@ use solana_program::{
 pub fn process_create(
     program_id: &Pubkey, 
     accounts: &[AccountInfo], 
-    user_id: u32, 
+    user_id: UserId, 
     user_account_bump_seed: u8) -> ProgramResult {
     
     let accounts_iter = &mut accounts.iter();
@@ -29,9 +28,14 @@ pub fn process_create(
         msg!("Provided operator account is not a signer");
         return Err(ProgramError::MissingRequiredSignature);   
     }
+
+    if user_id.is_reserved() {
+        msg!("User id {} is reserved", user_id);
+        return Err(UserAccountError::ReservedUserId.into());
+    }
     
     let seeds = [
-        &user_id.to_le_bytes()[..], 
+        &user_id.seed()[..], 
         &token_mint_account.key.to_bytes(), 
         &operator_account.key.to_bytes(), 
         &[user_account_bump_seed]
@@ -46,7 +50,7 @@ pub fn process_create(
         return Err(ProgramError::InvalidAccountData);   
     }
 
-    let user_account_size = size_of::<UserAccount>();
+    let user_account_size = UserAccount::LEN;
 
     let rent = Rent::get()?;
     let ix = create_account(
@@ -67,6 +71,11 @@ pub fn process_create(
         &[&seeds],
     )?;
 
+    let mut user_account_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_account_data)?;
+    user_account_object.token_mint = *token_mint_account.key;
+    user_account_object.user_id = user_id.into();
+
     msg!("The user account is created");
 
     Ok(())
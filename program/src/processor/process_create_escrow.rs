@@ -0,0 +1,145 @@
+use crate::state::{Escrow, TaggedAccount, UserId, ESCROW_SEED};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_create_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    escrow_id: u32,
+    escrow_bump_seed: u8,
+    party_a_user_id: UserId,
+    party_a_account_bump_seed: u8,
+    party_a_stake: u64,
+    party_b_user_id: UserId,
+    party_b_account_bump_seed: u8,
+    party_b_stake: u64,
+    arbiter: Pubkey,
+    refund_time: i64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let party_a_account = next_account_info(accounts_iter)?;
+    let party_b_account = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if party_a_user_id == party_b_user_id {
+        msg!("The escrow parties must be different users");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if refund_time <= Clock::get()?.unix_timestamp {
+        msg!("The refund time must be in the future");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let party_a_seeds = [
+        &party_a_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[party_a_account_bump_seed]
+    ];
+
+    let party_a_account_key = Pubkey::create_program_address(
+        &party_a_seeds,
+        program_id)?;
+
+    if *party_a_account.key != party_a_account_key {
+        msg!("Provided party A account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let party_b_seeds = [
+        &party_b_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[party_b_account_bump_seed]
+    ];
+
+    let party_b_account_key = Pubkey::create_program_address(
+        &party_b_seeds,
+        program_id)?;
+
+    if *party_b_account.key != party_b_account_key {
+        msg!("Provided party B account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let escrow_seeds = [
+        ESCROW_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &escrow_id.to_le_bytes(),
+        &[escrow_bump_seed]
+    ];
+
+    let escrow_account_key = Pubkey::create_program_address(
+        &escrow_seeds,
+        program_id)?;
+
+    if *escrow_account.key != escrow_account_key {
+        msg!("Provided escrow account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let escrow_object = Escrow {
+        account_type: Escrow::ACCOUNT_TYPE,
+        party_a_user_id,
+        party_a_account: party_a_account_key,
+        party_a_stake,
+        party_a_funded: 0,
+        party_b_user_id,
+        party_b_account: party_b_account_key,
+        party_b_stake,
+        party_b_funded: 0,
+        arbiter,
+        refund_time,
+        operator: *operator_account.key,
+    };
+    let escrow_data = escrow_object.try_to_vec()?;
+
+    let rent = Rent::get()?;
+    let ix = create_account(
+        operator_account.key,
+        escrow_account.key,
+        rent.minimum_balance(escrow_data.len()),
+        escrow_data.len() as u64,
+        program_id,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            operator_account.clone(),
+            escrow_account.clone(),
+            system_account.clone()
+        ],
+        &[&escrow_seeds],
+    )?;
+
+    escrow_account.data.borrow_mut().copy_from_slice(&escrow_data);
+
+    msg!("Created escrow [id={}] between user {} staking {} and user {} staking {}, arbiter {}",
+        escrow_id, party_a_user_id, party_a_stake, party_b_user_id, party_b_stake, arbiter);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::state::{Pot, TaggedAccount, POT_SEED};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+pub fn process_create_pot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    pot_id: u32,
+    pot_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let pot_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let pot_seeds = [
+        POT_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &pot_id.to_le_bytes(),
+        &[pot_bump_seed]
+    ];
+
+    let pot_account_key = Pubkey::create_program_address(
+        &pot_seeds,
+        program_id)?;
+
+    if *pot_account.key != pot_account_key {
+        msg!("Provided pot account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent = Rent::get()?;
+    let ix = create_account(
+        operator_account.key,
+        pot_account.key,
+        rent.minimum_balance(Pot::LEN),
+        Pot::LEN as u64,
+        program_id,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            operator_account.clone(),
+            pot_account.clone(),
+            system_account.clone()
+        ],
+        &[&pot_seeds],
+    )?;
+
+    let pot_object = Pot {
+        account_type: Pot::ACCOUNT_TYPE,
+        token_mint: *token_mint_account.key,
+        operator: *operator_account.key,
+        amount: 0,
+    };
+    pot_object.serialize(&mut &mut pot_account.data.borrow_mut()[..])?;
+
+    msg!("Created pot [id={}]", pot_id);
+
+    Ok(())
+}
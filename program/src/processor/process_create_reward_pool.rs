@@ -0,0 +1,79 @@
+use crate::state::{RewardPool, REWARD_POOL_SEED};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+pub fn process_create_reward_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    reward_pool_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let reward_pool_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let reward_pool_seeds = [
+        REWARD_POOL_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[reward_pool_bump_seed]
+    ];
+
+    let reward_pool_account_key = Pubkey::create_program_address(
+        &reward_pool_seeds,
+        program_id)?;
+
+    if *reward_pool_account.key != reward_pool_account_key {
+        msg!("Provided reward pool account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent = Rent::get()?;
+    let ix = create_account(
+        operator_account.key,
+        reward_pool_account.key,
+        rent.minimum_balance(RewardPool::LEN),
+        RewardPool::LEN as u64,
+        program_id,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            operator_account.clone(),
+            reward_pool_account.clone(),
+            system_account.clone()
+        ],
+        &[&reward_pool_seeds],
+    )?;
+
+    let reward_pool_object = RewardPool {
+        token_mint: *token_mint_account.key,
+        operator: *operator_account.key,
+        reward_per_share: 0,
+        total_shares: 0,
+        unclaimed_amount: 0,
+    };
+    reward_pool_object.serialize(&mut &mut reward_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Created the reward pool");
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::{error::UserAccountError, state::{RewardPool, REWARD_PRECISION}};
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_distribute_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let reward_pool_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if reward_pool_account.owner != program_id {
+        msg!("Provided reward pool account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut reward_pool_object = RewardPool::try_from_slice(&reward_pool_account.data.borrow())?;
+
+    if !operator_account.is_signer || *operator_account.key != reward_pool_object.operator {
+        msg!("The reward pool operator did not sign the distribution");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if reward_pool_object.total_shares == 0 {
+        msg!("No balance is enrolled in the reward pool");
+        return Err(UserAccountError::NoEnrolledBalance.into());
+    }
+
+    reward_pool_object.reward_per_share = reward_pool_object.reward_per_share
+        .checked_add(amount as u128 * REWARD_PRECISION / reward_pool_object.total_shares as u128)
+        .ok_or(ProgramError::InvalidArgument)?;
+    reward_pool_object.unclaimed_amount = reward_pool_object.unclaimed_amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    reward_pool_object.serialize(&mut &mut reward_pool_account.data.borrow_mut()[..])?;
+
+    msg!("Distributed {} over {} shares, reward per share {}",
+        amount, reward_pool_object.total_shares, reward_pool_object.reward_per_share);
+
+    Ok(())
+}
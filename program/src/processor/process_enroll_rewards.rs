@@ -0,0 +1,77 @@
+use crate::state::{RewardPool, UserAccount, UserId};
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_enroll_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let reward_pool_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if reward_pool_account.owner != program_id {
+        msg!("Provided reward pool account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut reward_pool_object = RewardPool::try_from_slice(&reward_pool_account.data.borrow())?;
+
+    if *operator_account.key != reward_pool_object.operator {
+        msg!("Provided operator account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_seeds = [
+        &user_id.seed()[..],
+        &reward_pool_object.token_mint.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &user_seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    if user_account_object.reward.reward_pool != Pubkey::default() {
+        msg!("The user is already enrolled in a reward pool");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The balance held before the enrolment earns only the rewards distributed after it
+    user_account_object.reward.reward_pool = *reward_pool_account.key;
+    user_account_object.reward.reward_per_share = reward_pool_object.reward_per_share.to_le_bytes();
+
+    reward_pool_object.total_shares = reward_pool_object.total_shares
+        .checked_add(user_account_object.balance)
+        .ok_or(ProgramError::InvalidArgument)?;
+    reward_pool_object.serialize(&mut &mut reward_pool_account.data.borrow_mut()[..])?;
+
+    msg!("User {} enrolled in the reward pool with {} shares", user_id, user_account_object.balance);
+
+    Ok(())
+}
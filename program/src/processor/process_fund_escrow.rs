@@ -0,0 +1,72 @@
+use crate::state::{Escrow, TaggedAccount, UserAccount};
+use super::process_transfer::debit_sender;
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_fund_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let party_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if escrow_account.owner != program_id {
+        msg!("Provided escrow account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut escrow_object = Escrow::load(&escrow_account.data.borrow())?;
+
+    if Clock::get()?.unix_timestamp >= escrow_object.refund_time {
+        msg!("The escrow can no longer be funded after {}", escrow_object.refund_time);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (party_user_id, stake) = if *party_account.key == escrow_object.party_a_account {
+        if escrow_object.party_a_funded > 0 {
+            msg!("Party A has already funded the escrow");
+            return Err(ProgramError::InvalidArgument);
+        }
+        escrow_object.party_a_funded = escrow_object.party_a_stake;
+        (escrow_object.party_a_user_id, escrow_object.party_a_stake)
+    } else if *party_account.key == escrow_object.party_b_account {
+        if escrow_object.party_b_funded > 0 {
+            msg!("Party B has already funded the escrow");
+            return Err(ProgramError::InvalidArgument);
+        }
+        escrow_object.party_b_funded = escrow_object.party_b_stake;
+        (escrow_object.party_b_user_id, escrow_object.party_b_stake)
+    } else {
+        msg!("Provided party account is not a party of the escrow");
+        return Err(ProgramError::InvalidAccountData);
+    };
+
+    let party_account_object = *UserAccount::load(&party_account.data.borrow())?;
+
+    let signed_by_operator = authority_account.is_signer
+        && *authority_account.key == escrow_object.operator;
+
+    if !signed_by_operator && !party_account_object.is_signed_by_owner(authority_account) {
+        msg!("Neither the operator nor the party's owner signed the funding");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    debit_sender(accounts, party_account, party_user_id, stake)?;
+    escrow_object.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    msg!("User {} locked {} in the escrow", party_user_id, stake);
+
+    Ok(())
+}
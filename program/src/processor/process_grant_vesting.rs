@@ -0,0 +1,72 @@
+use crate::{error::UserAccountError, state::{UserAccount, UserId, Vesting}};
+use super::update_rewards;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_grant_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    vesting: Vesting) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if vesting.cliff_duration < 0 || vesting.duration < vesting.cliff_duration {
+        msg!("The vesting cliff must be between 0 and the vesting duration");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    if user_account_object.vesting.unvested_amount(Clock::get()?.unix_timestamp) > 0 {
+        msg!("The user account has a vesting schedule in progress");
+        return Err(UserAccountError::VestingInProgress.into());
+    }
+
+    let previous_balance = user_account_object.balance;
+    user_account_object.balance = user_account_object.balance
+        .checked_add(vesting.total_amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    update_rewards(accounts, user_account_object, previous_balance)?;
+    user_account_object.vesting = vesting;
+
+    msg!("Granted {:?}. Updated user account [id={}, balance={}, blocked_amount={}]",
+        user_account_object.vesting, user_id, user_account_object.balance, user_account_object.blocked_amount);
+
+    Ok(())
+}
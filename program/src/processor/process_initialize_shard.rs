@@ -0,0 +1,101 @@
+use crate::state::{ShardHeader, SHARD_SEED, SHARD_SLOT_COUNT};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+pub fn process_initialize_shard(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    shard_index: u64,
+    shard_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let shard_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let shard_seeds = [
+        SHARD_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &shard_index.to_le_bytes(),
+        &[shard_bump_seed]
+    ];
+
+    let shard_account_key = Pubkey::create_program_address(
+        &shard_seeds,
+        program_id)?;
+
+    if *shard_account.key != shard_account_key {
+        msg!("Provided shard account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // An instruction can only allocate MAX_PERMITTED_DATA_INCREASE bytes, so the shard is 
+    // created with the lamports of its whole size and grows over several calls
+    if shard_account.data_is_empty() {
+        let ix = create_account(
+            operator_account.key,
+            shard_account.key,
+            Rent::get()?.minimum_balance(ShardHeader::ACCOUNT_LEN),
+            MAX_PERMITTED_DATA_INCREASE.min(ShardHeader::ACCOUNT_LEN) as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &ix,
+            &[
+                operator_account.clone(),
+                shard_account.clone(),
+                system_account.clone()
+            ],
+            &[&shard_seeds],
+        )?;
+    } else {
+        if shard_account.owner != program_id {
+            msg!("Provided shard account is not owned by the program");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // The call reaching the whole size writes the header
+        if shard_account.data_len() >= ShardHeader::ACCOUNT_LEN {
+            msg!("The shard is already initialized");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let data_len = (shard_account.data_len() + MAX_PERMITTED_DATA_INCREASE).min(ShardHeader::ACCOUNT_LEN);
+        shard_account.realloc(data_len, false)?;
+    }
+
+    if shard_account.data_len() < ShardHeader::ACCOUNT_LEN {
+        msg!("Allocated {} of the {} bytes of the shard [index={}]", 
+            shard_account.data_len(), ShardHeader::ACCOUNT_LEN, shard_index);
+        return Ok(());
+    }
+
+    *ShardHeader::load_mut(&mut shard_account.data.borrow_mut())? = ShardHeader {
+        token_mint: *token_mint_account.key,
+        operator: *operator_account.key,
+        shard_index,
+    };
+
+    msg!("Initialized shard [index={}] for the users from {}", 
+        shard_index, shard_index.saturating_mul(SHARD_SLOT_COUNT));
+
+    Ok(())
+}
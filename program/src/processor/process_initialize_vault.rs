@@ -0,0 +1,82 @@
+use crate::state::VAULT_SEED;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+
+pub fn process_initialize_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    vault_authority_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_program_account = next_account_info(accounts_iter)?;
+    let associated_token_program_account = next_account_info(accounts_iter)?;
+    let rent_account = next_account_info(accounts_iter)?;
+    let vault_authority_account = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault_authority_seeds = [
+        VAULT_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[vault_authority_bump_seed]
+    ];
+
+    let vault_authority_key = Pubkey::create_program_address(
+        &vault_authority_seeds,
+        program_id)?;
+
+    if *vault_authority_account.key != vault_authority_key {
+        msg!("Provided vault authority account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let vault_account_key = get_associated_token_address(
+        &vault_authority_key,
+        token_mint_account.key);
+
+    if *vault_account.key != vault_account_key {
+        msg!("Provided vault account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let ix = create_associated_token_account(
+        operator_account.key,
+        vault_authority_account.key,
+        token_mint_account.key,
+        &spl_token::id());
+
+    invoke(
+        &ix,
+        &[
+            operator_account.clone(),
+            vault_account.clone(),
+            vault_authority_account.clone(),
+            token_mint_account.clone(),
+            system_account.clone(),
+            token_program_account.clone(),
+            rent_account.clone(),
+            associated_token_program_account.clone()
+        ],
+    )?;
+
+    msg!("Initialized the vault {} of the operator {}", vault_account_key, operator_account.key);
+
+    Ok(())
+}
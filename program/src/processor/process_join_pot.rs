@@ -0,0 +1,73 @@
+use crate::state::{Pot, TaggedAccount, UserAccount, UserId};
+use super::process_transfer::debit_sender;
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_join_pot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let pot_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    if pot_account.owner != program_id {
+        msg!("Provided pot account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut pot_object = Pot::load(&pot_account.data.borrow())?;
+
+    if *operator_account.key != pot_object.operator {
+        msg!("Provided operator account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_seeds = [
+        &user_id.seed()[..],
+        &pot_object.token_mint.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &user_seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_account_object = *UserAccount::load(&user_account.data.borrow())?;
+
+    if !operator_account.is_signer
+        && !matches!(owner_account, Some(owner_account) if user_account_object.is_signed_by_owner(owner_account)) {
+        msg!("Neither the operator nor the user's owner signed the stake");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    debit_sender(accounts, user_account, user_id, amount)?;
+
+    pot_object.amount = pot_object.amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    pot_object.serialize(&mut &mut pot_account.data.borrow_mut()[..])?;
+
+    msg!("User {} joined the pot with {}, pot amount {}", user_id, amount, pot_object.amount);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::state::{UserAccount, UserId};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::transfer,
+    msg
+};
+
+pub fn process_migrate_user_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let user_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key || user_account.owner != program_id {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // The fields are only ever appended, so a previous layout is a prefix of the current one
+    let previous_len = user_account.data_len();
+
+    if previous_len == UserAccount::LEN {
+        msg!("The user account is already migrated");
+        return Ok(());
+    }
+
+    if !UserAccount::LEGACY_LENS.contains(&previous_len) {
+        msg!("The user account has an unknown layout of {} bytes", previous_len);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(UserAccount::LEN).saturating_sub(user_account.lamports());
+
+    if rent_lamports > 0 {
+        invoke(
+            &transfer(operator_account.key, user_account.key, rent_lamports),
+            &[
+                operator_account.clone(),
+                user_account.clone(),
+                system_account.clone()
+            ],
+        )?;
+    }
+
+    user_account.realloc(UserAccount::LEN, true)?;
+
+    let mut user_account_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_account_data)?;
+    user_account_object.token_mint = *token_mint_account.key;
+    user_account_object.user_id = user_id.into();
+
+    msg!("User account [id={}] migrated from {} to {} bytes", user_id, previous_len, UserAccount::LEN);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use crate::state::{UserId, VAULT_SEED};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    msg
+};
+use spl_token::{instruction::transfer, state::Account as TokenAccount};
+use spl_associated_token_account::get_associated_token_address;
+
+pub fn process_migrate_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    legacy_vault_authority_bump_seed: u8,
+    vault_authority_bump_seed: u8) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let token_program_account = next_account_info(accounts_iter)?;
+    let legacy_vault_authority_account = next_account_info(accounts_iter)?;
+    let legacy_vault_account = next_account_info(accounts_iter)?;
+    let vault_authority_account = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // The previous versions derived the vault authority like the account of the user 0
+    let legacy_vault_authority_seeds = [
+        &UserId::U32(0).seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[legacy_vault_authority_bump_seed]
+    ];
+
+    let legacy_vault_authority_key = Pubkey::create_program_address(
+        &legacy_vault_authority_seeds,
+        program_id)?;
+
+    if *legacy_vault_authority_account.key != legacy_vault_authority_key {
+        msg!("Provided vault authority account of the previous versions is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *legacy_vault_account.key != get_associated_token_address(&legacy_vault_authority_key, token_mint_account.key) {
+        msg!("Provided vault account of the previous versions is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let vault_authority_seeds = [
+        VAULT_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[vault_authority_bump_seed]
+    ];
+
+    let vault_authority_key = Pubkey::create_program_address(
+        &vault_authority_seeds,
+        program_id)?;
+
+    if *vault_authority_account.key != vault_authority_key {
+        msg!("Provided vault authority account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *vault_account.key != get_associated_token_address(&vault_authority_key, token_mint_account.key) {
+        msg!("Provided vault account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let amount = TokenAccount::unpack(&legacy_vault_account.data.borrow())?.amount;
+
+    if amount == 0 {
+        msg!("The vault of the previous versions is empty");
+        return Ok(());
+    }
+
+    let ix = transfer(
+        token_program_account.key,
+        legacy_vault_account.key,
+        vault_account.key,
+        legacy_vault_authority_account.key,
+        &[],
+        amount
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[
+            legacy_vault_account.clone(),
+            vault_account.clone(),
+            legacy_vault_authority_account.clone(),
+            token_program_account.clone()
+        ],
+        &[&legacy_vault_authority_seeds],
+    )?;
+
+    msg!("Moved {} from the vault {} to the vault {}", amount, legacy_vault_account.key, vault_account.key);
+
+    Ok(())
+}
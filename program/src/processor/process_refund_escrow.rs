@@ -0,0 +1,58 @@
+use crate::state::{Escrow, TaggedAccount};
+use super::{close_account, process_transfer::credit_recipient};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_refund_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let party_a_account = next_account_info(accounts_iter)?;
+    let party_b_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if escrow_account.owner != program_id {
+        msg!("Provided escrow account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let escrow_object = Escrow::load(&escrow_account.data.borrow())?;
+
+    if *party_a_account.key != escrow_object.party_a_account
+        || *party_b_account.key != escrow_object.party_b_account {
+        msg!("Provided party accounts are invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *operator_account.key != escrow_object.operator {
+        msg!("Provided operator account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if Clock::get()?.unix_timestamp < escrow_object.refund_time {
+        msg!("The escrow cannot be refunded before {}", escrow_object.refund_time);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    credit_recipient(accounts, party_a_account, escrow_object.party_a_user_id, escrow_object.party_a_funded)?;
+    credit_recipient(accounts, party_b_account, escrow_object.party_b_user_id, escrow_object.party_b_funded)?;
+
+    close_account(escrow_account, operator_account)?;
+
+    msg!("Refunded the escrow: user {} received {}, user {} received {}",
+        escrow_object.party_a_user_id, escrow_object.party_a_funded,
+        escrow_object.party_b_user_id, escrow_object.party_b_funded);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::state::{Escrow, TaggedAccount};
+use super::{close_account, process_transfer::credit_recipient};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_resolve_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    party_a_amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let party_a_account = next_account_info(accounts_iter)?;
+    let party_b_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let arbiter_account = next_account_info(accounts_iter)?;
+
+    if escrow_account.owner != program_id {
+        msg!("Provided escrow account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let escrow_object = Escrow::load(&escrow_account.data.borrow())?;
+
+    if !arbiter_account.is_signer || *arbiter_account.key != escrow_object.arbiter {
+        msg!("The escrow arbiter did not sign the resolution");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *party_a_account.key != escrow_object.party_a_account
+        || *party_b_account.key != escrow_object.party_b_account {
+        msg!("Provided party accounts are invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *operator_account.key != escrow_object.operator {
+        msg!("Provided operator account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if escrow_object.party_a_funded != escrow_object.party_a_stake
+        || escrow_object.party_b_funded != escrow_object.party_b_stake {
+        msg!("The escrow is not funded by both parties");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let total_amount = escrow_object.party_a_funded
+        .checked_add(escrow_object.party_b_funded)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    if party_a_amount > total_amount {
+        msg!("The given amount is greater than the escrowed amount");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    credit_recipient(accounts, party_a_account, escrow_object.party_a_user_id, party_a_amount)?;
+    credit_recipient(accounts, party_b_account, escrow_object.party_b_user_id, total_amount - party_a_amount)?;
+
+    close_account(escrow_account, operator_account)?;
+
+    msg!("Resolved the escrow: user {} received {}, user {} received {}",
+        escrow_object.party_a_user_id, party_a_amount,
+        escrow_object.party_b_user_id, total_amount - party_a_amount);
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use crate::state::{Allowance, Delegate, UserAccount, UserId, ALLOWANCE_SEED};
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_revoke(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let allowance_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_account_object = *UserAccount::load(&user_account.data.borrow())?;
+
+    if !operator_account.is_signer
+        && !matches!(owner_account, Some(owner_account) if user_account_object.is_signed_by_owner(owner_account)) {
+        msg!("Neither the operator nor the user's owner signed the revocation");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let allowance_seeds = [
+        ALLOWANCE_SEED,
+        &user_account_key.to_bytes(),
+        &delegate.kind_seed(),
+        &delegate.seed(),
+        &[allowance_bump_seed]
+    ];
+
+    let allowance_account_key = Pubkey::create_program_address(
+        &allowance_seeds,
+        program_id)?;
+
+    if *allowance_account.key != allowance_account_key {
+        msg!("Provided allowance account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut allowance_object = Allowance::try_from_slice(&allowance_account.data.borrow())?;
+    allowance_object.amount = 0;
+    allowance_object.serialize(&mut &mut allowance_account.data.borrow_mut()[..])?;
+
+    msg!("User [id={}] revoked the allowance of {:?}", user_id, delegate);
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use crate::state::{ScheduledTransfer, TaggedAccount, UserId, SCHEDULED_TRANSFER_SEED};
+use super::process_transfer::debit_sender;
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_schedule_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    sender_user_id: UserId,
+    sender_account_bump_seed: u8,
+    recipient_user_id: UserId,
+    recipient_account_bump_seed: u8,
+    schedule_id: u32,
+    scheduled_transfer_bump_seed: u8,
+    amount: u64,
+    execute_at: i64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let sender_account = next_account_info(accounts_iter)?;
+    let recipient_account = next_account_info(accounts_iter)?;
+    let scheduled_transfer_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if execute_at <= Clock::get()?.unix_timestamp {
+        msg!("The execution time must be in the future");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let sender_seeds = [
+        &sender_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[sender_account_bump_seed]
+    ];
+
+    let sender_account_key = Pubkey::create_program_address(
+        &sender_seeds,
+        program_id)?;
+
+    if *sender_account.key != sender_account_key {
+        msg!("Provided sender account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let recipient_seeds = [
+        &recipient_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[recipient_account_bump_seed]
+    ];
+
+    let recipient_account_key = Pubkey::create_program_address(
+        &recipient_seeds,
+        program_id)?;
+
+    if *recipient_account.key != recipient_account_key {
+        msg!("Provided recipient account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let scheduled_transfer_seeds = [
+        SCHEDULED_TRANSFER_SEED,
+        &sender_account_key.to_bytes(),
+        &schedule_id.to_le_bytes(),
+        &[scheduled_transfer_bump_seed]
+    ];
+
+    let scheduled_transfer_account_key = Pubkey::create_program_address(
+        &scheduled_transfer_seeds,
+        program_id)?;
+
+    if *scheduled_transfer_account.key != scheduled_transfer_account_key {
+        msg!("Provided scheduled transfer account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let scheduled_transfer_object = ScheduledTransfer {
+        account_type: ScheduledTransfer::ACCOUNT_TYPE,
+        sender_user_id,
+        sender_account: sender_account_key,
+        recipient_user_id,
+        recipient_account: recipient_account_key,
+        amount,
+        execute_at,
+        operator: *operator_account.key,
+    };
+    let scheduled_transfer_data = scheduled_transfer_object.try_to_vec()?;
+
+    let rent = Rent::get()?;
+    let ix = create_account(
+        operator_account.key,
+        scheduled_transfer_account.key,
+        rent.minimum_balance(scheduled_transfer_data.len()),
+        scheduled_transfer_data.len() as u64,
+        program_id,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            operator_account.clone(),
+            scheduled_transfer_account.clone(),
+            system_account.clone()
+        ],
+        &[&scheduled_transfer_seeds],
+    )?;
+
+    debit_sender(accounts, sender_account, sender_user_id, amount)?;
+
+    scheduled_transfer_account.data.borrow_mut().copy_from_slice(&scheduled_transfer_data);
+
+    msg!("Scheduled transfer [id={}] of {} from user {} to user {} at {}",
+        schedule_id, amount, sender_user_id, recipient_user_id, execute_at);
+
+    Ok(())
+}
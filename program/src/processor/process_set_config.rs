@@ -0,0 +1,92 @@
+use crate::state::{Config, CONFIG_SEED};
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+pub fn process_set_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config_bump_seed: u8,
+    withdrawal_wallet_delay: i64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if withdrawal_wallet_delay < 0 {
+        msg!("The withdrawal wallet delay cannot be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seeds = [
+        CONFIG_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[config_bump_seed]
+    ];
+
+    let config_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *config_account.key != config_account_key {
+        msg!("Provided config account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if config_account.data_is_empty() {
+        let rent = Rent::get()?;
+        let ix = create_account(
+            operator_account.key,
+            config_account.key,
+            rent.minimum_balance(Config::LEN),
+            Config::LEN as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &ix,
+            &[
+                operator_account.clone(),
+                config_account.clone(),
+                system_account.clone()
+            ],
+            &[&seeds],
+        )?;
+
+        msg!("The config account is created");
+    }
+
+    let mut config_object = Config::try_from_slice(&config_account.data.borrow())?;
+    config_object.set_withdrawal_wallet_delay(withdrawal_wallet_delay, Clock::get()?.unix_timestamp)?;
+    config_object.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    if config_object.withdrawal_wallet_delay_change_time != 0 {
+        msg!("Updated config [withdrawal_wallet_delay={}], the delay changes to {} at {}",
+            config_object.withdrawal_wallet_delay,
+            config_object.pending_withdrawal_wallet_delay,
+            config_object.withdrawal_wallet_delay_change_time);
+    } else {
+        msg!("Updated config [withdrawal_wallet_delay={}]", config_object.withdrawal_wallet_delay);
+    }
+
+    Ok(())
+}
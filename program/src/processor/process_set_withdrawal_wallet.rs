@@ -0,0 +1,96 @@
+use crate::state::{Config, UserAccount, UserId, CONFIG_SEED};
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+    msg
+};
+
+pub fn process_set_withdrawal_wallet(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_id: UserId,
+    user_account_bump_seed: u8,
+    config_bump_seed: u8,
+    wallet: Pubkey) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let user_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if wallet == Pubkey::default() {
+        msg!("Provided withdrawal wallet is invalid");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let seeds = [
+        &user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &seeds,
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let config_seeds = [
+        CONFIG_SEED,
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[config_bump_seed]
+    ];
+
+    let config_account_key = Pubkey::create_program_address(
+        &config_seeds,
+        program_id)?;
+
+    if *config_account.key != config_account_key {
+        msg!("Provided config account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if config_account.data_is_empty() {
+        msg!("The config account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let config_object = Config::try_from_slice(&config_account.data.borrow())?;
+    let now = Clock::get()?.unix_timestamp;
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+    user_account_object.apply_pending_withdrawal_wallet(now);
+
+    if user_account_object.withdrawal_wallet == Pubkey::default() {
+        user_account_object.withdrawal_wallet = wallet;
+        msg!("Registered withdrawal wallet {} for user account [id={}]", wallet, user_id);
+    } else {
+        user_account_object.pending_withdrawal_wallet = wallet;
+        user_account_object.withdrawal_wallet_change_time = now
+            .checked_add(config_object.effective_withdrawal_wallet_delay(now))
+            .ok_or(ProgramError::InvalidArgument)?;
+        msg!("Withdrawal wallet of user account [id={}] changes to {} at {}",
+            user_id, wallet, user_account_object.withdrawal_wallet_change_time);
+    }
+
+    Ok(())
+}
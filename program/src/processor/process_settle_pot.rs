@@ -0,0 +1,101 @@
+use crate::state::{Pot, PotRake, TaggedAccount, PotWinner, UserId, RAKE_BASIS_POINTS_DENOMINATOR};
+use super::{close_account, process_transfer::credit_recipient};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+pub fn process_settle_pot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    winners: Vec<PotWinner>,
+    rake: Option<PotRake>) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let pot_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+
+    if pot_account.owner != program_id {
+        msg!("Provided pot account is invalid");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let pot_object = Pot::load(&pot_account.data.borrow())?;
+
+    if !operator_account.is_signer || *operator_account.key != pot_object.operator {
+        msg!("The pot operator did not sign the settlement");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let total_weight = winners
+        .iter()
+        .try_fold(0u64, |total, winner| total.checked_add(winner.weight))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    if total_weight == 0 {
+        msg!("The pot must be settled to at least one winner with a positive weight");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let user_account_key = |user_id: UserId, bump_seed: u8| Pubkey::create_program_address(
+        &[
+            &user_id.seed()[..],
+            &pot_object.token_mint.to_bytes(),
+            &pot_object.operator.to_bytes(),
+            &[bump_seed]
+        ],
+        program_id);
+
+    let mut prize_amount = pot_object.amount;
+
+    if let Some(rake) = rake {
+        let house_account = next_account_info(accounts_iter)?;
+
+        if *house_account.key != user_account_key(rake.house_user_id, rake.house_account_bump_seed)? {
+            msg!("Provided house account is invalid");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if u64::from(rake.basis_points) > RAKE_BASIS_POINTS_DENOMINATOR {
+            msg!("The rake cannot exceed the pot");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let rake_amount = (u128::from(pot_object.amount) * u128::from(rake.basis_points)
+            / u128::from(RAKE_BASIS_POINTS_DENOMINATOR)) as u64;
+
+        credit_recipient(accounts, house_account, rake.house_user_id, rake_amount)?;
+        prize_amount -= rake_amount;
+    }
+
+    // Shares are rounded down, the remainder goes to the first winner
+    let shares: Vec<u64> = winners
+        .iter()
+        .map(|winner| (u128::from(prize_amount) * u128::from(winner.weight)
+            / u128::from(total_weight)) as u64)
+        .collect();
+    let remainder = prize_amount - shares.iter().sum::<u64>();
+
+    for (index, (winner, share)) in winners.iter().zip(shares).enumerate() {
+        let winner_account = next_account_info(accounts_iter)?;
+
+        if *winner_account.key != user_account_key(winner.user_id, winner.user_account_bump_seed)? {
+            msg!("Provided winner account is invalid");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let amount = if index == 0 { share + remainder } else { share };
+        credit_recipient(accounts, winner_account, winner.user_id, amount)?;
+    }
+
+    close_account(pot_account, operator_account)?;
+
+    msg!("Settled the pot of {} to {} winners", pot_object.amount, winners.len());
+
+    Ok(())
+}
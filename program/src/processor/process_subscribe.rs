@@ -0,0 +1,144 @@
+use crate::state::{Subscription, TaggedAccount, UserAccount, UserId, SUBSCRIPTION_SEED};
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+    system_instruction::create_account,
+    msg
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_subscribe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    subscriber_user_id: UserId,
+    subscriber_account_bump_seed: u8,
+    merchant_user_id: UserId,
+    merchant_account_bump_seed: u8,
+    subscription_bump_seed: u8,
+    amount_per_period: u64,
+    period: i64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let system_account = next_account_info(accounts_iter)?;
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let subscriber_account = next_account_info(accounts_iter)?;
+    let merchant_account = next_account_info(accounts_iter)?;
+    let subscription_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
+
+    if period <= 0 {
+        msg!("The subscription period must be positive");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let subscriber_seeds = [
+        &subscriber_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[subscriber_account_bump_seed]
+    ];
+
+    let subscriber_account_key = Pubkey::create_program_address(
+        &subscriber_seeds,
+        program_id)?;
+
+    if *subscriber_account.key != subscriber_account_key {
+        msg!("Provided subscriber account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let merchant_seeds = [
+        &merchant_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[merchant_account_bump_seed]
+    ];
+
+    let merchant_account_key = Pubkey::create_program_address(
+        &merchant_seeds,
+        program_id)?;
+
+    if *merchant_account.key != merchant_account_key {
+        msg!("Provided merchant account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let subscriber_account_object = *UserAccount::load(&subscriber_account.data.borrow())?;
+
+    let payer_account = if operator_account.is_signer {
+        operator_account
+    } else {
+        match owner_account {
+            Some(owner_account) if subscriber_account_object.is_signed_by_owner(owner_account) => owner_account,
+            _ => {
+                msg!("Neither the operator nor the subscriber's owner signed the subscription");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+    };
+
+    let subscription_seeds = [
+        SUBSCRIPTION_SEED,
+        &subscriber_account_key.to_bytes(),
+        &merchant_account_key.to_bytes(),
+        &[subscription_bump_seed]
+    ];
+
+    let subscription_account_key = Pubkey::create_program_address(
+        &subscription_seeds,
+        program_id)?;
+
+    if *subscription_account.key != subscription_account_key {
+        msg!("Provided subscription account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let subscription_object = Subscription {
+        account_type: Subscription::ACCOUNT_TYPE,
+        subscriber_user_id,
+        subscriber_account: subscriber_account_key,
+        merchant_user_id,
+        merchant_account: merchant_account_key,
+        amount_per_period,
+        period,
+        next_charge_time: Clock::get()?.unix_timestamp,
+        operator: *operator_account.key,
+        payer: *payer_account.key,
+    };
+    let subscription_data = subscription_object.try_to_vec()?;
+
+    let rent = Rent::get()?;
+    let ix = create_account(
+        payer_account.key,
+        subscription_account.key,
+        rent.minimum_balance(subscription_data.len()),
+        subscription_data.len() as u64,
+        program_id,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            payer_account.clone(),
+            subscription_account.clone(),
+            system_account.clone()
+        ],
+        &[&subscription_seeds],
+    )?;
+
+    subscription_account.data.borrow_mut().copy_from_slice(&subscription_data);
+
+    msg!("User {} subscribed to user {} for {} every {} seconds",
+        subscriber_user_id, merchant_user_id, amount_per_period, period);
+
+    Ok(())
+}
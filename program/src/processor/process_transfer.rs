@@ -1,20 +1,22 @@
-use crate::state::UserAccount;
+use crate::{error::UserAccountError, state::{UserAccount, UserId}};
+use super::{shard::{is_shard, load_shard_slot}, update_rewards};
 
-use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
     msg
 };
 
 pub fn process_transfer(
     program_id: &Pubkey, 
     accounts: &[AccountInfo], 
-    sender_user_id: u32, 
+    sender_user_id: UserId, 
     sender_account_bump_seed: u8, 
-    recipient_user_id: u32, 
+    recipient_user_id: UserId, 
     recipient_account_bump_seed: u8,
     amount: u64) -> ProgramResult {
 
@@ -24,14 +26,28 @@ pub fn process_transfer(
     let sender_account = next_account_info(accounts_iter)?;
     let recipient_account = next_account_info(accounts_iter)?;
     let operator_account = next_account_info(accounts_iter)?;
+    let owner_account = next_account_info(accounts_iter).ok();
 
-    if !operator_account.is_signer {
-        msg!("Provided operator account is not a signer");
-        return Err(ProgramError::MissingRequiredSignature);   
+    let sender_is_shard = is_shard(program_id, sender_account);
+    if sender_is_shard != is_shard(program_id, recipient_account) {
+        msg!("Transfers between a ledger shard and a user account are not supported");
+        return Err(UserAccountError::MixedLedgerTransfer.into());
+    }
+
+    if sender_is_shard {
+        return process_shard_transfer(
+            program_id, 
+            sender_account, 
+            recipient_account, 
+            token_mint_account, 
+            operator_account, 
+            sender_user_id, 
+            recipient_user_id, 
+            amount);
     }
     
     let sender_seeds = [
-        &sender_user_id.to_le_bytes()[..], 
+        &sender_user_id.seed()[..], 
         &token_mint_account.key.to_bytes(), 
         &operator_account.key.to_bytes(), 
         &[sender_account_bump_seed]
@@ -48,7 +64,7 @@ pub fn process_transfer(
     }
 
     let recipient_seeds = [
-        &recipient_user_id.to_le_bytes()[..], 
+        &recipient_user_id.seed()[..], 
         &token_mint_account.key.to_bytes(), 
         &operator_account.key.to_bytes(), 
         &[recipient_account_bump_seed]
@@ -63,29 +79,139 @@ pub fn process_transfer(
         return Err(ProgramError::InvalidAccountData);   
     }
 
-    let mut sender_account_object = UserAccount::try_from_slice(&sender_account.data.borrow())?;
-    msg!("Updating sender account [id={}, balance={}, blocked_amount={}]", 
+    {
+        let mut sender_data = sender_account.data.borrow_mut();
+        let sender_account_object = UserAccount::load_mut(&mut sender_data)?;
+
+        if !operator_account.is_signer
+            && !matches!(owner_account, Some(owner_account) if sender_account_object.is_signed_by_owner(owner_account)) {
+            msg!("Neither the operator nor the sender's owner signed the transfer");
+            return Err(ProgramError::MissingRequiredSignature);   
+        }
+
+        // The part of the amount not covered by the available balance is spent on credit. 
+        // Without a credit limit, `debit_sender` rejects the amount as before.
+        let borrowed_amount = amount.saturating_sub(
+            sender_account_object.available_balance(Clock::get()?.unix_timestamp));
+
+        if borrowed_amount > 0 && sender_account_object.credit_limit > 0 {
+            let debt = sender_account_object.debt.saturating_add(borrowed_amount);
+
+            if debt > sender_account_object.credit_limit {
+                msg!("The given amount exceeds the available balance and the credit limit");
+                return Err(UserAccountError::CreditLimitExceeded.into());
+            }
+
+            let previous_balance = sender_account_object.balance;
+            sender_account_object.balance = sender_account_object.balance
+                .checked_add(borrowed_amount)
+                .ok_or(ProgramError::InvalidArgument)?;
+            sender_account_object.debt = debt;
+            update_rewards(accounts, sender_account_object, previous_balance)?;
+
+            msg!("Borrowed {}. User {} owes {}", borrowed_amount, sender_user_id, debt);
+        }
+    }
+
+    debit_sender(accounts, sender_account, sender_user_id, amount)?;
+    credit_recipient(accounts, recipient_account, recipient_user_id, amount)
+}
+
+/// Moves the given `amount` between two users stored in ledger shards
+#[allow(clippy::too_many_arguments)]
+fn process_shard_transfer(
+    program_id: &Pubkey,
+    sender_shard_account: &AccountInfo,
+    recipient_shard_account: &AccountInfo,
+    token_mint_account: &AccountInfo,
+    operator_account: &AccountInfo,
+    sender_user_id: UserId,
+    recipient_user_id: UserId,
+    amount: u64) -> ProgramResult {
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);   
+    }
+
+    let sender_balance = {
+        let mut sender_slot = load_shard_slot(
+            program_id, sender_shard_account, token_mint_account.key, operator_account.key, sender_user_id)?;
+
+        if sender_slot.balance.saturating_sub(sender_slot.blocked_amount) < amount {
+            msg!("The given amount is greater than the available balance");
+            return Err(ProgramError::InvalidArgument);   
+        }
+
+        sender_slot.balance -= amount;
+        sender_slot.balance
+    };
+
+    // Loaded once the sender slot is released, as both users may share the slot or the shard
+    let mut recipient_slot = load_shard_slot(
+        program_id, recipient_shard_account, token_mint_account.key, operator_account.key, recipient_user_id)?;
+
+    recipient_slot.balance = recipient_slot.balance
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    msg_verbose!("Sent {} from user {} to user {}. Updated shard slots [balance={}, balance={}]", 
+        amount, sender_user_id, recipient_user_id, sender_balance, recipient_slot.balance);
+
+    Ok(())
+}
+
+/// Takes `amount` from the available balance of the sender, updating the sender account in place.
+/// `accounts` must include the reward pool if the sender is enrolled in one.
+pub fn debit_sender(
+    accounts: &[AccountInfo],
+    sender_account: &AccountInfo,
+    sender_user_id: UserId,
+    amount: u64) -> ProgramResult {
+
+    let mut sender_data = sender_account.data.borrow_mut();
+    let sender_account_object = UserAccount::load_mut(&mut sender_data)?;
+
+    msg_verbose!("Updating sender account [id={}, balance={}, blocked_amount={}]", 
         sender_user_id, sender_account_object.balance, sender_account_object.blocked_amount);
 
-    if (sender_account_object.balance - sender_account_object.blocked_amount) < amount {
+    if sender_account_object.available_balance(Clock::get()?.unix_timestamp) < amount {
         msg!("The given amount is greater than the available balance");
         return Err(ProgramError::InvalidArgument);   
     }
 
+    let previous_balance = sender_account_object.balance;
     sender_account_object.balance -= amount;
-    sender_account_object.serialize(&mut &mut sender_account.data.borrow_mut()[..])?;
+    update_rewards(accounts, sender_account_object, previous_balance)?;
 
-    msg!("Sent {}. Updated sender account [id={}, balance={}, blocked_amount={}]", 
+    msg_verbose!("Sent {}. Updated sender account [id={}, balance={}, blocked_amount={}]", 
         amount, sender_user_id, sender_account_object.balance, sender_account_object.blocked_amount);
 
-    let mut recipient_account_object = UserAccount::try_from_slice(&recipient_account.data.borrow())?;
-    msg!("Updating recipient account [id={}, balance={}, blocked_amount={}]", 
+    Ok(())
+}
+
+/// Adds `amount` to the balance of the recipient. The recipient is loaded only 
+/// after the sender is updated, so a transfer to the same account keeps its balance.
+/// `accounts` must include the reward pool if the recipient is enrolled in one.
+pub fn credit_recipient(
+    accounts: &[AccountInfo],
+    recipient_account: &AccountInfo,
+    recipient_user_id: UserId,
+    amount: u64) -> ProgramResult {
+
+    let mut recipient_data = recipient_account.data.borrow_mut();
+    let recipient_account_object = UserAccount::load_mut(&mut recipient_data)?;
+
+    msg_verbose!("Updating recipient account [id={}, balance={}, blocked_amount={}]", 
         recipient_user_id, recipient_account_object.balance, recipient_account_object.blocked_amount);
 
-    recipient_account_object.balance += amount;
-    recipient_account_object.serialize(&mut &mut recipient_account.data.borrow_mut()[..])?;
+    let previous_balance = recipient_account_object.balance;
+    recipient_account_object.balance = recipient_account_object.balance
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    update_rewards(accounts, recipient_account_object, previous_balance)?;
 
-    msg!("Received {}. Updated recipient account [id={}, balance={}, blocked_amount={}]", 
+    msg_verbose!("Received {}. Updated recipient account [id={}, balance={}, blocked_amount={}]", 
         amount, recipient_user_id, recipient_account_object.balance, recipient_account_object.blocked_amount);
 
     Ok(())
@@ -0,0 +1,134 @@
+use crate::state::{Allowance, Delegate, UserAccount, UserId, ALLOWANCE_SEED};
+use super::process_transfer::{credit_recipient, debit_sender};
+
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_transfer_from(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    sender_user_id: UserId,
+    sender_account_bump_seed: u8,
+    recipient_user_id: UserId,
+    recipient_account_bump_seed: u8,
+    allowance_bump_seed: u8,
+    delegate: Delegate,
+    delegate_account_bump_seed: u8,
+    amount: u64) -> ProgramResult {
+
+    let accounts_iter = &mut accounts.iter();
+
+    let token_mint_account = next_account_info(accounts_iter)?;
+    let sender_account = next_account_info(accounts_iter)?;
+    let recipient_account = next_account_info(accounts_iter)?;
+    let allowance_account = next_account_info(accounts_iter)?;
+    let operator_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if !authority_account.is_signer {
+        msg!("Provided delegate authority account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let sender_seeds = [
+        &sender_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[sender_account_bump_seed]
+    ];
+
+    let sender_account_key = Pubkey::create_program_address(
+        &sender_seeds,
+        program_id)?;
+
+    if *sender_account.key != sender_account_key {
+        msg!("Provided sender account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let recipient_seeds = [
+        &recipient_user_id.seed()[..],
+        &token_mint_account.key.to_bytes(),
+        &operator_account.key.to_bytes(),
+        &[recipient_account_bump_seed]
+    ];
+
+    let recipient_account_key = Pubkey::create_program_address(
+        &recipient_seeds,
+        program_id)?;
+
+    if *recipient_account.key != recipient_account_key {
+        msg!("Provided recipient account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let authorized = match delegate {
+        Delegate::Key(key) => *authority_account.key == key,
+        Delegate::User(delegate_user_id) => {
+            let delegate_account = next_account_info(accounts_iter)?;
+
+            let delegate_seeds = [
+                &delegate_user_id.seed()[..],
+                &token_mint_account.key.to_bytes(),
+                &operator_account.key.to_bytes(),
+                &[delegate_account_bump_seed]
+            ];
+
+            let delegate_account_key = Pubkey::create_program_address(
+                &delegate_seeds,
+                program_id)?;
+
+            if *delegate_account.key != delegate_account_key {
+                msg!("Provided delegate account is invalid");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let delegate_account_object = *UserAccount::load(&delegate_account.data.borrow())?;
+            delegate_account_object.is_signed_by_owner(authority_account)
+        }
+    };
+
+    if !authorized {
+        msg!("Provided delegate authority account does not act for the delegate");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let allowance_seeds = [
+        ALLOWANCE_SEED,
+        &sender_account_key.to_bytes(),
+        &delegate.kind_seed(),
+        &delegate.seed(),
+        &[allowance_bump_seed]
+    ];
+
+    let allowance_account_key = Pubkey::create_program_address(
+        &allowance_seeds,
+        program_id)?;
+
+    if *allowance_account.key != allowance_account_key {
+        msg!("Provided allowance account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut allowance_object = Allowance::try_from_slice(&allowance_account.data.borrow())?;
+
+    if allowance_object.amount < amount {
+        msg!("The given amount is greater than the allowance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    allowance_object.amount -= amount;
+    allowance_object.serialize(&mut &mut allowance_account.data.borrow_mut()[..])?;
+
+    msg!("Spent {} of the allowance of {:?}, {} left", amount, delegate, allowance_object.amount);
+
+    debit_sender(accounts, sender_account, sender_user_id, amount)?;
+    credit_recipient(accounts, recipient_account, recipient_user_id, amount)
+}
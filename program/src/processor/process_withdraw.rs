@@ -1,12 +1,14 @@
-use crate::state::UserAccount;
+use crate::{error::UserAccountError, state::{UserAccount, UserId, VAULT_SEED}};
+use super::{shard::{is_shard, load_shard_slot}, update_rewards};
 
-use borsh::{BorshSerialize, BorshDeserialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
     msg
 };
 use spl_token::instruction::transfer;
@@ -15,7 +17,7 @@ use spl_associated_token_account::get_associated_token_address;
 pub fn process_withdraw(
     program_id: &Pubkey,
     accounts: &[AccountInfo], 
-    user_id: u32,
+    user_id: UserId,
     user_account_bump_seed: u8, 
     source_authority_bump_seed: u8, 
     amount: u64) -> ProgramResult {
@@ -29,30 +31,10 @@ pub fn process_withdraw(
     let source_authority_account = next_account_info(accounts_iter)?;
     let destination_account = next_account_info(accounts_iter)?;
     let operator_account = next_account_info(accounts_iter)?;
-
-    if !operator_account.is_signer {
-        msg!("Provided operator account is not a signer");
-        return Err(ProgramError::MissingRequiredSignature);   
-    }
+    let owner_account = next_account_info(accounts_iter).ok();
     
-    let user_account_seeds = [
-        &user_id.to_le_bytes()[..], 
-        &token_mint_account.key.to_bytes(), 
-        &operator_account.key.to_bytes(), 
-        &[user_account_bump_seed]
-    ];
-
-    let user_account_key = Pubkey::create_program_address(
-        &user_account_seeds, 
-        &program_id)?;
-
-    if *user_account.key != user_account_key {
-        msg!("Provided user account is invalid");
-        return Err(ProgramError::InvalidAccountData);   
-    }
-
     let source_authority_account_seeds = [
-        &(0 as u32).to_le_bytes()[..], 
+        VAULT_SEED, 
         &token_mint_account.key.to_bytes(), 
         &operator_account.key.to_bytes(), 
         &[source_authority_bump_seed]
@@ -76,15 +58,154 @@ pub fn process_withdraw(
         return Err(ProgramError::InvalidAccountData); 
     }
 
-    let mut user_account_object = UserAccount::try_from_slice(&user_account.data.borrow())?;
-    msg!("Updating user account [id={}, balance={}, blocked_amount={}]", 
+    if is_shard(program_id, user_account) {
+        return process_shard_withdraw(
+            program_id, 
+            token_program_account, 
+            user_account, 
+            token_mint_account, 
+            source_account, 
+            source_authority_account, 
+            &source_authority_account_seeds, 
+            destination_account, 
+            operator_account, 
+            user_id, 
+            amount);
+    }
+
+    let user_account_seeds = [
+        &user_id.seed()[..], 
+        &token_mint_account.key.to_bytes(), 
+        &operator_account.key.to_bytes(), 
+        &[user_account_bump_seed]
+    ];
+
+    let user_account_key = Pubkey::create_program_address(
+        &user_account_seeds, 
+        program_id)?;
+
+    if *user_account.key != user_account_key {
+        msg!("Provided user account is invalid");
+        return Err(ProgramError::InvalidAccountData);   
+    }
+
+    let mut user_data = user_account.data.borrow_mut();
+    let user_account_object = UserAccount::load_mut(&mut user_data)?;
+
+    if !operator_account.is_signer
+        && !matches!(owner_account, Some(owner_account) if user_account_object.is_signed_by_owner(owner_account)) {
+        msg!("Neither the operator nor the user's owner signed the withdrawal");
+        return Err(ProgramError::MissingRequiredSignature);   
+    }
+
+    msg_verbose!("Updating user account [id={}, balance={}, blocked_amount={}]", 
         user_id, user_account_object.balance, user_account_object.blocked_amount);
 
-    if (user_account_object.balance - user_account_object.blocked_amount) < amount {
+    if user_account_object.debt > 0 {
+        msg!("The user account owes {}", user_account_object.debt);
+        return Err(UserAccountError::OutstandingDebt.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if user_account_object.available_balance(now) < amount {
         msg!("The given amount is greater than the available balance");
         return Err(ProgramError::InvalidArgument);   
     }
 
+    user_account_object.apply_pending_withdrawal_wallet(now);
+
+    if user_account_object.withdrawal_wallet != Pubkey::default() {
+        let destination_account_key = get_associated_token_address(
+            &user_account_object.withdrawal_wallet, 
+            token_mint_account.key);
+
+        if *destination_account.key != destination_account_key {
+            msg!("Provided destination account does not belong to the registered withdrawal wallet");
+            return Err(UserAccountError::InvalidWithdrawalDestination.into());
+        }
+    } else if !operator_account.is_signer {
+        let destination_account_key = get_associated_token_address(
+            &user_account_object.owner, 
+            token_mint_account.key);
+
+        if *destination_account.key != destination_account_key {
+            msg!("Provided destination account does not belong to the user's owner");
+            return Err(UserAccountError::InvalidWithdrawalDestination.into());
+        }
+    }
+
+    transfer_from_vault(
+        token_program_account, 
+        source_account, 
+        source_authority_account, 
+        &source_authority_account_seeds, 
+        destination_account, 
+        amount)?;
+
+    let previous_balance = user_account_object.balance;
+    user_account_object.balance -= amount;
+    update_rewards(accounts, user_account_object, previous_balance)?;
+
+    msg_verbose!("Withdrawn {}. Updated user account [id={}, balance={}, blocked_amount={}]", 
+        amount, user_id, user_account_object.balance, user_account_object.blocked_amount);
+
+    Ok(())
+}
+
+/// Withdraws the given `amount` of a user stored in a ledger shard. Shard users have neither 
+/// an owner nor a withdrawal wallet, so only the operator can withdraw their tokens.
+#[allow(clippy::too_many_arguments)]
+fn process_shard_withdraw<'a>(
+    program_id: &Pubkey,
+    token_program_account: &AccountInfo<'a>,
+    shard_account: &AccountInfo<'a>,
+    token_mint_account: &AccountInfo<'a>,
+    source_account: &AccountInfo<'a>,
+    source_authority_account: &AccountInfo<'a>,
+    source_authority_account_seeds: &[&[u8]],
+    destination_account: &AccountInfo<'a>,
+    operator_account: &AccountInfo<'a>,
+    user_id: UserId,
+    amount: u64) -> ProgramResult {
+
+    if !operator_account.is_signer {
+        msg!("Provided operator account is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut shard_slot = load_shard_slot(program_id, shard_account, token_mint_account.key, operator_account.key, user_id)?;
+
+    if shard_slot.balance.saturating_sub(shard_slot.blocked_amount) < amount {
+        msg!("The given amount is greater than the available balance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    shard_slot.balance -= amount;
+
+    transfer_from_vault(
+        token_program_account, 
+        source_account, 
+        source_authority_account, 
+        source_authority_account_seeds, 
+        destination_account, 
+        amount)?;
+
+    msg_verbose!("Withdrawn {}. Updated shard slot [id={}, balance={}, blocked_amount={}]", 
+        amount, user_id, shard_slot.balance, shard_slot.blocked_amount);
+
+    Ok(())
+}
+
+/// Transfers the given `amount` from the vault, signed by the vault authority
+fn transfer_from_vault<'a>(
+    token_program_account: &AccountInfo<'a>,
+    source_account: &AccountInfo<'a>,
+    source_authority_account: &AccountInfo<'a>,
+    source_authority_account_seeds: &[&[u8]],
+    destination_account: &AccountInfo<'a>,
+    amount: u64) -> ProgramResult {
+
     let ix = transfer(
         token_program_account.key, 
         source_account.key, 
@@ -102,14 +223,6 @@ pub fn process_withdraw(
             source_authority_account.clone(), 
             token_program_account.clone()
         ],
-        &[&source_authority_account_seeds],
-    )?;
-
-    user_account_object.balance -= amount;
-    user_account_object.serialize(&mut &mut user_account.data.borrow_mut()[..])?;
-
-    msg!("Withdrawn {}. Updated user account [id={}, balance={}, blocked_amount={}]", 
-        amount, user_id, user_account_object.balance, user_account_object.blocked_amount);
-
-    Ok(())
-}
\ No newline at end of file
+        &[source_authority_account_seeds],
+    )
+}
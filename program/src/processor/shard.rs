@@ -0,0 +1,64 @@
+//! Access to the users stored in ledger shards
+use crate::state::{ShardHeader, ShardSlot, UserId};
+
+use solana_program::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    msg
+};
+use std::cell::RefMut;
+
+/// Checks whether the given account is a ledger shard rather than a user account
+pub fn is_shard(program_id: &Pubkey, account: &AccountInfo) -> bool {
+    account.owner == program_id && account.data_len() == ShardHeader::ACCOUNT_LEN
+}
+
+/// Returns the slot of the given user for in place updates, after checking that the shard stores it.
+/// A free slot is assigned to the kind of the user id. The shard data stays borrowed until the slot is dropped.
+pub fn load_shard_slot<'a>(
+    program_id: &Pubkey,
+    shard_account: &'a AccountInfo,
+    token_mint_key: &Pubkey,
+    operator_key: &Pubkey,
+    user_id: UserId) -> Result<RefMut<'a, ShardSlot>, ProgramError> {
+
+    if !is_shard(program_id, shard_account) {
+        msg!("Provided shard account is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let user_number = shard_user_number(user_id)?;
+    let data = shard_account.data.borrow_mut();
+    let shard_header = ShardHeader::load(&data)?;
+
+    if shard_header.token_mint != *token_mint_key
+        || shard_header.operator != *operator_key
+        || shard_header.shard_index != ShardHeader::shard_index(user_number) {
+        msg!("Provided shard account does not store user {}", user_id);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Checked before mapping the data to the slot, which cannot fail afterwards
+    ShardSlot::load(&data, user_number)?;
+    let mut shard_slot = RefMut::map(data, |data| 
+        bytemuck::from_bytes_mut::<ShardSlot>(&mut data[ShardHeader::slot_range(user_number)]));
+    let seed_len = user_id.seed().len() as u64;
+
+    if shard_slot.seed_len == 0 {
+        shard_slot.seed_len = seed_len;
+    } else if shard_slot.seed_len != seed_len {
+        msg!("The slot of user {} stores a user with another kind of id", user_id);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(shard_slot)
+}
+
+/// Returns the numeric id locating the user in the ledger shards
+fn shard_user_number(user_id: UserId) -> Result<u64, ProgramError> {
+    user_id.number().ok_or_else(|| {
+        msg!("Ledger shards only store users with numeric ids");
+        ProgramError::InvalidArgument
+    })
+}
@@ -1,8 +1,610 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::MAX_PERMITTED_DATA_INCREASE, msg, program_error::ProgramError, pubkey::Pubkey,
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    ops::Range,
+};
 
-/// Define the type of state stored in accounts
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+/// Seed prefix of the vault authority, the owner of the token account holding the deposited tokens
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix of the operator config account
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed prefix of allowance accounts
+pub const ALLOWANCE_SEED: &[u8] = b"allowance";
+
+/// Seed prefix of scheduled transfer accounts
+pub const SCHEDULED_TRANSFER_SEED: &[u8] = b"scheduled_transfer";
+
+/// Seed prefix of subscription accounts
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+
+/// Seed prefix of escrow accounts
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// Seed prefix of game pot accounts
+pub const POT_SEED: &[u8] = b"pot";
+
+/// Denominator of the pot rake, expressed in basis points
+pub const RAKE_BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+/// Seed prefix of the operator reward pool account
+pub const REWARD_POOL_SEED: &[u8] = b"reward_pool";
+
+/// Scale of the reward per unit of balance, so that small rewards over large balances are not lost
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Seed prefix of ledger shard accounts
+pub const SHARD_SEED: &[u8] = b"shard";
+
+/// Number of users stored in each shard of the ledger
+pub const SHARD_SLOT_COUNT: u64 = 8192;
+
+/// The first field of the program accounts holding escrowed amounts, so that an account of 
+/// another type cannot be passed in their place even when its data happens to deserialize
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AccountType {
+    Uninitialized,
+    ScheduledTransfer,
+    Subscription,
+    Escrow,
+    Pot,
+}
+
+/// A program account starting with its `AccountType`
+pub trait TaggedAccount: BorshDeserialize {
+    const ACCOUNT_TYPE: AccountType;
+
+    /// Deserializes the account, rejecting the accounts of the other types
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.first() != Some(&(Self::ACCOUNT_TYPE as u8)) {
+            msg!("The account is not a {:?} account", Self::ACCOUNT_TYPE);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self::try_from_slice(data)?)
+    }
+}
+
+/// The external identifier of a user, the first seed of its account address
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UserId {
+    /// A 32-bit id, the only kind supported by the accounts created before wider ids.
+    /// Those accounts remain reachable with this kind only.
+    U32(u32),
+    /// A 64-bit id. The account differs from the one of the same `U32` id.
+    U64(u64),
+    /// An opaque 32-byte id, such as a UUID padded with zeros or a hash
+    Bytes([u8; 32]),
+}
+
+impl UserId {
+    /// Returns the seed identifying the user in the account address.
+    /// Each kind has a distinct seed length, so different kinds never share an address.
+    pub fn seed(&self) -> Vec<u8> {
+        match self {
+            UserId::U32(user_id) => user_id.to_le_bytes().to_vec(),
+            UserId::U64(user_id) => user_id.to_le_bytes().to_vec(),
+            UserId::Bytes(user_id) => user_id.to_vec(),
+        }
+    }
+
+    /// Checks whether accounts cannot be created for the id. User 0 was the vault authority
+    /// before the vault had its own seed prefix, so its account may still hold a former vault.
+    pub fn is_reserved(&self) -> bool {
+        matches!(self, UserId::U32(0))
+    }
+
+    /// Returns the id of the given seed, the inverse of `seed`
+    pub fn from_seed(seed: &[u8]) -> Option<Self> {
+        match seed.len() {
+            4 => Some(UserId::U32(u32::from_le_bytes(seed.try_into().ok()?))),
+            8 => Some(UserId::U64(u64::from_le_bytes(seed.try_into().ok()?))),
+            32 => Some(UserId::Bytes(seed.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Returns the numeric value of the id, or `None` for an opaque id
+    pub fn number(&self) -> Option<u64> {
+        match self {
+            UserId::U32(user_id) => Some(*user_id as u64),
+            UserId::U64(user_id) => Some(*user_id),
+            UserId::Bytes(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for UserId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserId::U32(user_id) => write!(f, "{}", user_id),
+            UserId::U64(user_id) => write!(f, "{}", user_id),
+            UserId::Bytes(user_id) => user_id.iter().try_for_each(|byte| write!(f, "{:02x}", byte)),
+        }
+    }
+}
+
+impl From<u32> for UserId {
+    fn from(user_id: u32) -> Self {
+        UserId::U32(user_id)
+    }
+}
+
+impl From<u64> for UserId {
+    fn from(user_id: u64) -> Self {
+        UserId::U64(user_id)
+    }
+}
+
+impl From<[u8; 32]> for UserId {
+    fn from(user_id: [u8; 32]) -> Self {
+        UserId::Bytes(user_id)
+    }
+}
+
+/// A user id stored in an account, as its seed padded with zeros
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PackedUserId {
+    /// The length of the seed. 0 means the id is not stored.
+    pub seed_len: u64,
+    pub seed: [u8; 32],
+}
+
+impl PackedUserId {
+    pub const LEN: usize = 8 + 32;
+
+    /// Returns the stored id, or `None` for the accounts created before the id was stored
+    pub fn user_id(&self) -> Option<UserId> {
+        self.seed.get(..self.seed_len as usize).and_then(UserId::from_seed)
+    }
+}
+
+impl From<UserId> for PackedUserId {
+    fn from(user_id: UserId) -> Self {
+        let user_id_seed = user_id.seed();
+        let mut seed = [0u8; 32];
+        seed[..user_id_seed.len()].copy_from_slice(&user_id_seed);
+
+        PackedUserId { seed_len: user_id_seed.len() as u64, seed }
+    }
+}
+
+/// Define the type of state stored in accounts. The Borsh encoding matches the in-memory 
+/// layout, so processors access the account data in place with `load` and `load_mut`.
+/// Only the user accounts and the ledger shards, changed by every balance update, are accessed 
+/// in place. The other program accounts are rarely written and are deserialized with Borsh.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Pod, Zeroable)]
 pub struct UserAccount {
     pub balance: u64,
     pub blocked_amount: u64,
+    /// The wallet whose associated token account receives all withdrawals.
+    /// `Pubkey::default()` means no wallet is registered.
+    pub withdrawal_wallet: Pubkey,
+    /// The wallet that replaces `withdrawal_wallet` at `withdrawal_wallet_change_time`.
+    /// `Pubkey::default()` means no change is pending.
+    pub pending_withdrawal_wallet: Pubkey,
+    pub withdrawal_wallet_change_time: i64,
+    /// The key allowed to sign transfers and withdrawals instead of the operator.
+    /// `Pubkey::default()` means the account is operated by the operator only.
+    pub owner: Pubkey,
+    /// The schedule that gradually unlocks a part of the balance
+    pub vesting: Vesting,
+    /// The rewards settled for the balance held so far
+    pub reward: RewardCheckpoint,
+    /// The maximum `debt` the user can take on when transferring more than its available balance
+    pub credit_limit: u64,
+    /// The amount spent on credit, repaid first by the next deposits
+    pub debt: u64,
+    /// The mint of the account, so that the accounts can be listed without knowing their seeds.
+    /// The accounts created before the mint was stored are shorter, see `LEGACY_LENS`, 
+    /// and get their mint from `MigrateUserAccount`.
+    pub token_mint: Pubkey,
+    /// The id of the user of the account
+    pub user_id: PackedUserId,
+}
+
+impl UserAccount {
+    pub const LEN: usize = 8 + 8 + 32 + 32 + 8 + 32 + Vesting::LEN + RewardCheckpoint::LEN + 8 + 8 + 32 + PackedUserId::LEN;
+
+    /// The sizes of the user accounts created by the previous versions of the program, 
+    /// which `MigrateUserAccount` grows to `LEN`. Each layout is a prefix of the next one.
+    pub const LEGACY_LENS: [usize; 6] = [
+        8 + 8,
+        8 + 8 + 32 + 32 + 8,
+        8 + 8 + 32 + 32 + 8 + 32,
+        8 + 8 + 32 + 32 + 8 + 32 + Vesting::LEN,
+        8 + 8 + 32 + 32 + 8 + 32 + Vesting::LEN + RewardCheckpoint::LEN,
+        8 + 8 + 32 + 32 + 8 + 32 + Vesting::LEN + RewardCheckpoint::LEN + 8 + 8,
+    ];
+
+    /// The offset of `token_mint` in the account data, to filter the accounts of a mint
+    pub const TOKEN_MINT_OFFSET: usize = Self::LEN - 32 - PackedUserId::LEN;
+
+    /// Returns the user account stored in the given account data without copying it
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Returns the user account stored in the given account data for in place updates
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Returns the part of the balance that is neither blocked nor unvested at `now`
+    pub fn available_balance(&self, now: i64) -> u64 {
+        self.balance
+            .saturating_sub(self.blocked_amount)
+            .saturating_sub(self.vesting.unvested_amount(now))
+    }
+
+    /// Checks whether the given account is the registered owner and has signed the instruction
+    pub fn is_signed_by_owner(&self, account: &AccountInfo) -> bool {
+        self.owner != Pubkey::default() && account.is_signer && *account.key == self.owner
+    }
+
+    /// Makes the pending withdrawal wallet the registered one once its delay has elapsed
+    pub fn apply_pending_withdrawal_wallet(&mut self, now: i64) {
+        if self.pending_withdrawal_wallet != Pubkey::default()
+            && now >= self.withdrawal_wallet_change_time {
+            self.withdrawal_wallet = self.pending_withdrawal_wallet;
+            self.pending_withdrawal_wallet = Pubkey::default();
+            self.withdrawal_wallet_change_time = 0;
+        }
+    }
+}
+
+/// A vesting schedule. Nothing is vested before the cliff, then the `total_amount` 
+/// vests linearly from `start_time` until `start_time + duration`.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Vesting {
+    pub start_time: i64,
+    /// Number of seconds after `start_time` before anything is vested
+    pub cliff_duration: i64,
+    /// Number of seconds after `start_time` until everything is vested
+    pub duration: i64,
+    pub total_amount: u64,
+}
+
+impl Vesting {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    /// Returns the part of `total_amount` vested at `now`
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start_time);
+
+        if elapsed < self.cliff_duration {
+            0
+        } else if elapsed >= self.duration {
+            self.total_amount
+        } else {
+            (self.total_amount as u128 * elapsed as u128 / self.duration as u128) as u64
+        }
+    }
+
+    /// Returns the part of `total_amount` still locked at `now`
+    pub fn unvested_amount(&self, now: i64) -> u64 {
+        self.total_amount - self.vested_amount(now)
+    }
+}
+
+/// The position of a user in the reward pool
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct RewardCheckpoint {
+    /// The reward pool the user is enrolled in.
+    /// `Pubkey::default()` means the user does not earn rewards.
+    pub reward_pool: Pubkey,
+    /// The reward per unit of balance of the pool when the rewards were last settled,
+    /// as a little-endian `u128` that does not require 16-byte alignment
+    pub reward_per_share: [u8; 16],
+    /// The rewards settled but not claimed yet
+    pub pending_amount: u64,
+}
+
+impl RewardCheckpoint {
+    pub const LEN: usize = 32 + 16 + 8;
+
+    /// Adds the rewards earned by `balance` since the last settlement to `pending_amount`
+    pub fn settle(&mut self, balance: u64, reward_per_share: u128) -> Result<(), ProgramError> {
+        let earned_amount = reward_per_share
+            .checked_sub(u128::from_le_bytes(self.reward_per_share))
+            .and_then(|reward_per_share_delta| reward_per_share_delta.checked_mul(balance as u128))
+            .map(|earned_amount| earned_amount / REWARD_PRECISION)
+            .and_then(|earned_amount| u64::try_from(earned_amount).ok())
+            .ok_or(ProgramError::InvalidArgument)?;
+
+        self.pending_amount = self.pending_amount
+            .checked_add(earned_amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        self.reward_per_share = reward_per_share.to_le_bytes();
+
+        Ok(())
+    }
+}
+
+/// The party allowed to spend from another user's balance
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum Delegate {
+    /// Another user, acting through the owner key registered on its account
+    User(UserId),
+    /// An external key
+    Key(Pubkey),
+}
+
+impl Delegate {
+    /// Returns the seed identifying the delegate in the allowance account address
+    pub fn seed(&self) -> Vec<u8> {
+        match self {
+            Delegate::User(user_id) => user_id.seed(),
+            Delegate::Key(key) => key.to_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the seed identifying the kind of the delegate in the allowance account address,
+    /// so that a key and the user with a 32-byte id of the same bytes do not share an allowance
+    pub fn kind_seed(&self) -> [u8; 1] {
+        match self {
+            Delegate::Key(_) => [0],
+            Delegate::User(_) => [1],
+        }
+    }
+}
+
+/// The amount a delegate may still spend from the balance of a user
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Allowance {
+    pub delegate: Delegate,
+    pub amount: u64,
+}
+
+/// A transfer between two users that can be executed after `execute_at`.
+/// The `amount` is escrowed in this account until then. The account is sized to its data.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ScheduledTransfer {
+    pub account_type: AccountType,
+    pub sender_user_id: UserId,
+    pub sender_account: Pubkey,
+    pub recipient_user_id: UserId,
+    pub recipient_account: Pubkey,
+    pub amount: u64,
+    /// Unix timestamp after which anyone can execute the transfer
+    pub execute_at: i64,
+    /// The operator that may cancel the transfer and receives the rent back
+    pub operator: Pubkey,
+}
+
+impl TaggedAccount for ScheduledTransfer {
+    const ACCOUNT_TYPE: AccountType = AccountType::ScheduledTransfer;
+}
+
+/// A recurring payment from a subscriber to a merchant, 
+/// charged at most once per `period` up to `amount_per_period`. The account is sized to its data.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Subscription {
+    pub account_type: AccountType,
+    pub subscriber_user_id: UserId,
+    pub subscriber_account: Pubkey,
+    pub merchant_user_id: UserId,
+    pub merchant_account: Pubkey,
+    pub amount_per_period: u64,
+    /// Number of seconds between two charges
+    pub period: i64,
+    /// Unix timestamp from which the next charge is allowed
+    pub next_charge_time: i64,
+    pub operator: Pubkey,
+    /// The account that paid the rent and receives it back on cancellation
+    pub payer: Pubkey,
+}
+
+impl TaggedAccount for Subscription {
+    const ACCOUNT_TYPE: AccountType = AccountType::Subscription;
+}
+
+/// Stakes of two users locked until the `arbiter` resolves them,
+/// or refundable by anyone after `refund_time`. The account is sized to its data.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Escrow {
+    pub account_type: AccountType,
+    pub party_a_user_id: UserId,
+    pub party_a_account: Pubkey,
+    pub party_a_stake: u64,
+    /// The amount party A has locked so far, either 0 or `party_a_stake`
+    pub party_a_funded: u64,
+    pub party_b_user_id: UserId,
+    pub party_b_account: Pubkey,
+    pub party_b_stake: u64,
+    /// The amount party B has locked so far, either 0 or `party_b_stake`
+    pub party_b_funded: u64,
+    pub arbiter: Pubkey,
+    /// Unix timestamp after which anyone can refund the unresolved escrow
+    pub refund_time: i64,
+    /// The operator that paid the rent and receives it back when the escrow is closed
+    pub operator: Pubkey,
+}
+
+impl TaggedAccount for Escrow {
+    const ACCOUNT_TYPE: AccountType = AccountType::Escrow;
+}
+
+/// Stakes of any number of users locked until the operator settles the game
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Pot {
+    pub account_type: AccountType,
+    pub token_mint: Pubkey,
+    /// The operator that settles the pot and receives the rent back
+    pub operator: Pubkey,
+    /// Sum of the stakes joined so far
+    pub amount: u64,
+}
+
+impl Pot {
+    pub const LEN: usize = 1 + 32 + 32 + 8;
+}
+
+impl TaggedAccount for Pot {
+    const ACCOUNT_TYPE: AccountType = AccountType::Pot;
+}
+
+/// A user receiving a share of the pot proportional to `weight`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PotWinner {
+    pub user_id: UserId,
+    pub user_account_bump_seed: u8,
+    pub weight: u64,
+}
+
+/// The part of the pot kept by the house user before the winners are paid
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PotRake {
+    pub house_user_id: UserId,
+    pub house_account_bump_seed: u8,
+    pub basis_points: u16,
+}
+
+/// Rewards distributed to the enrolled users in proportion to their balances
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RewardPool {
+    pub token_mint: Pubkey,
+    pub operator: Pubkey,
+    /// The rewards distributed per unit of balance since the pool was created, scaled by `REWARD_PRECISION`
+    pub reward_per_share: u128,
+    /// Sum of the balances of the enrolled users
+    pub total_shares: u64,
+    /// The rewards distributed but not claimed yet, including rounding leftovers
+    pub unclaimed_amount: u64,
+}
+
+impl RewardPool {
+    pub const LEN: usize = 32 + 32 + 16 + 8 + 8;
+}
+
+/// The header of a ledger shard account. Instead of an account per user, a shard stores 
+/// the balances of the users `shard_index * SHARD_SLOT_COUNT..(shard_index + 1) * SHARD_SLOT_COUNT` 
+/// in fixed-size slots following the header, which are read and written one at a time.
+/// Only users with numeric ids can be stored in shards, and a slot holds a single kind of id,
+/// so `U32(n)` and `U64(n)` cannot both be stored in the slot `n`.
+/// Like `UserAccount`, the header and the slots are accessed in place.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ShardHeader {
+    /// `Pubkey::default()` until the shard is initialized
+    pub token_mint: Pubkey,
+    pub operator: Pubkey,
+    pub shard_index: u64,
+}
+
+impl ShardHeader {
+    pub const LEN: usize = 32 + 32 + 8;
+    /// Offset of the first slot, keeping the slots 8-byte aligned
+    pub const SLOTS_OFFSET: usize = 72;
+    /// Size of a whole shard account
+    pub const ACCOUNT_LEN: usize = Self::SLOTS_OFFSET + SHARD_SLOT_COUNT as usize * ShardSlot::LEN;
+    /// Number of `InitializeShard` calls allocating a whole shard account
+    pub const ALLOCATION_COUNT: usize = Self::ACCOUNT_LEN / MAX_PERMITTED_DATA_INCREASE 
+        + (Self::ACCOUNT_LEN % MAX_PERMITTED_DATA_INCREASE != 0) as usize;
+
+    /// Returns the shard header stored at the start of the given account data
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(&data[..Self::LEN]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Returns the shard header stored at the start of the given account data for in place updates
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(&mut data[..Self::LEN]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Returns the index of the shard storing the user with the given numeric id
+    pub fn shard_index(user_id: u64) -> u64 {
+        user_id / SHARD_SLOT_COUNT
+    }
+
+    /// Returns the range of the shard account data holding the slot of the user with the given numeric id
+    pub fn slot_range(user_id: u64) -> Range<usize> {
+        let offset = Self::SLOTS_OFFSET + (user_id % SHARD_SLOT_COUNT) as usize * ShardSlot::LEN;
+        offset..offset + ShardSlot::LEN
+    }
+}
+
+/// The state of a user stored in a shard
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ShardSlot {
+    /// The length of the seed of the id stored in the slot, which identifies its kind.
+    /// 0 means the slot is free.
+    pub seed_len: u64,
+    pub balance: u64,
+    pub blocked_amount: u64,
+}
+
+impl ShardSlot {
+    pub const LEN: usize = 8 + 8 + 8;
+
+    /// Returns the id of the user stored in the slot of the given numeric id, or `None` if the slot is free
+    pub fn user_id(&self, user_id: u64) -> Option<UserId> {
+        UserId::from_seed(user_id.to_le_bytes().get(..self.seed_len as usize)?)
+    }
+
+    /// Returns the slot of the user with the given numeric id in the given shard account data
+    pub fn load(data: &[u8], user_id: u64) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(&data[ShardHeader::slot_range(user_id)]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Returns the slot of the user with the given numeric id in the given shard account data for in place updates
+    pub fn load_mut(data: &mut [u8], user_id: u64) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(&mut data[ShardHeader::slot_range(user_id)]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Operator-wide settings shared by all user accounts of a token mint
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Config {
+    /// Number of seconds before a withdrawal wallet change takes effect
+    pub withdrawal_wallet_delay: i64,
+    /// A lower delay that replaces `withdrawal_wallet_delay` at `withdrawal_wallet_delay_change_time`,
+    /// so that the operator cannot lower the delay to change a wallet sooner than the current delay
+    pub pending_withdrawal_wallet_delay: i64,
+    /// 0 when no lower delay is pending
+    pub withdrawal_wallet_delay_change_time: i64,
+}
+
+impl Config {
+    pub const LEN: usize = 8 + 8 + 8;
+
+    /// Returns the withdrawal wallet delay in effect at `now`
+    pub fn effective_withdrawal_wallet_delay(&self, now: i64) -> i64 {
+        if self.withdrawal_wallet_delay_change_time != 0 && now >= self.withdrawal_wallet_delay_change_time {
+            self.pending_withdrawal_wallet_delay
+        } else {
+            self.withdrawal_wallet_delay
+        }
+    }
+
+    /// Sets the withdrawal wallet delay. A higher delay takes effect immediately, 
+    /// a lower one only once the delay in effect at `now` has elapsed.
+    pub fn set_withdrawal_wallet_delay(&mut self, withdrawal_wallet_delay: i64, now: i64) -> Result<(), ProgramError> {
+        let current_delay = self.effective_withdrawal_wallet_delay(now);
+
+        if withdrawal_wallet_delay >= current_delay {
+            self.withdrawal_wallet_delay = withdrawal_wallet_delay;
+            self.pending_withdrawal_wallet_delay = 0;
+            self.withdrawal_wallet_delay_change_time = 0;
+        } else {
+            self.withdrawal_wallet_delay = current_delay;
+            self.pending_withdrawal_wallet_delay = withdrawal_wallet_delay;
+            self.withdrawal_wallet_delay_change_time = now
+                .checked_add(current_delay)
+                .ok_or(ProgramError::InvalidArgument)?;
+        }
+
+        Ok(())
+    }
 }
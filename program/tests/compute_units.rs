@@ -0,0 +1,155 @@
+#![cfg(feature = "test-bpf")]
+//! Compute units consumed by the most frequent instructions. Run with `cargo test-bpf` or 
+//! `scripts/compute-units.sh`: the test always executes the BPF program with the BPF loader, so that 
+//! its compute units are metered like on chain, and fails if the program was not built.
+#[allow(dead_code)]
+mod program_test;
+
+use solana_program_test::tokio;
+use solana_program::instruction::Instruction;
+
+use solana_sdk::{
+    signature::Signer,
+    transaction::Transaction
+};
+
+use emotion_user_account::{
+    instruction::{deposit, withdraw, transfer, block, unblock},
+    state::UserId,
+};
+
+use program_test::UserAccountProgramTest;
+
+use spl_associated_token_account::{ 
+    get_associated_token_address,
+    instruction::create_associated_token_account
+};
+
+// Maximum compute units per instruction, without the `verbose-logging` feature. 
+// Lower a budget after an optimization so that later changes cannot silently undo it.
+const DEPOSIT_COMPUTE_UNITS: u64 = 5_000;
+const WITHDRAW_COMPUTE_UNITS: u64 = 25_000;
+const TRANSFER_COMPUTE_UNITS: u64 = 8_000;
+const BLOCK_COMPUTE_UNITS: u64 = 5_000;
+const UNBLOCK_COMPUTE_UNITS: u64 = 5_000;
+
+/// Simulates a transaction with the single given instruction and returns the compute units it consumed
+async fn compute_units(program_test: &mut UserAccountProgramTest, name: &str, instruction: Instruction) -> u64 {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    let simulation = program_test.context.banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+
+    simulation.result.unwrap().unwrap();
+
+    let units_consumed = simulation.simulation_details.unwrap().units_consumed;
+    println!("{}: {} compute units", name, units_consumed);
+
+    units_consumed
+}
+
+#[tokio::test]
+async fn test_compute_units() {
+    let mint_amount = 100000000000u64;
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new_bpf().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+
+    let (source_account_key, source_token_account_key, source_bump) = 
+        program_test.with_source_user(mint_amount).await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.create_user_account(sender_user_id, amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &program_test.context.payer.pubkey(),
+                &token_mint_key,
+                &spl_token::id()
+            )
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+
+    let deposit_units = compute_units(
+        &mut program_test, 
+        "Deposit",
+        deposit(&program_id, sender_user_id, sender_bump, amount, &sender_account_key, &token_mint_key, &payer_key).unwrap()
+    ).await;
+
+    let withdraw_units = compute_units(
+        &mut program_test, 
+        "Withdraw",
+        withdraw(
+            &program_id, 
+            sender_user_id, 
+            sender_bump, 
+            source_bump,
+            amount,
+            &sender_account_key,
+            &token_mint_key,
+            &source_token_account_key,
+            &source_account_key,
+            &get_associated_token_address(&payer_key, &token_mint_key),
+            &payer_key
+        ).unwrap()
+    ).await;
+
+    let transfer_units = compute_units(
+        &mut program_test, 
+        "Transfer",
+        transfer(
+            &program_id, 
+            &token_mint_key,
+            sender_user_id, 
+            sender_bump,
+            &sender_account_key,
+            recipient_user_id, 
+            recipient_bump,
+            &recipient_account_key, 
+            amount,
+            &payer_key
+        ).unwrap()
+    ).await;
+
+    let block_units = compute_units(
+        &mut program_test, 
+        "Block",
+        block(&program_id, sender_user_id, sender_bump, amount, &sender_account_key, &token_mint_key, &payer_key).unwrap()
+    ).await;
+
+    // Nothing is blocked yet, as the simulations are not committed
+    let unblock_units = compute_units(
+        &mut program_test, 
+        "Unblock",
+        unblock(&program_id, sender_user_id, sender_bump, 0, &sender_account_key, &token_mint_key, &payer_key).unwrap()
+    ).await;
+
+    assert!(deposit_units <= DEPOSIT_COMPUTE_UNITS, "Deposit regressed to {} compute units", deposit_units);
+    assert!(withdraw_units <= WITHDRAW_COMPUTE_UNITS, "Withdraw regressed to {} compute units", withdraw_units);
+    assert!(transfer_units <= TRANSFER_COMPUTE_UNITS, "Transfer regressed to {} compute units", transfer_units);
+    assert!(block_units <= BLOCK_COMPUTE_UNITS, "Block regressed to {} compute units", block_units);
+    assert!(unblock_units <= UNBLOCK_COMPUTE_UNITS, "Unblock regressed to {} compute units", unblock_units);
+}
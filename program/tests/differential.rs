@@ -0,0 +1,218 @@
+#![cfg(feature = "test-bpf")]
+mod model;
+#[allow(dead_code)]
+mod program_test;
+
+use std::collections::HashSet;
+
+use solana_program_test::*;
+
+use borsh::BorshDeserialize;
+
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+
+use solana_sdk::{
+    signature::{Signature, Signer},
+    transaction::{Transaction, TransactionError}
+};
+
+use emotion_user_account::{
+    error::UserAccountError,
+    instruction::{
+        block_derived, create_derived, deposit_derived, get_user_account_address,
+        transfer_derived, unblock_derived, withdraw_derived
+    },
+    state::{UserAccount, UserId},
+};
+
+use model::{LedgerModel, ModelError, Operation};
+use program_test::UserAccountProgramTest;
+
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+
+// The user 0 is reserved, and the same numbers of different kinds are distinct users
+const USER_IDS: [UserId; 6] = [
+    UserId::U32(0), UserId::U32(1), UserId::U32(2), UserId::U32(3), UserId::U64(1), UserId::Bytes([1; 32])
+];
+// The other operations take smaller amounts than deposits, so that most of them succeed
+const MAX_DEPOSIT_AMOUNT: u64 = 1000;
+const MAX_AMOUNT: u64 = 250;
+const OPERATION_COUNT: usize = 100;
+const SEEDS: [u64; 4] = [1, 2, 3, 0x5eed];
+
+/// A xorshift generator, so that a failing sequence can be replayed from its seed
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn user_id(&mut self) -> UserId {
+        USER_IDS[self.below(USER_IDS.len() as u64) as usize]
+    }
+
+    fn amount(&mut self) -> u64 {
+        self.below(MAX_AMOUNT + 1)
+    }
+
+    fn deposit_amount(&mut self) -> u64 {
+        self.below(MAX_DEPOSIT_AMOUNT + 1)
+    }
+
+    fn operation(&mut self) -> Operation {
+        match self.below(10) {
+            0 | 1 => Operation::Create { user_id: self.user_id() },
+            2 | 3 => Operation::Deposit { user_id: self.user_id(), amount: self.deposit_amount() },
+            4 => Operation::Withdraw { user_id: self.user_id(), amount: self.amount() },
+            5 | 6 => Operation::Transfer {
+                sender_user_id: self.user_id(),
+                recipient_user_id: self.user_id(),
+                amount: self.amount()
+            },
+            7 | 8 => Operation::Block { user_id: self.user_id(), amount: self.amount() },
+            _ => Operation::Unblock { user_id: self.user_id(), amount: self.amount() },
+        }
+    }
+}
+
+/// The error the program returns for a rejection of the model
+fn program_error(error: ModelError) -> TransactionError {
+    let instruction_error = match error {
+        ModelError::ReservedUserId => InstructionError::Custom(UserAccountError::ReservedUserId as u32),
+        // `SystemError::AccountAlreadyInUse`, returned by the creation of the account
+        ModelError::AccountAlreadyExists => InstructionError::Custom(0),
+        ModelError::AccountNotFound => InstructionError::InvalidAccountData,
+        ModelError::InsufficientAvailableBalance | ModelError::InsufficientBlockedAmount =>
+            InstructionError::InvalidArgument,
+    };
+
+    TransactionError::InstructionError(0, instruction_error)
+}
+
+struct Harness {
+    program_test: UserAccountProgramTest,
+    token_mint_key: Pubkey,
+    destination_key: Pubkey,
+    sent_signatures: HashSet<Signature>,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let mut program_test = UserAccountProgramTest::start_new().await;
+        let token_mint_key = program_test.with_token_mint().await;
+        program_test.with_source_user(u64::MAX).await;
+
+        let payer_key = program_test.context.payer.pubkey();
+        let transaction = Transaction::new_signed_with_payer(
+            &[create_associated_token_account(&payer_key, &payer_key, &token_mint_key, &spl_token::id())],
+            Some(&payer_key),
+            &[&program_test.context.payer],
+            program_test.context.last_blockhash
+        );
+        program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+        Harness {
+            program_test,
+            token_mint_key,
+            destination_key: get_associated_token_address(&payer_key, &token_mint_key),
+            sent_signatures: HashSet::new(),
+        }
+    }
+
+    async fn execute(&mut self, operation: Operation) -> Result<(), TransactionError> {
+        let program_id = self.program_test.program_id;
+        let token_mint_key = self.token_mint_key;
+        let payer_key = self.program_test.context.payer.pubkey();
+
+        let instruction = match operation {
+            Operation::Create { user_id } =>
+                create_derived(&program_id, user_id, &token_mint_key, &payer_key),
+            Operation::Deposit { user_id, amount } =>
+                deposit_derived(&program_id, user_id, amount, &token_mint_key, &payer_key),
+            Operation::Withdraw { user_id, amount } =>
+                withdraw_derived(&program_id, user_id, amount, &token_mint_key, &self.destination_key, &payer_key),
+            Operation::Transfer { sender_user_id, recipient_user_id, amount } =>
+                transfer_derived(&program_id, &token_mint_key, sender_user_id, recipient_user_id, amount, &payer_key),
+            Operation::Block { user_id, amount } =>
+                block_derived(&program_id, user_id, amount, &token_mint_key, &payer_key),
+            Operation::Unblock { user_id, amount } =>
+                unblock_derived(&program_id, user_id, amount, &token_mint_key, &payer_key),
+        }.unwrap();
+
+        let mut transaction = Transaction::new_signed_with_payer(
+            &[instruction.clone()],
+            Some(&payer_key),
+            &[&self.program_test.context.payer],
+            self.program_test.context.last_blockhash
+        );
+
+        // The same operation twice with the same blockhash would be rejected as already processed
+        if !self.sent_signatures.insert(transaction.signatures[0]) {
+            let context = &mut self.program_test.context;
+            context.last_blockhash = context.banks_client
+                .get_new_latest_blockhash(&context.last_blockhash)
+                .await
+                .unwrap();
+            self.sent_signatures.clear();
+
+            transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer_key),
+                &[&context.payer],
+                context.last_blockhash
+            );
+            self.sent_signatures.insert(transaction.signatures[0]);
+        }
+
+        self.program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|error| error.unwrap())
+    }
+
+    async fn user_account(&mut self, user_id: UserId) -> Option<UserAccount> {
+        let (user_account_key, _) = get_user_account_address(
+            &self.program_test.program_id,
+            user_id,
+            &self.token_mint_key,
+            &self.program_test.context.payer.pubkey());
+
+        self.program_test.context.banks_client
+            .get_account(user_account_key)
+            .await
+            .unwrap()
+            .map(|account| UserAccount::try_from_slice(&account.data).unwrap())
+    }
+}
+
+#[tokio::test]
+async fn test_program_matches_model() {
+    for seed in SEEDS {
+        let mut random = Random(seed);
+        let mut model = LedgerModel::default();
+        let mut harness = Harness::start().await;
+
+        for step in 0..OPERATION_COUNT {
+            let operation = random.operation();
+
+            let expected = model.apply(operation).map_err(program_error);
+            let actual = harness.execute(operation).await;
+            assert_eq!(actual, expected, "Seed {}, step {}: {:?}", seed, step, operation);
+
+            for user_id in USER_IDS {
+                let expected = model.account(user_id).map(|account| (account.balance, account.blocked_amount));
+                let actual = harness.user_account(user_id).await
+                    .map(|account| (account.balance, account.blocked_amount));
+                assert_eq!(actual, expected, "Seed {}, step {}: user {} after {:?}", seed, step, user_id, operation);
+            }
+        }
+    }
+}
@@ -3,30 +3,53 @@ mod program_test;
 
 use solana_program_test::*;
 
+use borsh::BorshDeserialize;
+
 use solana_program::{
+    clock::Clock,
     instruction::InstructionError,
+    pubkey::Pubkey,
 };
 
 use solana_sdk::{
-    signature::Signer,
+    program_pack::Pack,
+    signature::{Keypair, Signer},
     transaction::{Transaction, TransactionError}
 };
 
 use emotion_user_account::{
-    instruction::{deposit, withdraw, transfer, block, unblock},
+    error::UserAccountError,
+    instruction::{
+        deposit, withdraw, transfer, block, unblock, 
+        set_config, set_withdrawal_wallet, cancel_withdrawal_wallet_change, cancel_withdrawal_wallet_change_by_owner,
+        set_owner, transfer_by_owner, withdraw_by_owner,
+        approve, revoke, transfer_from,
+        schedule_transfer, execute_scheduled_transfer, cancel_scheduled_transfer,
+        grant_vesting, subscribe, charge_subscription, cancel_subscription,
+        create_escrow, fund_escrow, resolve_escrow, refund_escrow,
+        create_pot, join_pot, settle_pot,
+        create_reward_pool, enroll_rewards, distribute_rewards, claim, with_reward_pool,
+        set_credit_limit, initialize_shard, migrate_user_account, migrate_vault,
+        get_user_account_address, get_allowance_address, get_shard_address, get_legacy_vault_authority_address, 
+        create_derived, deposit_derived, withdraw_derived, transfer_derived, block_derived
+    },
+    state::{
+        AccountType, Delegate, PotRake, PotWinner, ShardHeader, ShardSlot, UserAccount, UserId, Vesting, 
+        SCHEDULED_TRANSFER_SEED, SUBSCRIPTION_SEED, ESCROW_SEED, POT_SEED, REWARD_POOL_SEED
+    },
 };
 
 use program_test::UserAccountProgramTest;
 
 use spl_associated_token_account::{ 
     get_associated_token_address,
-    create_associated_token_account
+    instruction::create_associated_token_account
 };
 
 #[tokio::test]
 async fn test_user_account() {
     
-    let user_id = 100u32;
+    let user_id = UserId::U32(100);
     let amount = 5000000000u64;
 
     let mut program_test = UserAccountProgramTest::start_new().await;
@@ -78,7 +101,7 @@ async fn test_user_account() {
 #[tokio::test]
 async fn test_withdraw() {
     let mint_amount = 100000000000u64;
-    let user_id = 100u32;
+    let user_id = UserId::U32(100);
     let deposit_amount = 5000000000u64;
 
     let mut program_test = UserAccountProgramTest::start_new().await;
@@ -96,7 +119,8 @@ async fn test_withdraw() {
             create_associated_token_account(
                 &program_test.context.payer.pubkey(),
                 &program_test.context.payer.pubkey(),
-                &token_mint_key
+                &token_mint_key,
+                &spl_token::id()
             ),
             withdraw(
                 &program_test.program_id, 
@@ -121,8 +145,8 @@ async fn test_withdraw() {
 
 #[tokio::test]
 async fn test_transfer() {
-    let sender_user_id = 100u32;
-    let recipient_user_id = 101u32;
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
     let deposit_amount = 5000000000u64;
     let transfer_amount = 1000000000u64;
 
@@ -160,7 +184,7 @@ async fn test_transfer() {
 
 #[tokio::test]
 async fn test_block_too_big_amount_error() {
-    let user_id = 100u32;
+    let user_id = UserId::U32(100);
     let deposit_amount = 5000000000u64;
 
     let mut program_test = UserAccountProgramTest::start_new().await;
@@ -219,7 +243,7 @@ async fn test_block_too_big_amount_error() {
 
 #[tokio::test]
 async fn test_unblock_too_big_amount_error() {
-    let user_id = 100u32;
+    let user_id = UserId::U32(100);
     let deposit_amount = 5000000000u64;
 
     let mut program_test = UserAccountProgramTest::start_new().await;
@@ -270,7 +294,7 @@ async fn test_unblock_too_big_amount_error() {
 #[tokio::test]
 async fn test_withdraw_too_big_amount_error() {
     let mint_amount = 100000000000u64;
-    let user_id = 100u32;
+    let user_id = UserId::U32(100);
     let deposit_amount = 5000000000u64;
 
     let mut program_test = UserAccountProgramTest::start_new().await;
@@ -288,7 +312,8 @@ async fn test_withdraw_too_big_amount_error() {
             create_associated_token_account(
                 &program_test.context.payer.pubkey(),
                 &program_test.context.payer.pubkey(),
-                &token_mint_key
+                &token_mint_key,
+                &spl_token::id()
             ),
             block(
                 &program_test.program_id, 
@@ -330,8 +355,8 @@ async fn test_withdraw_too_big_amount_error() {
 
 #[tokio::test]
 async fn test_transfer_too_big_amount_error() {
-    let sender_user_id = 100u32;
-    let recipient_user_id = 101u32;
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
     let deposit_amount = 5000000000u64;
     let block_amount = 3000000000u64;
     let transfer_amount = 2100000000u64;
@@ -383,3 +408,2247 @@ async fn test_transfer_too_big_amount_error() {
         TransactionError::InstructionError(1, InstructionError::InvalidArgument)
     );
 }
+
+#[tokio::test]
+async fn test_withdrawal_wallet() {
+    let mint_amount = 100000000000u64;
+    let user_id = UserId::U32(100);
+    let deposit_amount = 5000000000u64;
+    let withdraw_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let (config_account_key, config_bump) = program_test.with_config(3600).await;
+    
+    let (source_account_key, source_token_account_key, source_bump) = 
+        program_test.with_source_user(mint_amount).await;
+    
+    let (user_account_key, user_bump) = 
+        program_test.with_user(user_id, deposit_amount).await;
+
+    let wallet = Keypair::new().pubkey();
+    let new_wallet = Keypair::new().pubkey();
+    let wallet_token_account_key = get_associated_token_address(&wallet, &token_mint_key);
+    let new_wallet_token_account_key = get_associated_token_address(&new_wallet, &token_mint_key);
+
+    let register_transaction = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &wallet,
+                &token_mint_key,
+                &spl_token::id()
+            ),
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &new_wallet,
+                &token_mint_key,
+                &spl_token::id()
+            ),
+            set_withdrawal_wallet(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                config_bump,
+                &wallet,
+                &user_account_key,
+                &config_account_key,
+                &token_mint_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            withdraw(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                source_bump,
+                withdraw_amount,
+                &user_account_key,
+                &token_mint_key,
+                &source_token_account_key,
+                &source_account_key,
+                &wallet_token_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            set_withdrawal_wallet(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                config_bump,
+                &new_wallet,
+                &user_account_key,
+                &config_account_key,
+                &token_mint_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(register_transaction).await.unwrap();
+
+    // The new wallet is still pending, so the withdrawal must go to the first one
+    let withdraw_transaction = Transaction::new_signed_with_payer(
+        &[
+            withdraw(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                source_bump,
+                withdraw_amount,
+                &user_account_key,
+                &token_mint_key,
+                &source_token_account_key,
+                &source_account_key,
+                &new_wallet_token_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(withdraw_transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(UserAccountError::InvalidWithdrawalDestination as u32)
+        )
+    );
+
+    let cancel_transaction = Transaction::new_signed_with_payer(
+        &[
+            cancel_withdrawal_wallet_change(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                &user_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            cancel_withdrawal_wallet_change(
+                &program_test.program_id, 
+                user_id, 
+                user_bump, 
+                &user_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(cancel_transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1, 
+            InstructionError::Custom(UserAccountError::NoPendingWithdrawalWalletChange as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_withdrawal_wallet_delay() {
+    let user_id = UserId::U32(100);
+    let delay = 3600;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+
+    let token_mint_key = program_test.with_token_mint().await;
+    let (config_account_key, config_bump) = program_test.with_config(delay).await;
+    let (user_account_key, user_bump) = program_test.with_user(user_id, 0).await;
+    let payer_key = program_test.context.payer.pubkey();
+    let program_id = program_test.program_id;
+    let owner = Keypair::new();
+
+    let set_wallet = |wallet: &Pubkey| set_withdrawal_wallet(
+        &program_id,
+        user_id,
+        user_bump,
+        config_bump,
+        wallet,
+        &user_account_key,
+        &config_account_key,
+        &token_mint_key,
+        &payer_key
+    ).unwrap();
+    let lower_delay = set_config(
+        &program_id,
+        config_bump,
+        0,
+        &config_account_key,
+        &token_mint_key,
+        &payer_key
+    ).unwrap();
+
+    let wallet = Pubkey::new_unique();
+    let new_wallet = Pubkey::new_unique();
+    let other_wallet = Pubkey::new_unique();
+
+    // Lowering the delay keeps the pending change, and the change requested next, at the current delay
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            set_owner(&program_id, user_id, user_bump, &owner.pubkey(), &user_account_key, &token_mint_key, &payer_key)
+                .unwrap(),
+            set_wallet(&wallet),
+            set_wallet(&new_wallet),
+            lower_delay,
+        ],
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let now = program_test.context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let user_account = program_test.context.banks_client.get_account(user_account_key).await.unwrap().unwrap();
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.withdrawal_wallet, wallet);
+    assert_eq!(user_account_object.pending_withdrawal_wallet, new_wallet);
+    assert_eq!(user_account_object.withdrawal_wallet_change_time, now + delay);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_wallet(&other_wallet)],
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client.get_account(user_account_key).await.unwrap().unwrap();
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.withdrawal_wallet, wallet);
+    assert_eq!(user_account_object.pending_withdrawal_wallet, other_wallet);
+    assert_eq!(user_account_object.withdrawal_wallet_change_time, now + delay);
+
+    // The owner can cancel the pending change without the operator
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            cancel_withdrawal_wallet_change_by_owner(
+                &program_id,
+                user_id,
+                user_bump,
+                &user_account_key,
+                &token_mint_key,
+                &payer_key,
+                &owner.pubkey()
+            ).unwrap()
+        ],
+        Some(&payer_key),
+        &[&program_test.context.payer, &owner],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client.get_account(user_account_key).await.unwrap().unwrap();
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.withdrawal_wallet, wallet);
+    assert_eq!(user_account_object.pending_withdrawal_wallet, Pubkey::default());
+
+    // The lower delay takes effect once the previous delay has elapsed
+    let mut clock = program_test.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += delay;
+    program_test.context.set_sysvar(&clock);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_wallet(&new_wallet)],
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client.get_account(user_account_key).await.unwrap().unwrap();
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.pending_withdrawal_wallet, new_wallet);
+    assert_eq!(user_account_object.withdrawal_wallet_change_time, clock.unix_timestamp);
+}
+
+#[tokio::test]
+async fn test_owner_signed_transfer_and_withdraw() {
+    let mint_amount = 100000000000u64;
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let transfer_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (source_account_key, source_token_account_key, source_bump) = 
+        program_test.with_source_user(mint_amount).await;
+
+    let (sender_account_key, sender_bump) = 
+        program_test.with_user(sender_user_id, deposit_amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let owner = Keypair::new();
+    let stranger = Keypair::new();
+    let operator_key = program_test.context.payer.pubkey();
+
+    // The owner and the stranger pay the fees, so that the operator does not sign their transactions
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &owner.pubkey(),
+                &token_mint_key,
+                &spl_token::id()
+            ),
+            set_owner(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                &owner.pubkey(),
+                &sender_account_key,
+                &token_mint_key, 
+                &operator_key
+            ).unwrap(),
+            solana_program::system_instruction::transfer(&operator_key, &owner.pubkey(), 1000000000),
+            solana_program::system_instruction::transfer(&operator_key, &stranger.pubkey(), 1000000000),
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            transfer_by_owner(
+                &program_test.program_id, 
+                &token_mint_key,
+                sender_user_id, 
+                sender_bump,
+                &sender_account_key,
+                recipient_user_id, 
+                recipient_bump,
+                &recipient_account_key, 
+                transfer_amount,
+                &operator_key,
+                &owner.pubkey()
+            ).unwrap(),
+            withdraw_by_owner(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                source_bump,
+                transfer_amount,
+                &sender_account_key,
+                &token_mint_key,
+                &source_token_account_key,
+                &source_account_key,
+                &get_associated_token_address(&owner.pubkey(), &token_mint_key),
+                &operator_key,
+                &owner.pubkey()
+            ).unwrap()
+        ], 
+        Some(&owner.pubkey()),
+        &[&owner],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let transfer_transaction = Transaction::new_signed_with_payer(
+        &[
+            transfer_by_owner(
+                &program_test.program_id, 
+                &token_mint_key,
+                sender_user_id, 
+                sender_bump,
+                &sender_account_key,
+                recipient_user_id, 
+                recipient_bump,
+                &recipient_account_key, 
+                transfer_amount,
+                &operator_key,
+                &stranger.pubkey()
+            ).unwrap()
+        ], 
+        Some(&stranger.pubkey()),
+        &[&stranger],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transfer_transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn test_allowance() {
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let allowance_amount = 2000000000u64;
+    let transfer_amount = 1500000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.with_user(sender_user_id, deposit_amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let partner = Keypair::new();
+    let delegate = Delegate::Key(partner.pubkey());
+
+    let (allowance_account_key, allowance_bump) = get_allowance_address(
+        &program_test.program_id, &sender_account_key, &delegate);
+
+    let program_id = program_test.program_id;
+    let operator_key = program_test.context.payer.pubkey();
+
+    let transfer_from_instruction = |amount: u64| transfer_from(
+        &program_id, 
+        &token_mint_key,
+        sender_user_id, 
+        sender_bump,
+        &sender_account_key,
+        recipient_user_id, 
+        recipient_bump,
+        &recipient_account_key, 
+        allowance_bump,
+        &allowance_account_key,
+        delegate.clone(),
+        None,
+        amount,
+        &operator_key,
+        &partner.pubkey()
+    ).unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            approve(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                allowance_bump,
+                delegate.clone(),
+                allowance_amount,
+                &sender_account_key,
+                &allowance_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            transfer_from_instruction(transfer_amount)
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &partner],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // Only 500000000 of the allowance is left
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer_from_instruction(transfer_amount)], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &partner],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            revoke(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                allowance_bump,
+                delegate.clone(),
+                &sender_account_key,
+                &allowance_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            transfer_from_instruction(1)
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &partner],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_allowance_of_key_is_not_spent_by_user_with_same_bytes() {
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let allowance_amount = 2000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.with_user(sender_user_id, deposit_amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let partner = Keypair::new();
+    let key_delegate = Delegate::Key(partner.pubkey());
+
+    // A user whose 32-byte id has the bytes of the partner key, owned by someone else
+    let impostor = Keypair::new();
+    let impostor_user_id = UserId::Bytes(partner.pubkey().to_bytes());
+    let user_delegate = Delegate::User(impostor_user_id);
+
+    let (impostor_account_key, impostor_bump) = 
+        program_test.create_user_account(impostor_user_id, 0).await;
+
+    let (allowance_account_key, allowance_bump) = get_allowance_address(
+        &program_test.program_id, &sender_account_key, &key_delegate);
+
+    let (user_allowance_account_key, user_allowance_bump) = 
+        get_allowance_address(&program_test.program_id, &sender_account_key, &user_delegate);
+    assert_ne!(user_allowance_account_key, allowance_account_key);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            approve(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                allowance_bump,
+                key_delegate,
+                allowance_amount,
+                &sender_account_key,
+                &allowance_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            set_owner(
+                &program_test.program_id, 
+                impostor_user_id, 
+                impostor_bump, 
+                &impostor.pubkey(),
+                &impostor_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            transfer_from(
+                &program_test.program_id, 
+                &token_mint_key,
+                sender_user_id, 
+                sender_bump,
+                &sender_account_key,
+                recipient_user_id, 
+                recipient_bump,
+                &recipient_account_key, 
+                user_allowance_bump,
+                &allowance_account_key,
+                user_delegate,
+                Some((&impostor_account_key, impostor_bump)),
+                allowance_amount,
+                &program_test.context.payer.pubkey(),
+                &impostor.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &impostor],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn test_scheduled_transfer() {
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let transfer_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.with_user(sender_user_id, deposit_amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let mut clock = program_test.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let execute_at = clock.unix_timestamp + 3600;
+
+    let scheduled_transfer_keys: Vec<(Pubkey, u8)> = (1u32..=2).map(|schedule_id| 
+        Pubkey::find_program_address(
+            &[SCHEDULED_TRANSFER_SEED, &sender_account_key.to_bytes(), &schedule_id.to_le_bytes()], 
+            &program_test.program_id)
+    ).collect();
+
+    let schedule_instructions: Vec<_> = scheduled_transfer_keys.iter().zip(1u32..).map(
+        |((scheduled_transfer_account_key, scheduled_transfer_bump), schedule_id)| schedule_transfer(
+            &program_test.program_id, 
+            &token_mint_key,
+            sender_user_id, 
+            sender_bump,
+            &sender_account_key,
+            recipient_user_id, 
+            recipient_bump,
+            &recipient_account_key, 
+            schedule_id,
+            *scheduled_transfer_bump,
+            scheduled_transfer_account_key,
+            transfer_amount,
+            execute_at,
+            &program_test.context.payer.pubkey()
+        ).unwrap()
+    ).collect();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &schedule_instructions, 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let execute_instructions: Vec<_> = scheduled_transfer_keys.iter().map(
+        |(scheduled_transfer_account_key, _)| execute_scheduled_transfer(
+            &program_test.program_id, 
+            scheduled_transfer_account_key,
+            &recipient_account_key,
+            &program_test.context.payer.pubkey()
+        ).unwrap()
+    ).collect();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[execute_instructions[1].clone()], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            cancel_scheduled_transfer(
+                &program_test.program_id, 
+                &scheduled_transfer_keys[1].0,
+                &sender_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    clock.unix_timestamp = execute_at;
+    program_test.context.set_sysvar(&clock);
+
+    // An account of the program of another type is rejected, even with the data of a scheduled transfer
+    let mut scheduled_transfer_data = program_test.context.banks_client
+        .get_account(scheduled_transfer_keys[0].0)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    scheduled_transfer_data[0] = AccountType::Uninitialized as u8;
+    let other_account_key = program_test.with_program_account(scheduled_transfer_data);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            execute_scheduled_transfer(
+                &program_test.program_id, 
+                &other_account_key,
+                &recipient_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[execute_instructions[0].clone()], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_vesting() {
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let vesting_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.with_user(sender_user_id, 0).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let mut clock = program_test.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let vesting = Vesting {
+        start_time: clock.unix_timestamp,
+        cliff_duration: 100,
+        duration: 1000,
+        total_amount: vesting_amount
+    };
+
+    let program_id = program_test.program_id;
+    let operator_key = program_test.context.payer.pubkey();
+
+    let transfer_instruction = |amount: u64| transfer(
+        &program_id, 
+        &token_mint_key,
+        sender_user_id, 
+        sender_bump,
+        &sender_account_key,
+        recipient_user_id, 
+        recipient_bump,
+        &recipient_account_key, 
+        amount,
+        &operator_key
+    ).unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            grant_vesting(
+                &program_id, 
+                sender_user_id, 
+                sender_bump, 
+                vesting.clone(),
+                &sender_account_key,
+                &token_mint_key, 
+                &operator_key
+            ).unwrap(),
+            transfer_instruction(1)
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            grant_vesting(
+                &program_id, 
+                sender_user_id, 
+                sender_bump, 
+                vesting.clone(),
+                &sender_account_key,
+                &token_mint_key, 
+                &operator_key
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let sender_account = program_test.context.banks_client
+        .get_account(sender_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let sender_account_object = UserAccount::try_from_slice(&sender_account.data).unwrap();
+    assert_eq!(sender_account_object.vesting, vesting);
+    assert_eq!(sender_account_object.balance, vesting_amount);
+
+    clock.unix_timestamp = vesting.start_time + 500;
+    program_test.context.set_sysvar(&clock);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            transfer_instruction(vesting_amount / 2),
+            transfer_instruction(1)
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_subscription() {
+    let subscriber_user_id = UserId::U32(100);
+    let merchant_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let amount_per_period = 1000000000u64;
+    let period = 30 * 24 * 3600i64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (subscriber_account_key, subscriber_bump) = 
+        program_test.with_user(subscriber_user_id, deposit_amount).await;
+
+    let (merchant_account_key, merchant_bump) = 
+        program_test.create_user_account(merchant_user_id, 0).await;
+
+    let (subscription_account_key, subscription_bump) = Pubkey::find_program_address(
+        &[SUBSCRIPTION_SEED, &subscriber_account_key.to_bytes(), &merchant_account_key.to_bytes()], 
+        &program_test.program_id);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            subscribe(
+                &program_test.program_id, 
+                &token_mint_key,
+                subscriber_user_id, 
+                subscriber_bump,
+                &subscriber_account_key,
+                merchant_user_id, 
+                merchant_bump,
+                &merchant_account_key, 
+                subscription_bump,
+                &subscription_account_key,
+                amount_per_period,
+                period,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            charge_subscription(
+                &program_test.program_id, 
+                &subscription_account_key,
+                &subscriber_account_key,
+                &merchant_account_key, 
+                amount_per_period,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // The current period is already charged
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            charge_subscription(
+                &program_test.program_id, 
+                &subscription_account_key,
+                &subscriber_account_key,
+                &merchant_account_key, 
+                1,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // An account of the program of another type is rejected, even with the data of a subscription
+    let mut subscription_data = program_test.context.banks_client
+        .get_account(subscription_account_key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    subscription_data[0] = AccountType::ScheduledTransfer as u8;
+    let other_account_key = program_test.with_program_account(subscription_data);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            charge_subscription(
+                &program_test.program_id, 
+                &other_account_key,
+                &subscriber_account_key,
+                &merchant_account_key, 
+                1,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            cancel_subscription(
+                &program_test.program_id, 
+                &subscription_account_key,
+                &subscriber_account_key,
+                &merchant_account_key, 
+                &program_test.context.payer.pubkey(),
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    assert!(program_test.context.banks_client
+        .get_account(subscription_account_key)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_escrow() {
+    let party_a_user_id = UserId::U32(100);
+    let party_b_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let stake = 2000000000u64;
+    let escrow_id = 1u32;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (party_a_account_key, party_a_bump) = 
+        program_test.create_user_account(party_a_user_id, deposit_amount).await;
+
+    let (party_b_account_key, party_b_bump) = 
+        program_test.create_user_account(party_b_user_id, deposit_amount).await;
+
+    let (escrow_account_key, escrow_bump) = Pubkey::find_program_address(
+        &[
+            ESCROW_SEED, 
+            &token_mint_key.to_bytes(), 
+            &program_test.context.payer.pubkey().to_bytes(), 
+            &escrow_id.to_le_bytes()
+        ], 
+        &program_test.program_id);
+
+    let arbiter = Keypair::new();
+    let clock = program_test.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let refund_time = clock.unix_timestamp + 3600;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_escrow(
+                &program_test.program_id, 
+                &token_mint_key,
+                escrow_id,
+                escrow_bump,
+                &escrow_account_key,
+                party_a_user_id, 
+                party_a_bump,
+                &party_a_account_key,
+                stake,
+                party_b_user_id, 
+                party_b_bump,
+                &party_b_account_key, 
+                stake,
+                &arbiter.pubkey(),
+                refund_time,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            fund_escrow(
+                &program_test.program_id, 
+                &escrow_account_key,
+                &party_a_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // Party B has not funded the escrow yet
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            resolve_escrow(
+                &program_test.program_id, 
+                &escrow_account_key,
+                &party_a_account_key,
+                &party_b_account_key,
+                stake,
+                &program_test.context.payer.pubkey(),
+                &arbiter.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &arbiter],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // The escrow cannot be refunded before the refund time
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            refund_escrow(
+                &program_test.program_id, 
+                &escrow_account_key,
+                &party_a_account_key,
+                &party_b_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // An account of the program of another type is rejected, even with the data of an escrow
+    let mut escrow_data = program_test.context.banks_client
+        .get_account(escrow_account_key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    escrow_data[0] = AccountType::Subscription as u8;
+    let other_account_key = program_test.with_program_account(escrow_data);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            fund_escrow(
+                &program_test.program_id, 
+                &other_account_key,
+                &party_b_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            fund_escrow(
+                &program_test.program_id, 
+                &escrow_account_key,
+                &party_b_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            resolve_escrow(
+                &program_test.program_id, 
+                &escrow_account_key,
+                &party_a_account_key,
+                &party_b_account_key,
+                stake + stake / 2,
+                &program_test.context.payer.pubkey(),
+                &arbiter.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer, &arbiter],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let party_a_account = program_test.context.banks_client
+        .get_account(party_a_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let party_a_account_object = UserAccount::try_from_slice(&party_a_account.data).unwrap();
+    assert_eq!(party_a_account_object.balance, deposit_amount + stake / 2);
+
+    let party_b_account = program_test.context.banks_client
+        .get_account(party_b_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let party_b_account_object = UserAccount::try_from_slice(&party_b_account.data).unwrap();
+    assert_eq!(party_b_account_object.balance, deposit_amount - stake / 2);
+
+    assert!(program_test.context.banks_client
+        .get_account(escrow_account_key)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_pot() {
+    let player_user_ids = [UserId::U32(100), UserId::U32(101), UserId::U32(102)];
+    let house_user_id = UserId::U32(103);
+    let deposit_amount = 5000000000u64;
+    let stake = 1000000000u64;
+    let pot_id = 1u32;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+
+    let mut players = Vec::new();
+    for user_id in player_user_ids {
+        let (user_account_key, user_bump) = 
+            program_test.create_user_account(user_id, deposit_amount).await;
+        players.push((user_id, user_account_key, user_bump));
+    }
+
+    let (house_account_key, house_bump) = 
+        program_test.create_user_account(house_user_id, 0).await;
+
+    let (pot_account_key, pot_bump) = Pubkey::find_program_address(
+        &[
+            POT_SEED, 
+            &token_mint_key.to_bytes(), 
+            &program_test.context.payer.pubkey().to_bytes(), 
+            &pot_id.to_le_bytes()
+        ], 
+        &program_test.program_id);
+
+    let mut instructions = vec![
+        create_pot(
+            &program_test.program_id, 
+            pot_id,
+            pot_bump,
+            &pot_account_key,
+            &token_mint_key,
+            &program_test.context.payer.pubkey()
+        ).unwrap()
+    ];
+
+    for (user_id, user_account_key, user_bump) in players.iter() {
+        instructions.push(
+            join_pot(
+                &program_test.program_id, 
+                *user_id,
+                *user_bump,
+                stake,
+                user_account_key,
+                &pot_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        );
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions, 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // An account of the program of another type is rejected, even with the data of a pot
+    let mut pot_data = program_test.context.banks_client
+        .get_account(pot_account_key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    pot_data[0] = AccountType::Escrow as u8;
+    let other_account_key = program_test.with_program_account(pot_data);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            join_pot(
+                &program_test.program_id, 
+                players[2].0,
+                players[2].2,
+                stake,
+                &players[2].1,
+                &other_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // 5% rake, the rest is split 3:1 between the first two players
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            settle_pot(
+                &program_test.program_id, 
+                &pot_account_key,
+                vec![
+                    (PotWinner { user_id: players[0].0, user_account_bump_seed: players[0].2, weight: 3 }, players[0].1),
+                    (PotWinner { user_id: players[1].0, user_account_bump_seed: players[1].2, weight: 1 }, players[1].1),
+                ],
+                Some((PotRake { house_user_id, house_account_bump_seed: house_bump, basis_points: 500 }, house_account_key)),
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let expected_balances = [
+        (players[0].1, deposit_amount - stake + 2137500000),
+        (players[1].1, deposit_amount - stake + 712500000),
+        (players[2].1, deposit_amount - stake),
+        (house_account_key, 150000000),
+    ];
+
+    for (user_account_key, expected_balance) in expected_balances {
+        let user_account = program_test.context.banks_client
+            .get_account(user_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let user_account_object = UserAccount::try_from_slice(&user_account.data).unwrap();
+        assert_eq!(user_account_object.balance, expected_balance);
+    }
+
+    assert!(program_test.context.banks_client
+        .get_account(pot_account_key)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_rewards() {
+    let first_user_id = UserId::U32(100);
+    let second_user_id = UserId::U32(101);
+    let reward_amount = 400u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    
+    let (first_account_key, first_bump) = 
+        program_test.create_user_account(first_user_id, 3000000000).await;
+
+    let (second_account_key, second_bump) = 
+        program_test.create_user_account(second_user_id, 1000000000).await;
+
+    let (reward_pool_account_key, reward_pool_bump) = Pubkey::find_program_address(
+        &[
+            REWARD_POOL_SEED, 
+            &token_mint_key.to_bytes(), 
+            &program_test.context.payer.pubkey().to_bytes()
+        ], 
+        &program_test.program_id);
+
+    // The first distribution is shared 3:1
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_reward_pool(
+                &program_test.program_id, 
+                reward_pool_bump,
+                &reward_pool_account_key,
+                &token_mint_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            enroll_rewards(
+                &program_test.program_id, 
+                first_user_id,
+                first_bump,
+                &first_account_key,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            enroll_rewards(
+                &program_test.program_id, 
+                second_user_id,
+                second_bump,
+                &second_account_key,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            distribute_rewards(
+                &program_test.program_id, 
+                reward_amount,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let transfer_instruction = transfer(
+        &program_test.program_id, 
+        &token_mint_key,
+        first_user_id, 
+        first_bump,
+        &first_account_key,
+        second_user_id, 
+        second_bump,
+        &second_account_key, 
+        1000000000,
+        &program_test.context.payer.pubkey()
+    ).unwrap();
+
+    // The balances of enrolled users cannot change without the reward pool
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer_instruction.clone()], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(UserAccountError::MissingRewardPool as u32)
+        )
+    );
+
+    // After the transfer the second distribution is shared 1:1
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            with_reward_pool(transfer_instruction, &reward_pool_account_key),
+            distribute_rewards(
+                &program_test.program_id, 
+                reward_amount,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            claim(
+                &program_test.program_id, 
+                first_user_id,
+                first_bump,
+                &first_account_key,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            claim(
+                &program_test.program_id, 
+                second_user_id,
+                second_bump,
+                &second_account_key,
+                &reward_pool_account_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let first_account = program_test.context.banks_client
+        .get_account(first_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let first_account_object = UserAccount::try_from_slice(&first_account.data).unwrap();
+    assert_eq!(first_account_object.balance, 2000000000 + 300 + 200);
+    assert_eq!(first_account_object.reward.pending_amount, 0);
+
+    let second_account = program_test.context.banks_client
+        .get_account(second_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let second_account_object = UserAccount::try_from_slice(&second_account.data).unwrap();
+    assert_eq!(second_account_object.balance, 2000000000 + 100 + 200);
+    assert_eq!(second_account_object.reward.pending_amount, 0);
+}
+
+#[tokio::test]
+async fn test_credit_limit() {
+    let mint_amount = 100000000000u64;
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 1000000000u64;
+    let credit_limit = 1000000000u64;
+    let transfer_amount = 1500000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+
+    let (source_account_key, source_token_account_key, source_bump) = 
+        program_test.with_source_user(mint_amount).await;
+    
+    let (sender_account_key, sender_bump) = 
+        program_test.create_user_account(sender_user_id, deposit_amount).await;
+
+    let (recipient_account_key, recipient_bump) = 
+        program_test.create_user_account(recipient_user_id, 0).await;
+
+    let transfer_instruction = transfer(
+        &program_test.program_id, 
+        &token_mint_key,
+        sender_user_id, 
+        sender_bump,
+        &sender_account_key,
+        recipient_user_id, 
+        recipient_bump,
+        &recipient_account_key, 
+        transfer_amount,
+        &program_test.context.payer.pubkey()
+    ).unwrap();
+
+    // Without a credit limit the transfer cannot exceed the balance
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer_instruction.clone()], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            set_credit_limit(
+                &program_test.program_id, 
+                sender_user_id,
+                sender_bump,
+                credit_limit,
+                &sender_account_key,
+                &token_mint_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            transfer_instruction
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let sender_account = program_test.context.banks_client
+        .get_account(sender_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let sender_account_object = UserAccount::try_from_slice(&sender_account.data).unwrap();
+    assert_eq!(sender_account_object.balance, 0);
+    assert_eq!(sender_account_object.debt, transfer_amount - deposit_amount);
+
+    // The debt cannot grow beyond the credit limit
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            transfer(
+                &program_test.program_id, 
+                &token_mint_key,
+                sender_user_id, 
+                sender_bump,
+                &sender_account_key,
+                recipient_user_id, 
+                recipient_bump,
+                &recipient_account_key, 
+                credit_limit,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(UserAccountError::CreditLimitExceeded as u32)
+        )
+    );
+
+    // Nothing can be withdrawn while in debt
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &program_test.context.payer.pubkey(),
+                &token_mint_key,
+                &spl_token::id()
+            ),
+            withdraw(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                source_bump,
+                1,
+                &sender_account_key,
+                &token_mint_key,
+                &source_token_account_key,
+                &source_account_key,
+                &get_associated_token_address(&program_test.context.payer.pubkey(), &token_mint_key),
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1, 
+            InstructionError::Custom(UserAccountError::OutstandingDebt as u32)
+        )
+    );
+
+    // The deposit repays the debt first
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            deposit(
+                &program_test.program_id, 
+                sender_user_id, 
+                sender_bump, 
+                deposit_amount,
+                &sender_account_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let sender_account = program_test.context.banks_client
+        .get_account(sender_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let sender_account_object = UserAccount::try_from_slice(&sender_account.data).unwrap();
+    assert_eq!(sender_account_object.balance, 2 * deposit_amount - transfer_amount);
+    assert_eq!(sender_account_object.debt, 0);
+}
+
+#[tokio::test]
+async fn test_shard() {
+    let mint_amount = 100000000000u64;
+    let sender_user_id = UserId::U64(5);
+    let recipient_user_id = UserId::U64(6);
+    let deposit_amount = 5000000000u64;
+    let transfer_amount = 2000000000u64;
+    let block_amount = 1000000000u64;
+    let withdraw_amount = 500000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+
+    let (source_account_key, source_token_account_key, source_bump) = 
+        program_test.with_source_user(mint_amount).await;
+
+    let shard_index = ShardHeader::shard_index(sender_user_id.number().unwrap());
+    let (shard_key, shard_bump) = get_shard_address(
+        &program_test.program_id, 
+        &token_mint_key, 
+        &program_test.context.payer.pubkey(), 
+        shard_index);
+
+    let initialize_shard_instruction = initialize_shard(
+        &program_test.program_id, 
+        shard_index,
+        shard_bump,
+        &shard_key,
+        &token_mint_key,
+        &program_test.context.payer.pubkey()
+    ).unwrap();
+
+    let initialize_shard_transaction = Transaction::new_signed_with_payer(
+        &vec![initialize_shard_instruction.clone(); ShardHeader::ALLOCATION_COUNT], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(initialize_shard_transaction).await.unwrap();
+
+    let destination_key = get_associated_token_address(&program_test.context.payer.pubkey(), &token_mint_key);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            deposit(
+                &program_test.program_id, 
+                sender_user_id, 
+                0, 
+                deposit_amount,
+                &shard_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            transfer(
+                &program_test.program_id, 
+                &token_mint_key,
+                sender_user_id, 
+                0,
+                &shard_key,
+                recipient_user_id, 
+                0,
+                &shard_key, 
+                transfer_amount,
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            block(
+                &program_test.program_id, 
+                recipient_user_id, 
+                0, 
+                block_amount,
+                &shard_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap(),
+            create_associated_token_account(
+                &program_test.context.payer.pubkey(),
+                &program_test.context.payer.pubkey(),
+                &token_mint_key,
+                &spl_token::id()
+            ),
+            withdraw(
+                &program_test.program_id, 
+                sender_user_id, 
+                0, 
+                source_bump,
+                withdraw_amount,
+                &shard_key,
+                &token_mint_key,
+                &source_token_account_key,
+                &source_account_key,
+                &destination_key,
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let shard_account = program_test.context.banks_client
+        .get_account(shard_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(shard_account.data.len(), ShardHeader::ACCOUNT_LEN);
+
+    let sender_slot = ShardSlot::try_from_slice(
+        &shard_account.data[ShardHeader::slot_range(sender_user_id.number().unwrap())]).unwrap();
+    assert_eq!(sender_slot.balance, deposit_amount - transfer_amount - withdraw_amount);
+    assert_eq!(sender_slot.blocked_amount, 0);
+
+    let recipient_slot = ShardSlot::try_from_slice(
+        &shard_account.data[ShardHeader::slot_range(recipient_user_id.number().unwrap())]).unwrap();
+    assert_eq!(recipient_slot.balance, transfer_amount);
+    assert_eq!(recipient_slot.blocked_amount, block_amount);
+
+    let destination_account = program_test.context.banks_client
+        .get_account(destination_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(spl_token::state::Account::unpack(&destination_account.data).unwrap().amount, withdraw_amount);
+
+    // The slot of the user 5 holds a 64-bit id, so the 32-bit user 5 cannot use it
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            deposit(
+                &program_test.program_id, 
+                UserId::U32(5), 
+                0, 
+                deposit_amount,
+                &shard_key,
+                &token_mint_key, 
+                &program_test.context.payer.pubkey()
+            ).unwrap()
+        ], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    assert_eq!(
+        program_test.context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // A shard user and a user account cannot send tokens to each other
+    let account_user_id = UserId::U32(7);
+    let (account_user_key, account_user_bump) = program_test.with_user(account_user_id, deposit_amount).await;
+
+    for (sender_user_id, sender_bump, sender_key, recipient_user_id, recipient_bump, recipient_key) in [
+        (recipient_user_id, 0, shard_key, account_user_id, account_user_bump, account_user_key),
+        (account_user_id, account_user_bump, account_user_key, recipient_user_id, 0, shard_key),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                transfer(
+                    &program_test.program_id, 
+                    &token_mint_key,
+                    sender_user_id, 
+                    sender_bump,
+                    &sender_key,
+                    recipient_user_id, 
+                    recipient_bump,
+                    &recipient_key, 
+                    1,
+                    &program_test.context.payer.pubkey()
+                ).unwrap()
+            ], 
+            Some(&program_test.context.payer.pubkey()),
+            &[&program_test.context.payer],
+            program_test.context.last_blockhash
+        );
+        assert_eq!(
+            program_test.context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(UserAccountError::MixedLedgerTransfer as u32))
+        );
+    }
+
+    // The shard address is derived from its index, so a whole shard cannot be initialized twice
+    let transaction = Transaction::new_signed_with_payer(
+        &[initialize_shard_instruction], 
+        Some(&program_test.context.payer.pubkey()),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    assert_eq!(
+        program_test.context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn test_derived_builders() {
+    let sender_user_id = UserId::U32(100);
+    let recipient_user_id = UserId::U32(101);
+    let deposit_amount = 5000000000u64;
+    let transfer_amount = 2000000000u64;
+    let block_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_derived(&program_id, sender_user_id, &token_mint_key, &payer_key).unwrap(),
+            create_derived(&program_id, recipient_user_id, &token_mint_key, &payer_key).unwrap(),
+            deposit_derived(&program_id, sender_user_id, deposit_amount, &token_mint_key, &payer_key).unwrap(),
+            transfer_derived(
+                &program_id, 
+                &token_mint_key, 
+                sender_user_id, 
+                recipient_user_id, 
+                transfer_amount, 
+                &payer_key
+            ).unwrap(),
+            block_derived(&program_id, recipient_user_id, block_amount, &token_mint_key, &payer_key).unwrap()
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let (recipient_account_key, _) = 
+        get_user_account_address(&program_id, recipient_user_id, &token_mint_key, &payer_key);
+
+    let recipient_account = program_test.context.banks_client
+        .get_account(recipient_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let recipient_account_object = UserAccount::try_from_slice(&recipient_account.data).unwrap();
+    assert_eq!(recipient_account_object.balance, transfer_amount);
+    assert_eq!(recipient_account_object.blocked_amount, block_amount);
+}
+
+#[tokio::test]
+async fn test_wide_user_ids() {
+    let legacy_user_id = UserId::U32(100);
+    let wide_user_id = UserId::U64(1 << 40);
+    let opaque_user_id = UserId::Bytes([7; 32]);
+    let deposit_amount = 5000000000u64;
+    let transfer_amount = 2000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+
+    assert_ne!(
+        get_user_account_address(&program_id, UserId::U32(100), &token_mint_key, &payer_key),
+        get_user_account_address(&program_id, UserId::U64(100), &token_mint_key, &payer_key));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_derived(&program_id, legacy_user_id, &token_mint_key, &payer_key).unwrap(),
+            create_derived(&program_id, wide_user_id, &token_mint_key, &payer_key).unwrap(),
+            create_derived(&program_id, opaque_user_id, &token_mint_key, &payer_key).unwrap(),
+            deposit_derived(&program_id, legacy_user_id, deposit_amount, &token_mint_key, &payer_key).unwrap(),
+            transfer_derived(&program_id, &token_mint_key, legacy_user_id, wide_user_id, transfer_amount, &payer_key).unwrap(),
+            transfer_derived(&program_id, &token_mint_key, wide_user_id, opaque_user_id, transfer_amount, &payer_key).unwrap(),
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    for (user_id, balance) in [
+        (legacy_user_id, deposit_amount - transfer_amount), 
+        (wide_user_id, 0), 
+        (opaque_user_id, transfer_amount)
+    ] {
+        let (user_account_key, _) = 
+            get_user_account_address(&program_id, user_id, &token_mint_key, &payer_key);
+
+        let user_account = program_test.context.banks_client
+            .get_account(user_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let user_account_object = UserAccount::try_from_slice(&user_account.data).unwrap();
+        assert_eq!(user_account_object.balance, balance);
+        assert_eq!(user_account_object.token_mint, token_mint_key);
+        assert_eq!(user_account_object.user_id.user_id(), Some(user_id));
+    }
+}
+
+#[tokio::test]
+async fn test_reserved_user_id() {
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_derived(&program_id, UserId::U32(0), &token_mint_key, &payer_key).unwrap()
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0, 
+            InstructionError::Custom(UserAccountError::ReservedUserId as u32)
+        )
+    );
+
+    // Only the 32-bit id 0 is reserved
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_derived(&program_id, UserId::U64(0), &token_mint_key, &payer_key).unwrap()
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_legacy_instruction_encoding() {
+    let user_id = 100u32;
+    let deposit_amount = 5000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+    let (user_account_key, user_bump) = 
+        get_user_account_address(&program_id, UserId::U32(user_id), &token_mint_key, &payer_key);
+
+    // The first version of the program encoded the variant index followed by the fields, with 32-bit user ids
+    let mut instructions = [
+        create_derived(&program_id, UserId::U32(user_id), &token_mint_key, &payer_key).unwrap(),
+        deposit_derived(&program_id, UserId::U32(user_id), deposit_amount, &token_mint_key, &payer_key).unwrap()
+    ];
+    instructions[0].data = [&[0][..], &user_id.to_le_bytes(), &[user_bump]].concat();
+    instructions[1].data = [&[1][..], &user_id.to_le_bytes(), &[user_bump], &deposit_amount.to_le_bytes()].concat();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions, 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client
+        .get_account(user_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let user_account_object = UserAccount::try_from_slice(&user_account.data).unwrap();
+    assert_eq!(user_account_object.balance, deposit_amount);
+}
+
+#[tokio::test]
+async fn test_migrate_user_account() {
+    let user_id = 100u32;
+    let balance = 3000000000u64;
+    let blocked_amount = 1000000000u64;
+    let deposit_amount = 5000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+    let (user_account_key, user_bump) = program_test.with_legacy_user(user_id, balance, blocked_amount).await;
+
+    let deposit_instruction = deposit(
+        &program_id, 
+        UserId::U32(user_id), 
+        user_bump, 
+        deposit_amount,
+        &user_account_key,
+        &token_mint_key, 
+        &payer_key
+    ).unwrap();
+
+    // The legacy account cannot be loaded until it is migrated
+    let transaction = Transaction::new_signed_with_payer(
+        &[deposit_instruction.clone()], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    assert_eq!(
+        program_test.context.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let migrate_instruction = migrate_user_account(
+        &program_id, 
+        UserId::U32(user_id), 
+        user_bump, 
+        &user_account_key,
+        &token_mint_key, 
+        &payer_key
+    ).unwrap();
+
+    // Migrating twice leaves the account as it is
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_instruction.clone(), migrate_instruction, deposit_instruction], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client
+        .get_account(user_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = program_test.context.banks_client.get_rent().await.unwrap();
+    assert_eq!(user_account.data.len(), UserAccount::LEN);
+    assert!(rent.is_exempt(user_account.lamports, UserAccount::LEN));
+
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.balance, balance + deposit_amount);
+    assert_eq!(user_account_object.blocked_amount, blocked_amount);
+    assert_eq!(user_account_object.owner, Pubkey::default());
+    assert_eq!(user_account_object.debt, 0);
+    assert_eq!(user_account_object.token_mint, token_mint_key);
+    assert_eq!(user_account_object.user_id.user_id(), Some(UserId::U32(user_id)));
+}
+
+#[tokio::test]
+async fn test_legacy_user_account_with_legacy_instruction_encoding() {
+    let user_id = 100u32;
+    let balance = 3000000000u64;
+    let deposit_amount = 5000000000u64;
+    let block_amount = 1000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+    let (user_account_key, user_bump) = program_test.with_legacy_user(user_id, balance, 0).await;
+
+    // A client of the first version keeps sending its encoding once the operator has migrated the account
+    let mut deposit_instruction = 
+        deposit_derived(&program_id, UserId::U32(user_id), deposit_amount, &token_mint_key, &payer_key).unwrap();
+    deposit_instruction.data = 
+        [&[1][..], &user_id.to_le_bytes(), &[user_bump], &deposit_amount.to_le_bytes()].concat();
+
+    let mut block_instruction = 
+        block_derived(&program_id, UserId::U32(user_id), block_amount, &token_mint_key, &payer_key).unwrap();
+    block_instruction.data = 
+        [&[4][..], &user_id.to_le_bytes(), &[user_bump], &block_amount.to_le_bytes()].concat();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            migrate_user_account(
+                &program_id, 
+                UserId::U32(user_id), 
+                user_bump, 
+                &user_account_key,
+                &token_mint_key, 
+                &payer_key
+            ).unwrap(),
+            deposit_instruction,
+            block_instruction
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let user_account = program_test.context.banks_client
+        .get_account(user_account_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let user_account_object = UserAccount::load(&user_account.data).unwrap();
+    assert_eq!(user_account_object.balance, balance + deposit_amount);
+    assert_eq!(user_account_object.blocked_amount, block_amount);
+}
+
+#[tokio::test]
+async fn test_migrate_vault() {
+    let user_id = 100u32;
+    let balance = 3000000000u64;
+
+    let mut program_test = UserAccountProgramTest::start_new().await;
+    
+    let token_mint_key = program_test.with_token_mint().await;
+    let program_id = program_test.program_id;
+    let payer_key = program_test.context.payer.pubkey();
+    let (user_account_key, user_bump) = program_test.with_legacy_user(user_id, balance, 0).await;
+
+    // The tokens deposited with the first version are held by the vault of the authority of the user 0
+    let (legacy_vault_authority_key, legacy_vault_authority_bump) = 
+        get_legacy_vault_authority_address(&program_id, &token_mint_key, &payer_key);
+    let legacy_vault_key = get_associated_token_address(&legacy_vault_authority_key, &token_mint_key);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            create_associated_token_account(&payer_key, &legacy_vault_authority_key, &token_mint_key, &spl_token::id()),
+            spl_token::instruction::mint_to(
+                &spl_token::id(), 
+                &token_mint_key, 
+                &legacy_vault_key, 
+                &payer_key, 
+                &[],
+                balance
+            ).unwrap()
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let (vault_authority_key, vault_key, vault_authority_bump) = program_test.with_source_user(0).await;
+    let destination_key = get_associated_token_address(&payer_key, &token_mint_key);
+
+    let migrate_vault_instruction = migrate_vault(
+        &program_id,
+        legacy_vault_authority_bump,
+        &legacy_vault_authority_key,
+        &legacy_vault_key,
+        vault_authority_bump,
+        &vault_authority_key,
+        &vault_key,
+        &token_mint_key,
+        &payer_key
+    ).unwrap();
+
+    // Migrating the emptied vault again does nothing
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            migrate_user_account(&program_id, UserId::U32(user_id), user_bump, &user_account_key, &token_mint_key, &payer_key)
+                .unwrap(),
+            migrate_vault_instruction.clone(),
+            migrate_vault_instruction,
+            create_associated_token_account(&payer_key, &payer_key, &token_mint_key, &spl_token::id()),
+            withdraw_derived(&program_id, UserId::U32(user_id), balance, &token_mint_key, &destination_key, &payer_key)
+                .unwrap()
+        ], 
+        Some(&payer_key),
+        &[&program_test.context.payer],
+        program_test.context.last_blockhash
+    );
+    program_test.context.banks_client.process_transaction(transaction).await.unwrap();
+
+    for (token_account_key, amount) in [(legacy_vault_key, 0), (vault_key, 0), (destination_key, balance)] {
+        let token_account = program_test.context.banks_client
+            .get_account(token_account_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(spl_token::state::Account::unpack(&token_account.data).unwrap().amount, amount);
+    }
+}
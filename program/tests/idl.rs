@@ -0,0 +1,382 @@
+//! Generates the IDL of the program in the Anchor format from the sources of `UserAccountInstruction`
+//! and `UserAccount` and from `UserAccountError`, and checks that the published IDL is up to date.
+//! Run with `UPDATE_IDL=1` to write the generated IDL instead.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env,
+    fs,
+    path::Path,
+};
+
+use num_traits::FromPrimitive;
+use serde_json::{json, Map, Value};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Parser},
+    Attribute, Expr, ExprLit, Fields, GenericArgument, Ident, Item, Lit, LitInt, Meta, NestedMeta, PathArguments,
+    Token, Type,
+};
+
+use emotion_user_account::{
+    error::UserAccountError,
+    instruction::{INSTRUCTION_VERSION, INSTRUCTION_VERSION_MARKER},
+};
+
+const IDL_PATH: &str = "idl/emotion_user_account.json";
+const SOURCES: [&str; 2] = [include_str!("../src/instruction.rs"), include_str!("../src/state.rs")];
+const ACCOUNTS_HEADER: &str = "Accounts expected by this instruction:";
+
+/// The structs and enums of the sources by name
+fn source_items() -> BTreeMap<String, Item> {
+    SOURCES
+        .iter()
+        .flat_map(|source| syn::parse_file(source).unwrap().items)
+        .filter_map(|item| match &item {
+            Item::Struct(item_struct) => Some((item_struct.ident.to_string(), item)),
+            Item::Enum(item_enum) => Some((item_enum.ident.to_string(), item)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The tags of the instructions by variant, from the invocation of `instruction_tags!`
+fn instruction_tags() -> BTreeMap<String, u8> {
+    let invocation = syn::parse_file(SOURCES[0])
+        .unwrap()
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::Macro(item_macro) if item_macro.mac.path.is_ident("instruction_tags") => Some(item_macro.mac),
+            _ => None,
+        })
+        .expect("The instruction tags are not found");
+
+    let parse_tags = |input: ParseStream| {
+        let mut tags = BTreeMap::new();
+
+        while !input.is_empty() {
+            let variant: Ident = input.parse()?;
+            let fields;
+            braced!(fields in input);
+            fields.parse_terminated::<Ident, Token![,]>(Ident::parse)?;
+            input.parse::<Token![=]>()?;
+            let tag: LitInt = input.parse()?;
+            tags.insert(variant.to_string(), tag.base10_parse()?);
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(tags)
+    };
+
+    let tags = parse_tags.parse2(invocation.tokens).unwrap();
+    let mut used_tags = HashSet::new();
+
+    for (variant, tag) in &tags {
+        assert!(used_tags.insert(tag), "{} reuses the tag {}", variant, tag);
+    }
+
+    tags
+}
+
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    let mut lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) if meta.path.is_ident("doc") => match meta.lit {
+                Lit::Str(doc) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    while matches!(lines.last(), Some(line) if line.is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| match attr.parse_meta() {
+        Ok(Meta::List(meta)) if meta.path.is_ident("repr") => meta.nested
+            .iter()
+            .any(|nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C"))),
+        _ => false,
+    })
+}
+
+/// Returns an IDL item with its `name`, its `docs` when there are any, then the given `properties`
+fn idl_item(name: String, docs: Vec<String>, properties: Map<String, Value>) -> Value {
+    let mut item = Map::new();
+    item.insert("name".to_string(), json!(name));
+
+    if !docs.is_empty() {
+        item.insert("docs".to_string(), json!(docs));
+    }
+
+    item.extend(properties);
+    Value::Object(item)
+}
+
+/// Converts a Rust type, collecting the names of the types it refers to
+fn idl_type(ty: &Type, defined_types: &mut BTreeSet<String>) -> Value {
+    match ty {
+        Type::Array(array) => {
+            let length = match &array.len {
+                Expr::Lit(ExprLit { lit: Lit::Int(length), .. }) => length.base10_parse::<usize>().unwrap(),
+                _ => panic!("The IDL only supports arrays of a literal length"),
+            };
+
+            json!({ "array": [idl_type(&array.elem, defined_types), length] })
+        }
+        Type::Path(path) => {
+            let segment = path.path.segments.last().unwrap();
+            let name = segment.ident.to_string();
+
+            match name.as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" | "bool" =>
+                    json!(name),
+                "String" => json!("string"),
+                "Pubkey" => json!("pubkey"),
+                "Vec" | "Option" => {
+                    let element = match &segment.arguments {
+                        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+                            Some(GenericArgument::Type(element)) => element,
+                            _ => panic!("{} has no type argument", name),
+                        },
+                        _ => panic!("{} has no type argument", name),
+                    };
+
+                    let kind = name.to_lowercase();
+
+                    match idl_type(element, defined_types) {
+                        Value::String(element) if kind == "vec" && element == "u8" => json!("bytes"),
+                        element => json!({ kind: element }),
+                    }
+                }
+                _ => {
+                    defined_types.insert(name.clone());
+                    json!({ "defined": { "name": name } })
+                }
+            }
+        }
+        _ => panic!("The IDL only supports arrays and named types"),
+    }
+}
+
+/// Converts named fields to fields with a name and a type, and unnamed fields to types
+fn idl_fields(fields: &Fields, defined_types: &mut BTreeSet<String>) -> Option<Value> {
+    match fields {
+        Fields::Named(fields) => Some(fields.named
+            .iter()
+            .map(|field| {
+                let mut properties = Map::new();
+                properties.insert("type".to_string(), idl_type(&field.ty, defined_types));
+                idl_item(field.ident.as_ref().unwrap().to_string(), doc_lines(&field.attrs), properties)
+            })
+            .collect()),
+        Fields::Unnamed(fields) => Some(fields.unnamed
+            .iter()
+            .map(|field| idl_type(&field.ty, defined_types))
+            .collect()),
+        Fields::Unit => None,
+    }
+}
+
+fn idl_type_definition(item: &Item, defined_types: &mut BTreeSet<String>) -> Value {
+    let (name, attrs, type_definition) = match item {
+        Item::Struct(item_struct) => (&item_struct.ident, &item_struct.attrs, json!({
+            "kind": "struct",
+            "fields": idl_fields(&item_struct.fields, defined_types).unwrap_or_else(|| json!([])),
+        })),
+        Item::Enum(item_enum) => (&item_enum.ident, &item_enum.attrs, json!({
+            "kind": "enum",
+            "variants": item_enum.variants
+                .iter()
+                .map(|variant| {
+                    let mut properties = Map::new();
+
+                    if let Some(fields) = idl_fields(&variant.fields, defined_types) {
+                        properties.insert("fields".to_string(), fields);
+                    }
+
+                    idl_item(variant.ident.to_string(), doc_lines(&variant.attrs), properties)
+                })
+                .collect::<Vec<_>>(),
+        })),
+        _ => unreachable!(),
+    };
+
+    let mut properties = Map::new();
+
+    if is_repr_c(attrs) {
+        properties.insert("repr".to_string(), json!({ "kind": "c" }));
+    }
+
+    properties.insert("type".to_string(), type_definition);
+    idl_item(name.to_string(), doc_lines(attrs), properties)
+}
+
+/// Parses an account line such as ``0. `[writeable, signer]` (Optional) The user's owner``
+fn parse_account(line: &str) -> Option<Map<String, Value>> {
+    let (index, line) = line.split_once(". `[")?;
+    index.parse::<usize>().ok()?;
+
+    let (flags, description) = line.split_once("]` ")?;
+    let flags: Vec<&str> = flags.split(',').map(str::trim).collect();
+    let optional = description.starts_with("(Optional) ");
+    let description = description.trim_start_matches("(Optional) ");
+
+    let mut account = Map::new();
+    account.insert("name".to_string(), json!(account_name(description)));
+    account.insert("docs".to_string(), json!([description]));
+
+    for (flag, value) in [
+        ("writable", flags.contains(&"writeable")),
+        ("signer", flags.contains(&"signer")),
+        ("optional", optional)
+    ] {
+        if value {
+            account.insert(flag.to_string(), json!(true));
+        }
+    }
+
+    Some(account)
+}
+
+/// Names an account after the beginning of its description, "The token mint address" is `token_mint`
+fn account_name(description: &str) -> String {
+    let name = description.split([',', '/']).next().unwrap().to_lowercase().replace("'s", "");
+    let name = name
+        .trim_start_matches("the ")
+        .trim_end_matches(" address")
+        .trim_end_matches(" account");
+
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Splits the documentation of an instruction into its description and the accounts it expects
+fn instruction_docs(name: &str, lines: Vec<String>) -> (Vec<String>, Vec<Value>) {
+    let mut lines = lines.into_iter();
+    let mut docs: Vec<String> = lines.by_ref().take_while(|line| line != ACCOUNTS_HEADER).collect();
+
+    while matches!(docs.last(), Some(line) if line.is_empty()) {
+        docs.pop();
+    }
+
+    let mut accounts: Vec<Map<String, Value>> = Vec::new();
+
+    for line in lines.filter(|line| !line.is_empty()) {
+        match (parse_account(&line), accounts.last_mut()) {
+            (Some(account), _) => accounts.push(account),
+            // The description of the previous account continues on this line
+            (None, Some(account)) => {
+                let description = format!("{} {}", account["docs"][0].as_str().unwrap(), line);
+                account.insert("docs".to_string(), json!([description]));
+            }
+            (None, None) => panic!("{} lists its accounts in an unexpected format: {}", name, line),
+        }
+    }
+
+    let mut account_names = HashSet::new();
+
+    for account in &accounts {
+        assert!(account_names.insert(&account["name"]), "{} lists several accounts named {}", name, account["name"]);
+    }
+
+    (docs, accounts.into_iter().map(Value::Object).collect())
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+
+    for (index, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            snake_case.push('_');
+        }
+        snake_case.push(c.to_ascii_lowercase());
+    }
+
+    snake_case
+}
+
+fn generate_idl() -> Value {
+    let items = source_items();
+    let mut defined_types = BTreeSet::new();
+
+    let instruction_enum = match &items["UserAccountInstruction"] {
+        Item::Enum(item_enum) => item_enum,
+        _ => panic!("UserAccountInstruction is not an enum"),
+    };
+
+    let tags = instruction_tags();
+
+    // The instruction data starts with the version of the encoding and the tag of the instruction
+    let instructions: Vec<Value> = instruction_enum.variants
+        .iter()
+        .map(|variant| {
+            let name = variant.ident.to_string();
+            let (docs, accounts) = instruction_docs(&name, doc_lines(&variant.attrs));
+
+            json!({
+                "name": snake_case(&name),
+                "docs": docs,
+                "discriminator": [INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION, tags[&name]],
+                "accounts": accounts,
+                "args": idl_fields(&variant.fields, &mut defined_types).unwrap_or_else(|| json!([])),
+            })
+        })
+        .collect();
+
+    // The account data has no discriminator, the accounts are told apart by their address
+    defined_types.insert("UserAccount".to_string());
+    let accounts = json!([{ "name": "UserAccount", "discriminator": [] }]);
+
+    let errors: Vec<Value> = (0..)
+        .map_while(|code| UserAccountError::from_u32(code).map(|error| json!({
+            "code": code,
+            "name": format!("{:?}", error),
+            "msg": error.to_string(),
+        })))
+        .collect();
+
+    let mut types = BTreeMap::new();
+
+    // The definitions of the types refer to more types
+    while let Some(name) = defined_types.iter().find(|name| !types.contains_key(*name)).cloned() {
+        let item = items.get(&name).unwrap_or_else(|| panic!("The type {} is not found", name));
+        types.insert(name, idl_type_definition(item, &mut defined_types));
+    }
+
+    json!({
+        "metadata": {
+            "name": "emotion_user_account",
+            "version": env!("CARGO_PKG_VERSION"),
+            "spec": "0.1.0",
+        },
+        "instructions": instructions,
+        "accounts": accounts,
+        "errors": errors,
+        "types": types.into_values().collect::<Vec<_>>(),
+    })
+}
+
+#[test]
+fn test_idl_is_up_to_date() {
+    let idl = serde_json::to_string_pretty(&generate_idl()).unwrap() + "\n";
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(IDL_PATH);
+
+    if env::var_os("UPDATE_IDL").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &idl).unwrap();
+    }
+
+    let published_idl = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        published_idl == idl,
+        "{} is out of date, run `UPDATE_IDL=1 cargo test --test idl` to update it", IDL_PATH);
+}
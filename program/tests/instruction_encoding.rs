@@ -0,0 +1,136 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use emotion_user_account::{
+    instruction::{UserAccountInstruction, INSTRUCTION_VERSION, INSTRUCTION_VERSION_MARKER, LEGACY_INSTRUCTION_COUNT},
+    state::{PotRake, UserId},
+};
+
+/// The instructions of the first version of the program, encoded as the clients built for it encode them
+#[derive(BorshSerialize)]
+enum BaselineInstruction {
+    Create { user_id: u32, user_account_bump_seed: u8 },
+    Deposit { user_id: u32, user_account_bump_seed: u8, amount: u64 },
+    Withdraw { user_id: u32, user_account_bump_seed: u8, source_authority_bump_seed: u8, amount: u64 },
+    Transfer {
+        sender_user_id: u32,
+        sender_account_bump_seed: u8,
+        recipient_user_id: u32,
+        recipient_account_bump_seed: u8,
+        amount: u64
+    },
+    Block { user_id: u32, user_account_bump_seed: u8, amount: u64 },
+    Unblock { user_id: u32, user_account_bump_seed: u8, amount: u64 },
+}
+
+/// Every baseline instruction with the instruction it is decoded to
+fn baseline_instructions() -> Vec<(BaselineInstruction, UserAccountInstruction)> {
+    vec![
+        (
+            BaselineInstruction::Create { user_id: 7, user_account_bump_seed: 254 },
+            UserAccountInstruction::Create { user_id: UserId::U32(7), user_account_bump_seed: 254 }
+        ),
+        (
+            BaselineInstruction::Deposit { user_id: 7, user_account_bump_seed: 254, amount: 1000 },
+            UserAccountInstruction::Deposit { user_id: UserId::U32(7), user_account_bump_seed: 254, amount: 1000 }
+        ),
+        (
+            BaselineInstruction::Withdraw {
+                user_id: 7,
+                user_account_bump_seed: 254,
+                source_authority_bump_seed: 253,
+                amount: 1000
+            },
+            UserAccountInstruction::Withdraw {
+                user_id: UserId::U32(7),
+                user_account_bump_seed: 254,
+                source_authority_bump_seed: 253,
+                amount: 1000
+            }
+        ),
+        (
+            BaselineInstruction::Transfer {
+                sender_user_id: 1,
+                sender_account_bump_seed: 255,
+                recipient_user_id: u32::MAX,
+                recipient_account_bump_seed: 254,
+                amount: 1000
+            },
+            UserAccountInstruction::Transfer {
+                sender_user_id: UserId::U32(1),
+                sender_account_bump_seed: 255,
+                recipient_user_id: UserId::U32(u32::MAX),
+                recipient_account_bump_seed: 254,
+                amount: 1000
+            }
+        ),
+        (
+            BaselineInstruction::Block { user_id: 7, user_account_bump_seed: 254, amount: 1000 },
+            UserAccountInstruction::Block { user_id: UserId::U32(7), user_account_bump_seed: 254, amount: 1000 }
+        ),
+        (
+            BaselineInstruction::Unblock { user_id: 7, user_account_bump_seed: 254, amount: 1000 },
+            UserAccountInstruction::Unblock { user_id: UserId::U32(7), user_account_bump_seed: 254, amount: 1000 }
+        ),
+    ]
+}
+
+#[test]
+fn test_baseline_encoding_is_decoded() {
+    // A `Deposit` of 1000 to the user 7 as sent by the first clients
+    let deposit_data = [1, 7, 0, 0, 0, 254, 232, 3, 0, 0, 0, 0, 0, 0];
+    assert_eq!(
+        UserAccountInstruction::try_from_slice(&deposit_data).unwrap(),
+        UserAccountInstruction::Deposit { user_id: UserId::U32(7), user_account_bump_seed: 254, amount: 1000 }
+    );
+
+    let baseline_instructions = baseline_instructions();
+    assert_eq!(baseline_instructions.len(), LEGACY_INSTRUCTION_COUNT as usize);
+
+    for (baseline_instruction, instruction) in baseline_instructions {
+        let data = baseline_instruction.try_to_vec().unwrap();
+        assert_eq!(data[0], instruction.tag());
+        assert_eq!(UserAccountInstruction::try_from_slice(&data).unwrap(), instruction);
+    }
+}
+
+#[test]
+fn test_versioned_encoding() {
+    let instructions = baseline_instructions()
+        .into_iter()
+        .map(|(_, instruction)| instruction)
+        .chain([
+            UserAccountInstruction::Create { user_id: UserId::U64(1 << 40), user_account_bump_seed: 254 },
+            UserAccountInstruction::CancelSubscription,
+            UserAccountInstruction::SettlePot {
+                winners: vec![],
+                rake: Some(PotRake { house_user_id: UserId::Bytes([7; 32]), house_account_bump_seed: 253, basis_points: 500 })
+            },
+            UserAccountInstruction::InitializeVault { vault_authority_bump_seed: 253 },
+        ]);
+
+    for instruction in instructions {
+        let data = instruction.try_to_vec().unwrap();
+
+        assert_eq!(data[..3], [INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION, instruction.tag()]);
+        assert_eq!(UserAccountInstruction::try_from_slice(&data).unwrap(), instruction);
+    }
+}
+
+#[test]
+fn test_unknown_encoding_error() {
+    for data in [
+        vec![],
+        vec![INSTRUCTION_VERSION_MARKER],
+        vec![INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION + 1, 0, 0, 7, 0, 0, 0, 254],
+        vec![INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION, u8::MAX],
+        // Only the instructions of the first version have a legacy encoding
+        vec![LEGACY_INSTRUCTION_COUNT, 253, 0, 0, 0, 0, 0, 0, 0, 0],
+        // A legacy instruction missing its last field
+        vec![1, 7, 0, 0, 0, 254],
+        // Trailing bytes after the fields
+        vec![INSTRUCTION_VERSION_MARKER, INSTRUCTION_VERSION, 19, 0],
+        vec![0, 7, 0, 0, 0, 254, 0],
+    ] {
+        assert!(UserAccountInstruction::try_from_slice(&data).is_err(), "{:?} is decoded", data);
+    }
+}
@@ -0,0 +1,114 @@
+//! A reference model of the balances of the users, independent of the program,
+//! to check the program against in the differential tests
+
+use std::collections::HashMap;
+
+use emotion_user_account::state::UserId;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    Create { user_id: UserId },
+    Deposit { user_id: UserId, amount: u64 },
+    Withdraw { user_id: UserId, amount: u64 },
+    Transfer { sender_user_id: UserId, recipient_user_id: UserId, amount: u64 },
+    Block { user_id: UserId, amount: u64 },
+    Unblock { user_id: UserId, amount: u64 },
+}
+
+/// The reasons an operation is rejected, in the order the program checks them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModelError {
+    ReservedUserId,
+    AccountAlreadyExists,
+    AccountNotFound,
+    InsufficientAvailableBalance,
+    InsufficientBlockedAmount,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ModelAccount {
+    pub balance: u64,
+    pub blocked_amount: u64,
+}
+
+impl ModelAccount {
+    pub fn available_balance(&self) -> u64 {
+        self.balance - self.blocked_amount
+    }
+}
+
+/// The users of a single token mint and operator, without vesting, credit or rewards
+#[derive(Clone, Debug, Default)]
+pub struct LedgerModel {
+    accounts: HashMap<UserId, ModelAccount>,
+}
+
+impl LedgerModel {
+    pub fn account(&self, user_id: UserId) -> Option<&ModelAccount> {
+        self.accounts.get(&user_id)
+    }
+
+    /// Applies the operation, leaving the model unchanged when it is rejected
+    pub fn apply(&mut self, operation: Operation) -> Result<(), ModelError> {
+        match operation {
+            Operation::Create { user_id } => {
+                if user_id.is_reserved() {
+                    return Err(ModelError::ReservedUserId);
+                }
+
+                if self.accounts.contains_key(&user_id) {
+                    return Err(ModelError::AccountAlreadyExists);
+                }
+
+                self.accounts.insert(user_id, ModelAccount::default());
+            }
+            Operation::Deposit { user_id, amount } => {
+                self.account_mut(user_id)?.balance += amount;
+            }
+            Operation::Withdraw { user_id, amount } => {
+                let account = self.account_mut(user_id)?;
+
+                if account.available_balance() < amount {
+                    return Err(ModelError::InsufficientAvailableBalance);
+                }
+
+                account.balance -= amount;
+            }
+            Operation::Transfer { sender_user_id, recipient_user_id, amount } => {
+                if self.account_mut(sender_user_id)?.available_balance() < amount {
+                    return Err(ModelError::InsufficientAvailableBalance);
+                }
+
+                // The recipient is checked after the sender, as the program does
+                self.account_mut(recipient_user_id)?;
+
+                self.account_mut(sender_user_id)?.balance -= amount;
+                self.account_mut(recipient_user_id)?.balance += amount;
+            }
+            Operation::Block { user_id, amount } => {
+                let account = self.account_mut(user_id)?;
+
+                if account.available_balance() < amount {
+                    return Err(ModelError::InsufficientAvailableBalance);
+                }
+
+                account.blocked_amount += amount;
+            }
+            Operation::Unblock { user_id, amount } => {
+                let account = self.account_mut(user_id)?;
+
+                if account.blocked_amount < amount {
+                    return Err(ModelError::InsufficientBlockedAmount);
+                }
+
+                account.blocked_amount -= amount;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn account_mut(&mut self, user_id: UserId) -> Result<&mut ModelAccount, ModelError> {
+        self.accounts.get_mut(&user_id).ok_or(ModelError::AccountNotFound)
+    }
+}
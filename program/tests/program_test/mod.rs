@@ -8,6 +8,7 @@ use solana_program::{
 use solana_program_test::*;
 
 use solana_sdk::{
+    account::Account,
     program_pack::Pack,
     signature::{Keypair, Signer},
     transaction::Transaction
@@ -20,13 +21,11 @@ use spl_token::{
 
 use emotion_user_account::{
     entrypoint::process_instruction,
-    instruction::{create, deposit},
+    instruction::{create, deposit, initialize_vault, set_config, get_user_account_address, get_vault_authority_address},
+    state::{UserId, CONFIG_SEED},
 };
 
-use spl_associated_token_account::{ 
-    get_associated_token_address,
-    create_associated_token_account
-};
+use spl_associated_token_account::get_associated_token_address;
 
 pub struct UserAccountProgramTest {
     pub program_id: Pubkey,
@@ -35,19 +34,39 @@ pub struct UserAccountProgramTest {
     pub token_mint_key: Option<Pubkey>,
     
     pub user_account_key: Option<Pubkey>,
-    pub user_id: u32,
+    pub user_id: UserId,
     pub user_bump: u8,
     
     pub source_account_key: Option<Pubkey>,
     pub source_token_account_key: Option<Pubkey>,
-    pub source_bump: u8
+    pub source_bump: u8,
+
+    pub config_account_key: Option<Pubkey>,
+    pub config_bump: u8
 }
 
 impl UserAccountProgramTest {
     pub async fn start_new() -> Self {
+        Self::start(false).await
+    }
+
+    /// Same as `start_new`, but always runs the program built by `cargo test-bpf` instead of 
+    /// the native processor, and fails if it was not built
+    pub async fn start_new_bpf() -> Self {
+        Self::start(true).await
+    }
+
+    async fn start(prefer_bpf: bool) -> Self {
         let program_id = Pubkey::from_str("7b4tT3royc2qTcNFKPgryT7gv6K1fKxDDWs1edD4qFht").unwrap();
     
-        let program_test = ProgramTest::new(
+        // The preference must be set before the program is added
+        let mut program_test = ProgramTest::default();
+
+        if prefer_bpf {
+            program_test.prefer_bpf(true);
+        }
+
+        program_test.add_program(
             "emotion_user_account", 
             program_id, 
             processor!(process_instruction)
@@ -62,12 +81,15 @@ impl UserAccountProgramTest {
             token_mint_key: None,
             
             user_account_key: None,
-            user_id: 0,
+            user_id: UserId::U32(0),
             user_bump: 0,
 
             source_account_key: None,
             source_token_account_key: None,
-            source_bump: 0
+            source_bump: 0,
+
+            config_account_key: None,
+            config_bump: 0
         }
     }
 
@@ -106,16 +128,12 @@ impl UserAccountProgramTest {
         return pool_mint.pubkey().clone();
     }
 
-    pub async fn create_user_account(&mut self, user_id: u32, amount: u64) -> (Pubkey, u8) {
-        let seeds = [
-            &user_id.to_le_bytes()[..], 
-            &self.token_mint_key.unwrap().to_bytes(), 
-            &self.context.payer.pubkey().to_bytes(), 
-        ];
-
-        let (user_account_key, user_bump) = Pubkey::find_program_address(
-            &seeds, 
-            &self.program_id);
+    pub async fn create_user_account(&mut self, user_id: UserId, amount: u64) -> (Pubkey, u8) {
+        let (user_account_key, user_bump) = get_user_account_address(
+            &self.program_id, 
+            user_id, 
+            &self.token_mint_key.unwrap(), 
+            &self.context.payer.pubkey());
         
         let mut instructions = vec![
             create(
@@ -154,7 +172,7 @@ impl UserAccountProgramTest {
         return (user_account_key.clone(), user_bump);
     }
 
-    pub async fn with_user(&mut self, user_id: u32, amount: u64) -> (Pubkey, u8) {
+    pub async fn with_user(&mut self, user_id: UserId, amount: u64) -> (Pubkey, u8) {
         let (user_account_key, user_bump) = self.create_user_account(user_id, amount).await;
 
         self.user_id = user_id;
@@ -164,18 +182,49 @@ impl UserAccountProgramTest {
         return (user_account_key.clone(), user_bump);
     }
 
+    /// Stores a user account as the first version of the program created it, 
+    /// with the balance and the blocked amount only
+    pub async fn with_legacy_user(&mut self, user_id: u32, balance: u64, blocked_amount: u64) -> (Pubkey, u8) {
+        let (user_account_key, user_bump) = get_user_account_address(
+            &self.program_id, 
+            UserId::U32(user_id), 
+            &self.token_mint_key.unwrap(), 
+            &self.context.payer.pubkey());
+
+        let data = [balance.to_le_bytes(), blocked_amount.to_le_bytes()].concat();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0
+        };
+        self.context.set_account(&user_account_key, &account.into());
+
+        (user_account_key, user_bump)
+    }
+
+    /// Stores an account of the program with the given data at a new address
+    pub fn with_program_account(&mut self, data: Vec<u8>) -> Pubkey {
+        let account_key = Pubkey::new_unique();
+        let account = Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0
+        };
+        self.context.set_account(&account_key, &account.into());
+
+        account_key
+    }
+
     pub async fn with_source_user(&mut self, mint_amount: u64) -> (Pubkey, Pubkey, u8) {
-        let source_user_id = 0u32;
-        
-        let source_seeds = [
-            &source_user_id.to_le_bytes()[..], 
-            &self.token_mint_key.unwrap().to_bytes(), 
-            &self.context.payer.pubkey().to_bytes(), 
-        ];
-    
-        let (source_account_key, source_bump) = Pubkey::find_program_address(
-            &source_seeds, 
-            &self.program_id);
+        let (source_account_key, source_bump) = get_vault_authority_address(
+            &self.program_id, 
+            &self.token_mint_key.unwrap(), 
+            &self.context.payer.pubkey());
     
         let source_token_account_key = get_associated_token_address(
             &source_account_key, &self.token_mint_key.unwrap()
@@ -183,19 +232,14 @@ impl UserAccountProgramTest {
     
         let create_source_and_mint_transaction = Transaction::new_signed_with_payer(
             &[
-                create(
+                initialize_vault(
                     &self.program_id, 
-                    source_user_id, 
                     source_bump, 
                     &source_account_key,
+                    &source_token_account_key,
                     &self.token_mint_key.unwrap(), 
                     &self.context.payer.pubkey()
                 ).unwrap(),
-                create_associated_token_account(
-                    &self.context.payer.pubkey(),
-                    &source_account_key,
-                    &self.token_mint_key.unwrap()
-                ),
                 mint_to(
                     &spl_token::id(), 
                     &self.token_mint_key.unwrap(), 
@@ -217,4 +261,38 @@ impl UserAccountProgramTest {
 
         return (source_account_key.clone(), source_token_account_key.clone(), source_bump);
     }
+
+    pub async fn with_config(&mut self, withdrawal_wallet_delay: i64) -> (Pubkey, u8) {
+        let config_seeds = [
+            CONFIG_SEED,
+            &self.token_mint_key.unwrap().to_bytes(), 
+            &self.context.payer.pubkey().to_bytes(), 
+        ];
+
+        let (config_account_key, config_bump) = Pubkey::find_program_address(
+            &config_seeds, 
+            &self.program_id);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                set_config(
+                    &self.program_id, 
+                    config_bump, 
+                    withdrawal_wallet_delay,
+                    &config_account_key,
+                    &self.token_mint_key.unwrap(), 
+                    &self.context.payer.pubkey()
+                ).unwrap()
+            ], 
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer],
+            self.context.last_blockhash
+        );
+        self.context.banks_client.process_transaction(transaction).await.unwrap();
+
+        self.config_account_key = Some(config_account_key);
+        self.config_bump = config_bump;
+
+        return (config_account_key.clone(), config_bump);
+    }
 }
\ No newline at end of file